@@ -0,0 +1,113 @@
+//
+//  CustomResourceView.swift
+//  KubeViewerApp
+//
+
+import AlertToast
+import SwiftUI
+
+struct CustomResourceView: View {
+    let windowId: UUID
+    let kindId: CustomResourceKindId
+    @ObservedObject var globalModel: GlobalModel
+    @ObservedObject var mainViewModel: MainViewModel
+    @ObservedObject var model: CustomResourceViewModel
+
+    @State var isLoading: Bool = false
+
+    public init(windowId: UUID, kindId: CustomResourceKindId, globalModel: GlobalModel, mainViewModel: MainViewModel) {
+        self.windowId = windowId
+        self.kindId = kindId
+        self.globalModel = globalModel
+        self.mainViewModel = mainViewModel
+
+        self.model = globalModel.models[windowId]?.customResources ?? CustomResourceViewModel(windowId: windowId)
+
+        if let viewModels = globalModel.models[windowId],
+           viewModels.customResources == nil
+        {
+            $globalModel.models[windowId].wrappedValue!.customResources = self.model
+        }
+    }
+
+    var body: some View {
+        VStack {
+            self.innerBody
+                .onChange(of: self.model.resources, initial: true) { _, resources in
+                    switch resources {
+                    case .loading, .initial: self.isLoading = true
+                    case .loaded, .error: self.isLoading = false
+                    }
+                }
+        }
+        .frame(minWidth: 100)
+        .toast(isPresenting: self.$isLoading) {
+            AlertToast(displayMode: .alert, type: .loading, title: "Loading")
+        }
+        .onDisappear {
+            Task {
+                await self.model.data.stopWatcher()
+            }
+        }
+        // selecting another kind or cluster replaces the loaded objects and the watcher
+        .task(id: [self.kindId, self.mainViewModel.selectedCluster?.id.rawValue]) {
+            if let selectedCluster = self.mainViewModel.selectedCluster {
+                await self.model.data.selectKind(selectedCluster: selectedCluster.id, kindId: self.kindId)
+            }
+        }
+    }
+
+    @ViewBuilder
+    var innerBody: some View {
+        switch self.model.resources {
+        case .loaded(let resources):
+            if let kind = self.model.kind, kind.id == self.kindId {
+                self.DisplayResources(kind: kind, resources: resources)
+            } else {
+                HStack {}
+            }
+
+        case .loading, .initial:
+            HStack {}
+
+        case .error(let error):
+            Text("error: \(error)")
+        }
+    }
+
+    func DisplayResources(kind: CustomResourceKind, resources: [CustomResource]) -> some View {
+        ScrollView([.horizontal, .vertical]) {
+            Grid(alignment: .leading, horizontalSpacing: 16, verticalSpacing: 6) {
+                GridRow {
+                    Text("Name").bold()
+                    if kind.namespaced {
+                        Text("Namespace").bold()
+                    }
+                    ForEach(kind.printerColumns, id: \.name) { column in
+                        Text(column.name).bold()
+                    }
+                }
+
+                Divider()
+
+                ForEach(resources.sorted { $0.id < $1.id }) { resource in
+                    GridRow {
+                        NameView(name: resource.name)
+                        if kind.namespaced {
+                            Text(resource.namespace ?? "")
+                        }
+                        ForEach(Array(resource.cells.enumerated()), id: \.offset) { _, cell in
+                            Text(cell ?? "")
+                        }
+                    }
+                }
+            }
+            .padding()
+        }
+        .toolbar {
+            ToolbarItem(placement: .navigation) {
+                Text(kind.kind).font(.headline)
+            }
+        }
+    }
+}
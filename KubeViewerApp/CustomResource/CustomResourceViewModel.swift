@@ -0,0 +1,49 @@
+//
+//  CustomResourceViewModel.swift
+//  KubeViewerApp
+//
+
+import Combine
+import Foundation
+import SwiftUI
+
+class CustomResourceViewModel: ObservableObject, CustomResourceViewModelCallback {
+    let windowId: UUID
+    var data: RustCustomResourceViewModel
+
+    @Published var kind: CustomResourceKind? = nil
+    @Published var resources: LoadStatus<[CustomResource]> = .initial
+
+    init(windowId: UUID) {
+        self.windowId = windowId
+        self.data = RustCustomResourceViewModel()
+
+        DispatchQueue.main.async { self.setupCallback() }
+    }
+
+    private func setupCallback() {
+        Task {
+            await self.data.initializeModelWithResponder(responder: self)
+        }
+    }
+
+    func callback(message: CustomResourceViewModelMessage) {
+        Task {
+            await MainActor.run {
+                switch message {
+                    case .loading:
+                        self.resources = .loading
+
+                    case let .loadingFailed(error):
+                        self.resources = .error(error: error)
+
+                    case let .loaded(kind: kind, resources: resources):
+                        self.kind = kind
+                        self.resources = .loaded(data: resources)
+                }
+            }
+        }
+    }
+}
+
+extension CustomResource: Identifiable {}
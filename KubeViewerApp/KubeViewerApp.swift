@@ -11,6 +11,7 @@ struct ViewModels {
     var main: MainViewModel
     var nodes: NodeViewModel?
     var pods: PodViewModel?
+    var customResources: CustomResourceViewModel?
 }
 
 class GlobalModel: ObservableObject {
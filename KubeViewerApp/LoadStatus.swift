@@ -13,3 +13,19 @@ enum LoadStatus<T: Equatable>: Equatable {
          error(error: String),
          loaded(data: T)
 }
+
+/// Applies a batch of changes sent by the rust view models to the last loaded list, the inserted
+/// and updated items are placed at their position in the sorted list
+func applyChanges<T: Identifiable>(_ items: [T], inserted: [T], updated: [T], removed: [T.ID], positions: [T.ID: UInt32]) -> [T] {
+    let removed = Set(removed)
+    let changed = (inserted + updated).sorted { (positions[$0.id] ?? .max) < (positions[$1.id] ?? .max) }
+    let changedIds = Set(changed.map(\.id))
+
+    var result = items.filter { !removed.contains($0.id) && !changedIds.contains($0.id) }
+    for item in changed {
+        let position = positions[item.id].map { Int($0) } ?? result.count
+        result.insert(item, at: min(position, result.count))
+    }
+
+    return result
+}
@@ -56,6 +56,12 @@ struct MainView: View {
         .onAppear {
             self.globalModel.windowOpened(self.windowId)
         }
+        // custom resource tabs depend on the CRDs of the selected cluster
+        .task(id: self.model.selectedCluster?.id) {
+            if let selectedCluster = self.model.selectedCluster {
+                await self.model.data.loadCustomResourceTabs(selectedCluster: selectedCluster.id)
+            }
+        }
         .onReceive(NotificationCenter.default.publisher(for: NSWindow.willCloseNotification, object: self.window)) { _ in
             if self.window != nil {
                 DispatchQueue.main.async {
@@ -79,8 +85,14 @@ struct MainView: View {
             PodView(windowId: self.windowId, globalModel: self.globalModel, mainViewModel: self.model)
         case TabId.deployments:
             DeploymentView()
+        case let .customResource(kindId: kindId):
+            CustomResourceView(windowId: self.windowId, kindId: kindId, globalModel: self.globalModel, mainViewModel: self.model)
         default:
-            self.model.tabContentViews[self.model.selectedTab]!
+            if let tabContentView = self.model.tabContentViews[self.model.selectedTab] {
+                tabContentView
+            } else {
+                TabContentView(text: self.model.tabsMap[self.model.selectedTab]?.name ?? "Unknown tab")
+            }
             Text(self.model.windowId.uuidString)
         }
     }
@@ -96,7 +108,7 @@ struct MainView: View {
                         .padding(.horizontal, 12)
                         .id(FocusRegion.sidebarSearch)
 
-                    ForEach(self.model.filteredTabGroups) { tabGroup in
+                    ForEach(self.model.tabGroups) { tabGroup in
                         DisclosureGroup(isExpanded: self.$model.tabGroupExpansions[tabGroup.id] ?? true) {
                             VStack {
                                 if self.windowIsLoaded {
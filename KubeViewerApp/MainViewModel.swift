@@ -14,12 +14,15 @@ class MainViewModel: ObservableObject {
     private var listener: Listener?
     let data: RustMainViewModel
     let tabs: [Tab]
-    let tabsMap: [TabId: Tab]
 
     @Published var selectedMainTab: NSWindow?
+    @Published var tabsMap: [TabId: Tab]
+    @Published var tabGroups: [TabGroup]
     @Published var tabContentViews: [TabId: TabContentView]
     @Published var tabViewModels: [TabId: TabViewModel]
-    @Published var search: String
+    @Published var search: String {
+        didSet { self.tabGroups = self.data.tabGroupsFiltered(search: self.search) }
+    }
 
     @RustPublished var tabGroupExpansions: [TabGroupId: Bool]
     @RustPublished var selectedTab: TabId
@@ -30,12 +33,14 @@ class MainViewModel: ObservableObject {
         self.windowId = windowId
         self.data = RustMainViewModel(windowId: windowId.uuidString)
         self.tabs = self.data.tabs()
-        self.tabsMap = self.data.tabsMap()
+        let tabsMap = self.data.tabsMap()
+        self.tabsMap = tabsMap
+        self.tabGroups = self.data.tabGroupsFiltered(search: "")
         self.search = ""
         self.listener = nil
 
-        self.tabContentViews = self.tabsMap.mapValues { tab in TabContentView(text: tab.name) }
-        self.tabViewModels = self.tabsMap.mapValues { _ in TabViewModel() }
+        self.tabContentViews = tabsMap.mapValues { tab in TabContentView(text: tab.name) }
+        self.tabViewModels = tabsMap.mapValues { _ in TabViewModel() }
 
         self.tabGroupExpansions = self.data.tabGroupExpansions()
         self._tabGroupExpansions.getter = self.data.tabGroupExpansions
@@ -60,10 +65,6 @@ class MainViewModel: ObservableObject {
         DispatchQueue.main.async { self.setupListener() }
     }
 
-    var filteredTabGroups: [TabGroup] {
-        self.data.tabGroupsFiltered(search: self.search)
-    }
-
     private func setupListener() {
         if self.listener == nil {
             self.listener = Listener(callback: self.receiveListenerUpdate)
@@ -81,6 +82,12 @@ class MainViewModel: ObservableObject {
                         self.selectedTab = tabId
                     case let .tabGroupExpansions(expansions: expansions):
                         self.tabGroupExpansions = expansions
+                    case let .tabGroups(tabGroups: tabGroups):
+                        // custom resource tabs come and go with the selected cluster
+                        self.tabGroups = tabGroups
+                        self.tabsMap = self.data.tabsMap()
+                        self.tabGroupExpansions = self.data.tabGroupExpansions()
+                        self.selectedTab = self.data.selectedTab()
                 }
             }
         }
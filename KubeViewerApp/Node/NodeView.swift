@@ -18,7 +18,7 @@ struct NodeView: View {
     @State var isLoading: Bool = false
     @State var nodes: [Node] = []
 
-    @State private var sortOrder = [KeyPathComparator(\Node.name)]
+    @State private var sortOrder: [KeyPathComparator<Node>] = []
     @State private var selectedNodes = Set<Node.ID>()
 
     @State var detailsWidth: CGFloat = 300
@@ -107,18 +107,15 @@ struct NodeView: View {
                         { Text($0.kubeletVersion ?? "") }
                     TableColumn("Taints", value: \.taints, comparator: CountComparator())
                         { TaintView(taints: $0.taints) }
-                    TableColumn("Age", value: \.createdAt, comparator: OptionalAgeComparator()) {
+                    TableColumn("Age", value: \.createdAt, comparator: OptionalIntComparator()) {
                         AgeView(createdAt: $0.createdAt, age: $0.age)
                     }
-                    TableColumn("Conditions", value: \.conditions, comparator: ConditionsComparator())
+                    TableColumn("Conditions", value: \.conditions, comparator: CountComparator())
                         { self.ConditionsColumnContent($0) }
                 }
-                .onChange(of: self.sortOrder) { sortOrder, _ in
-                    switch sortOrder {
-                    case [KeyPathComparator(\Node.name)]: ()
-                    case [KeyPathComparator(\Node.createdAt)]: ()
-                    case let keyPath: self.nodes.sort(using: keyPath)
-                    }
+                .onChange(of: self.sortOrder) {
+                    // sorted in rust, the next loaded nodes come back in the new order
+                    self.model.data.setSort(keys: self.sortKeys(self.sortOrder))
                 }
                 .toolbar {
                     ToolbarItem(placement: .navigation) {
@@ -156,6 +153,20 @@ struct NodeView: View {
         }
     }
 
+    func sortKeys(_ sortOrder: [KeyPathComparator<Node>]) -> [SortKey] {
+        let fields: [PartialKeyPath<Node>: String] = [
+            \Node.name: "name",
+            \Node.kubeletVersion: "version",
+            \Node.taints: "taints",
+            \Node.createdAt: "age",
+            \Node.conditions: "conditions",
+        ]
+
+        return sortOrder.compactMap { comparator in
+            fields[comparator.keyPath].map { SortKey(field: $0, descending: comparator.order == .reverse) }
+        }
+    }
+
     func setLoading(_ loading: LoadStatus<[Node]>, _ _old: LoadStatus<[Node]>) {
         switch loading {
         case .loaded, .error:
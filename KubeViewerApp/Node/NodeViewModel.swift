@@ -14,6 +14,9 @@ class NodeViewModel: ObservableObject, NodeViewModelCallback {
     var data: RustNodeViewModel
 
     @Published var nodes: LoadStatus<[Node]> = .initial
+    @Published var connection: ConnectionState = .connected
+    @Published var searchError: String? = nil
+    @Published var drainProgress: [NodeId: [PodId: DrainPodProgress]] = [:]
 
     init(windowId: UUID, selectedCluster: Cluster?) {
         self.windowId = windowId
@@ -41,6 +44,30 @@ class NodeViewModel: ObservableObject, NodeViewModelCallback {
                     case let .loaded(nodes: nodes):
                         print("[swift] nodes loaded")
                         self.nodes = .loaded(data: nodes)
+
+                    case let .changed(inserted: inserted, updated: updated, removed: removed, positions: positions):
+                        guard case let .loaded(data: nodes) = self.nodes else { return }
+                        self.nodes = .loaded(data: applyChanges(nodes, inserted: inserted, updated: updated, removed: removed, positions: positions))
+
+                    case let .searchError(error: error):
+                        self.searchError = error
+
+                    case let .connectionChanged(state: state):
+                        self.connection = state
+
+                    case let .drainProgress(nodeId: nodeId, progress: progress):
+                        self.drainProgress[nodeId, default: [:]][progress.podId] = progress
+
+                    case let .drainFinished(nodeId: nodeId, error: error):
+                        if let error = error {
+                            print("[swift] drain of \(nodeId.rawValue) failed: \(error)")
+                        }
+
+                    case let .toastWarningMessage(message: message):
+                        print("[swift] node warning: \(message)")
+
+                    case let .toastErrorMessage(message: message):
+                        print("[swift] node error: \(message)")
                 }
             }
         }
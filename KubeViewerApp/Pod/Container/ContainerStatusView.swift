@@ -11,12 +11,14 @@ struct ContainerStatusView: View {
     let container: Container
 
     var stateColor: Color {
-        switch container.state {
-        case .some(.running(data: _)):
+        switch container.health.status {
+        case .healthy:
             return Color.green
-        case .some(.terminated(data: _)):
+        case .completed:
             return Color.gray
-        case .none, .some(.waiting(data: _)):
+        case .crashLoopBackOff, .imagePullBackOff, .oomKilled, .error:
+            return Color.red
+        case .unknown, .waiting, .starting, .notReady:
             return Color.orange
         }
     }
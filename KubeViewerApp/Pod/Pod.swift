@@ -12,6 +12,10 @@ extension Pod: Identifiable, CreatedAt, AgeTimestamp {
         self.containers.map { c in c.restartCount }.reduce(0, +)
     }
 
+    var restarts: Int32 {
+        self.totalRestarts()
+    }
+
     var logCmd: String {
         podLogCmd(namespace: self.namespace, podId: self.id)
     }
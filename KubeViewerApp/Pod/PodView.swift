@@ -19,7 +19,7 @@ struct PodView: View {
     @State private var isLoading: Bool = false
     @State private var pods: [Pod] = []
 
-    @State private var sortOrder = [KeyPathComparator(\Pod.name)]
+    @State private var sortOrder: [KeyPathComparator<Pod>] = []
     @State private var selectedPods = Set<Pod.ID>()
 
     @State private var detailsWidth: CGFloat = 300
@@ -152,13 +152,13 @@ struct PodView: View {
                     TableColumn("Containers", value: \.containers, comparator: CountComparator()) { pod in
                         ContainerView(containers: pod.containers)
                     }
-                    TableColumn("Restarts", value: \.containers, comparator: RestartComparator()) { pod in
+                    TableColumn("Restarts", value: \.restarts) { pod in
                         Text(String(pod.totalRestarts()))
                     }
                     TableColumn("QoS", value: \.qosClass, comparator: OptionalStringComparator()) { pod in
                         Text(pod.qosClass ?? "Unknown")
                     }
-                    TableColumn("Age", value: \.createdAt, comparator: OptionalAgeComparator()) {
+                    TableColumn("Age", value: \.createdAt, comparator: OptionalIntComparator()) {
                         AgeView(createdAt: $0.createdAt, age: $0.age)
                     }
                     TableColumn("Status", value: \.phase, comparator: RawValueComparator()) { pod in
@@ -214,11 +214,9 @@ struct PodView: View {
                             }
                     }
                 }
-                .onChange(of: self.pods) {
-                    self.pods.sort(using: self.sortOrder)
-                }
-                .onChange(of: self.sortOrder) { sortOrder, _ in
-                    self.pods.sort(using: sortOrder)
+                .onChange(of: self.sortOrder) {
+                    // sorted in rust, the next loaded pods come back in the new order
+                    self.model.data.setSort(keys: self.sortKeys(self.sortOrder))
                 }
                 .toolbar {
                     ToolbarItem(placement: .navigation) {
@@ -296,12 +294,27 @@ struct PodView: View {
         }
     }
 
+    func sortKeys(_ sortOrder: [KeyPathComparator<Pod>]) -> [SortKey] {
+        let fields: [PartialKeyPath<Pod>: String] = [
+            \Pod.name: "name",
+            \Pod.namespace: "namespace",
+            \Pod.containers: "containers",
+            \Pod.restarts: "restarts",
+            \Pod.qosClass: "qos",
+            \Pod.createdAt: "age",
+            \Pod.phase: "phase",
+        ]
+
+        return sortOrder.compactMap { comparator in
+            fields[comparator.keyPath].map { SortKey(field: $0, descending: comparator.order == .reverse) }
+        }
+    }
+
     func setLoading(_ loading: LoadStatus<[Pod]>, _ _old: LoadStatus<[Pod]>) {
         switch loading {
         case .loaded, .error:
             self.isLoading = false
-            if case .loaded(var pods) = self.model.pods {
-                pods.sort(using: self.sortOrder)
+            if case .loaded(let pods) = self.model.pods {
                 self.pods = pods
             }
         case .initial:
@@ -13,6 +13,10 @@ import SwiftUI
     let data: RustPodViewModel
 
     var pods: LoadStatus<[Pod]> = .initial
+    var loadingProgress: ListProgress? = nil
+    var connection: ConnectionState = .connected
+    var searchError: String? = nil
+    var highlights: [PodId: FuzzyMatch] = [:]
 
     var toastWarning: String? = nil
     var toastError: String? = nil
@@ -24,6 +28,8 @@ import SwiftUI
 
     func getDataAndSetupWatcher(_ selectedCluster: ClusterId) async {
         await self.data.initializeModelWithResponder(responder: self)
+        // names show while the full pods are listed, on large clusters that takes a while
+        self.data.setMetadataFirst(metadataFirst: true)
         await self.data.fetchPods(selectedCluster: selectedCluster)
         await self.data.startWatcher(selectedCluster: selectedCluster)
     }
@@ -45,16 +51,34 @@ import SwiftUI
         Task {
             await MainActor.run {
                 switch message {
-                    case .loading:
-                        self.pods = .loading
+                    case let .loading(progress: progress):
+                        // pages listed so far are sent as loaded, keep showing them
+                        self.loadingProgress = progress
+                        if progress == nil {
+                            self.pods = .loading
+                        }
 
                     case let .loadingFailed(error):
                         self.pods = .error(error: error)
 
-                    case let .loaded(pods: pods):
+                    case let .loaded(pods: pods, highlights: highlights):
                         print("[swift] pods loaded")
+                        self.loadingProgress = nil
+                        self.highlights = highlights
                         self.pods = .loaded(data: pods)
 
+                    case let .changed(inserted: inserted, updated: updated, removed: removed, highlights: highlights, positions: positions):
+                        guard case let .loaded(data: pods) = self.pods else { return }
+                        for id in removed { self.highlights.removeValue(forKey: id) }
+                        self.highlights.merge(highlights) { _, new in new }
+                        self.pods = .loaded(data: applyChanges(pods, inserted: inserted, updated: updated, removed: removed, positions: positions))
+
+                    case let .searchError(error: error):
+                        self.searchError = error
+
+                    case let .connectionChanged(state: state):
+                        self.connection = state
+
                     case let .toastWarningMessage(message: message):
                         self.toastWarning = message
 
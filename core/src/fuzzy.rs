@@ -0,0 +1,217 @@
+//! Fuzzy matching for the search boxes, every character of the pattern has to appear in order in
+//! the text, matches on word boundaries and runs of consecutive characters score higher
+
+use uniffi::Record;
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 12;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP: i64 = 1;
+
+/// Range of matched characters, in characters not bytes, the end is exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Record)]
+pub struct MatchRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Record)]
+pub struct FuzzyMatch {
+    /// Higher is more relevant, only comparable between matches of the same pattern
+    pub score: i64,
+    pub ranges: Vec<MatchRange>,
+}
+
+impl FuzzyMatch {
+    /// Adds the score and ranges of a match on the same text
+    pub fn merge(&mut self, other: FuzzyMatch) {
+        self.score += other.score;
+        self.ranges.extend(other.ranges);
+        self.ranges.sort_by_key(|range| range.start);
+
+        let mut merged: Vec<MatchRange> = Vec::with_capacity(self.ranges.len());
+        for range in self.ranges.drain(..) {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        self.ranges = merged;
+    }
+}
+
+/// Case insensitive fuzzy match of the pattern in the text, `None` if the text doesn't contain
+/// every character of the pattern in order
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    if pattern.is_empty() {
+        return Some(FuzzyMatch::default());
+    }
+
+    let lower = text
+        .iter()
+        .map(|char| char.to_lowercase().next().unwrap_or(*char))
+        .collect::<Vec<_>>();
+
+    // find where the first full match ends, then walk back from there to find the shortest match
+    // ending at that position, so `pool` in `p-pool` matches the second `p`
+    let mut pattern_index = 0;
+    let mut end = 0;
+    for (index, char) in lower.iter().enumerate() {
+        if *char == pattern[pattern_index] {
+            pattern_index += 1;
+
+            if pattern_index == pattern.len() {
+                end = index;
+                break;
+            }
+        }
+    }
+
+    if pattern_index < pattern.len() {
+        return None;
+    }
+
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut pattern_index = pattern.len();
+    for index in (0..=end).rev() {
+        if lower[index] == pattern[pattern_index - 1] {
+            positions.push(index);
+            pattern_index -= 1;
+
+            if pattern_index == 0 {
+                break;
+            }
+        }
+    }
+    positions.reverse();
+
+    Some(FuzzyMatch {
+        score: score(&text, &positions),
+        ranges: ranges(&positions),
+    })
+}
+
+fn score(text: &[char], positions: &[usize]) -> i64 {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+
+    for &position in positions {
+        score += SCORE_MATCH;
+
+        if is_boundary(text, position) {
+            score += BONUS_BOUNDARY;
+        }
+
+        match previous {
+            None if position == 0 => score += BONUS_FIRST_CHAR,
+            Some(previous) if position == previous + 1 => score += BONUS_CONSECUTIVE,
+            Some(previous) => {
+                let gap = (position - previous - 1) as i64;
+                score -= PENALTY_GAP_START + gap * PENALTY_GAP;
+            }
+            None => {}
+        }
+
+        previous = Some(position);
+    }
+
+    score
+}
+
+/// Start of the text, after a separator or the upper case start of a camel case word
+fn is_boundary(text: &[char], position: usize) -> bool {
+    let Some(before) = position.checked_sub(1).map(|index| text[index]) else {
+        return true;
+    };
+
+    let char = text[position];
+    !before.is_alphanumeric() || (before.is_lowercase() && char.is_uppercase())
+}
+
+/// Joins consecutive positions into ranges
+fn ranges(positions: &[usize]) -> Vec<MatchRange> {
+    let mut ranges: Vec<MatchRange> = vec![];
+
+    for &position in positions {
+        let position = position as u32;
+
+        match ranges.last_mut() {
+            Some(range) if range.end == position => range.end += 1,
+            _ => ranges.push(MatchRange {
+                start: position,
+                end: position + 1,
+            }),
+        }
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32) -> MatchRange {
+        MatchRange { start, end }
+    }
+
+    #[test]
+    fn matches_across_separators() {
+        let found = fuzzy_match("pgpool", "pg-pool-7f9c").unwrap();
+        assert_eq!(found.ranges, vec![range(0, 2), range(3, 7)]);
+    }
+
+    #[test]
+    fn ignores_case() {
+        let found = fuzzy_match("RS", "ReplicaSets").unwrap();
+        assert_eq!(found.ranges, vec![range(0, 1), range(7, 8)]);
+    }
+
+    #[test]
+    fn requires_every_character_in_order() {
+        assert_eq!(fuzzy_match("loop", "pg-pool"), None);
+        assert_eq!(fuzzy_match("pools", "pg-pool"), None);
+    }
+
+    #[test]
+    fn empty_pattern_matches_without_ranges() {
+        assert_eq!(fuzzy_match("", "pg-pool"), Some(FuzzyMatch::default()));
+    }
+
+    #[test]
+    fn prefers_the_shortest_match() {
+        let found = fuzzy_match("pool", "p-pool").unwrap();
+        assert_eq!(found.ranges, vec![range(2, 6)]);
+    }
+
+    #[test]
+    fn ranks_consecutive_and_boundary_matches_higher() {
+        let score = |text| fuzzy_match("pool", text).unwrap().score;
+
+        assert!(score("pool-7f9c") > score("pg-pool-7f9c"));
+        assert!(score("pg-pool-7f9c") > score("pg-p-o-o-l"));
+        assert!(score("pg-p-o-o-l") > score("apxoxoxl"));
+    }
+
+    #[test]
+    fn merges_overlapping_ranges() {
+        let mut found = FuzzyMatch {
+            score: 1,
+            ranges: vec![range(0, 2), range(6, 8)],
+        };
+
+        found.merge(FuzzyMatch {
+            score: 2,
+            ranges: vec![range(1, 4)],
+        });
+
+        assert_eq!(found.score, 3);
+        assert_eq!(found.ranges, vec![range(0, 4), range(6, 8)]);
+    }
+}
@@ -1,6 +1,25 @@
+pub mod cache;
 pub mod client_store;
+pub mod container_health;
 pub mod core;
+pub mod custom_resource;
+pub mod diagnosis;
+pub mod drain;
+pub mod helm;
+pub mod horizontal_pod_autoscaler;
 pub mod kube_config;
+pub mod lease;
+pub mod limit_range;
+pub mod manifest;
+pub mod metrics;
 pub mod node;
+pub mod node_edit;
 pub mod pod;
+pub mod pod_disruption_budget;
+pub mod pod_spec;
 pub mod pod_uniffi;
+pub mod priority_class;
+pub mod quantity;
+pub mod resource;
+pub mod resource_quota;
+pub mod runtime_class;
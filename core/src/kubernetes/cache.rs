@@ -0,0 +1,751 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use backoff::backoff::Backoff;
+use chrono::Utc;
+use eyre::Result;
+use futures::{FutureExt, StreamExt};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::{
+    api::WatchEvent,
+    runtime::{
+        reflector::{store::Writer, Store},
+        watcher::{self, DefaultBackoff},
+    },
+    Api, Client, Resource, ResourceExt,
+};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use crate::{cluster::ClusterId, query::Selectors, task};
+
+use super::resource::{
+    self, KubeResource, ListProgress, RecordHistory, RecordId, ResourceRecord as _, PAGE_SIZE,
+};
+
+/// Changes buffered for each subscriber, a subscriber that falls further behind reloads from the
+/// store instead
+const CHANGES_CAPACITY: usize = 1024;
+
+/// Retrying for longer than this without a successful event reports the cache as disconnected
+const DISCONNECTED_AFTER: Duration = Duration::from_secs(60);
+
+/// Wait before retrying when the backoff gives up, it doesn't with the default settings
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Status codes of a list the api server will reject again, retrying doesn't help
+const REJECTED_CODES: [u16; 3] = [400, 403, 404];
+
+/// Cluster, type, `<api version>/<plural>` of the kind and selectors of a running cache, the kind
+/// tells apart the custom resources which all share the `DynamicObject` type
+type CacheKey = (ClusterId, TypeId, String, Selectors);
+
+type HistoryById<K> = HashMap<RecordId<K>, RecordHistory<K>>;
+
+/// Running caches, holds `Weak<ResourceCache<K>>` so a cache is stopped once its last subscriber
+/// drops it
+static CACHES: Lazy<Mutex<HashMap<CacheKey, Box<dyn Any + Send + Sync>>>> =
+    Lazy::new(Default::default);
+
+/// A kind the [`ResourceCache`] can list and watch, every [`KubeResource`] is one through its
+/// records, other kinds are cached as objects only, ex: helm release secrets, custom resources
+pub trait CachedResource:
+    Resource + Clone + DeserializeOwned + Debug + Send + Sync + 'static
+{
+    /// What the cache observed on its objects since it started, `()` for kinds without history
+    type History: Default + Send + 'static;
+
+    /// Name of the kind in logs, ex: `pod`
+    fn name(kind: &Self::DynamicType) -> String {
+        Self::kind(kind).to_lowercase()
+    }
+
+    /// Records the change in the history, called for every change applied to the store
+    fn observe(_history: &mut Self::History, _event: &watcher::Event<Self>, _observed_at: i64) {}
+
+    /// An object with only the metadata set, served while the full objects are listed, `None`
+    /// when the kind can't be listed metadata first
+    fn from_metadata(_metadata: ObjectMeta) -> Option<Self> {
+        None
+    }
+}
+
+impl<K: KubeResource> CachedResource for K {
+    type History = HistoryById<K>;
+
+    fn name(_kind: &()) -> String {
+        K::NAME.to_string()
+    }
+
+    fn from_metadata(metadata: ObjectMeta) -> Option<Self> {
+        let mut resource = K::default();
+        *resource.meta_mut() = metadata;
+        Some(resource)
+    }
+
+    fn observe(history: &mut HistoryById<K>, event: &watcher::Event<K>, observed_at: i64) {
+        match event {
+            watcher::Event::Applied(resource) => {
+                observed(history, resource, observed_at);
+            }
+            watcher::Event::Deleted(resource) => {
+                history.remove(K::clone(resource).into_record().id());
+            }
+            watcher::Event::Restarted(resources) => {
+                let ids = resources
+                    .iter()
+                    .map(|resource| observed(history, resource, observed_at).id().clone())
+                    .collect::<HashSet<_>>();
+
+                history.retain(|id, _| ids.contains(id));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Change<K> {
+    /// A page of the initial list was added to the records, the store is ready after the
+    /// following `Restarted`
+    Listed(ListProgress),
+
+    Applied(Arc<K>),
+    Deleted(Arc<K>),
+
+    /// The list completed and the store was replaced, subscribers should reload from it
+    Restarted,
+
+    Connection(ConnectionState),
+}
+
+/// Whether the cache is receiving changes, the records are stale while it isn't
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum ConnectionState {
+    Connected,
+
+    /// The watch failed and is being retried with backoff
+    Reconnecting {
+        error: String,
+    },
+
+    /// Retrying has been failing for a while, or the api server rejected the list and it isn't
+    /// retried
+    Disconnected {
+        error: String,
+    },
+}
+
+/// A reflector store of one kind on one cluster, shared by every window and view model showing it
+/// with the same selectors
+pub struct ResourceCache<K: CachedResource>
+where
+    K::DynamicType: Clone + Eq + Hash,
+{
+    cluster_id: ClusterId,
+    kind: K::DynamicType,
+    selectors: Selectors,
+    name: String,
+    store: Store<K>,
+    changes: broadcast::Sender<Change<K>>,
+    connection: Arc<Mutex<ConnectionState>>,
+    listing: Arc<Mutex<Listing<K>>>,
+
+    /// What was observed on each object since the cache started, kept here so every window sees
+    /// the same history whenever it was opened
+    history: Arc<Mutex<K::History>>,
+
+    reflector: JoinHandle<()>,
+}
+
+/// Namespace and name of a listed object
+type ObjectKey = (Option<String>, String);
+
+/// Objects of the initial list received so far, served until the store is ready, the full
+/// objects replace the ones listed metadata first as they arrive
+struct Listing<K> {
+    objects: HashMap<ObjectKey, Arc<K>>,
+    progress: Option<ListProgress>,
+}
+
+impl<K> Default for Listing<K> {
+    fn default() -> Self {
+        Self {
+            objects: HashMap::new(),
+            progress: None,
+        }
+    }
+}
+
+impl<K: Resource> Listing<K> {
+    fn extend(&mut self, objects: impl IntoIterator<Item = K>, progress: ListProgress) {
+        self.objects.extend(
+            objects
+                .into_iter()
+                .map(|object| ((object.namespace(), object.name_any()), Arc::new(object))),
+        );
+
+        self.progress = Some(progress);
+    }
+}
+
+impl<K: CachedResource> ResourceCache<K>
+where
+    K::DynamicType: Clone + Eq + Hash + Default,
+{
+    /// Returns the running cache for the cluster, starting one if no one is subscribed to it yet
+    pub fn get_or_start(
+        cluster_id: &ClusterId,
+        selectors: &Selectors,
+        client: Client,
+    ) -> Arc<Self> {
+        Self::get_or_start_with(cluster_id, &Default::default(), selectors, client)
+    }
+
+    /// Like [`ResourceCache::get_or_start`], a cache started by this call lists only the metadata
+    /// first, so names and namespaces show right away on large clusters while the full objects
+    /// are listed
+    pub fn get_or_start_metadata_first(
+        cluster_id: &ClusterId,
+        selectors: &Selectors,
+        client: Client,
+    ) -> Arc<Self> {
+        Self::get_or_start_inner(cluster_id, &Default::default(), selectors, client, true)
+    }
+
+    /// Returns the running cache for the cluster without starting one, for one-off reads that
+    /// can list less than the whole kind when no one is watching it
+    pub fn get(cluster_id: &ClusterId, selectors: &Selectors) -> Option<Arc<Self>> {
+        Self::get_with(cluster_id, &Default::default(), selectors)
+    }
+}
+
+impl<K: CachedResource> ResourceCache<K>
+where
+    K::DynamicType: Clone + Eq + Hash,
+{
+    /// Like [`ResourceCache::get_or_start`] for kinds only known at runtime, ex: custom resources
+    pub fn get_or_start_with(
+        cluster_id: &ClusterId,
+        kind: &K::DynamicType,
+        selectors: &Selectors,
+        client: Client,
+    ) -> Arc<Self> {
+        Self::get_or_start_inner(cluster_id, kind, selectors, client, false)
+    }
+
+    fn get_or_start_inner(
+        cluster_id: &ClusterId,
+        kind: &K::DynamicType,
+        selectors: &Selectors,
+        client: Client,
+        metadata_first: bool,
+    ) -> Arc<Self> {
+        let key = Self::key(cluster_id, kind, selectors);
+        let mut caches = CACHES.lock();
+
+        if let Some(cache) = Self::running(&caches, &key) {
+            debug!("reusing {} cache for {:?}", K::name(kind), cluster_id);
+            return cache;
+        }
+
+        debug!(
+            "starting {} cache for {cluster_id:?} {selectors:?}",
+            K::name(kind)
+        );
+        let cache = Arc::new(Self::start(
+            cluster_id.clone(),
+            kind.clone(),
+            selectors.clone(),
+            client,
+            metadata_first,
+        ));
+        caches.insert(key, Box::new(Arc::downgrade(&cache)));
+
+        cache
+    }
+
+    /// Like [`ResourceCache::get`] for kinds only known at runtime
+    pub fn get_with(
+        cluster_id: &ClusterId,
+        kind: &K::DynamicType,
+        selectors: &Selectors,
+    ) -> Option<Arc<Self>> {
+        Self::running(&CACHES.lock(), &Self::key(cluster_id, kind, selectors))
+    }
+
+    fn key(cluster_id: &ClusterId, kind: &K::DynamicType, selectors: &Selectors) -> CacheKey {
+        (
+            cluster_id.clone(),
+            TypeId::of::<K>(),
+            format!("{}/{}", K::api_version(kind), K::plural(kind)),
+            selectors.clone(),
+        )
+    }
+
+    fn running(
+        caches: &HashMap<CacheKey, Box<dyn Any + Send + Sync>>,
+        key: &CacheKey,
+    ) -> Option<Arc<Self>> {
+        caches
+            .get(key)
+            .and_then(|cache| cache.downcast_ref::<Weak<Self>>())
+            .and_then(Weak::upgrade)
+    }
+
+    fn start(
+        cluster_id: ClusterId,
+        kind: K::DynamicType,
+        selectors: Selectors,
+        client: Client,
+        metadata_first: bool,
+    ) -> Self {
+        let name = K::name(&kind);
+        let writer = Writer::new(kind.clone());
+        let store = writer.as_reader();
+        let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+        let connection = Arc::new(Mutex::new(ConnectionState::Connected));
+        let listing = Arc::new(Mutex::new(Listing::default()));
+        let history = Arc::new(Mutex::new(K::History::default()));
+
+        let reflector = Reflector {
+            api: Api::all_with(client, &kind),
+            name: name.clone(),
+            selectors: selectors.clone(),
+            writer,
+            changes: changes.clone(),
+            connection: connection.clone(),
+            listing: listing.clone(),
+            history: history.clone(),
+            metadata_first,
+            listed: false,
+            failing_since: None,
+            backoff: DefaultBackoff::default(),
+        };
+
+        let reflector = task::spawn(reflector.run());
+
+        Self {
+            cluster_id,
+            kind,
+            selectors,
+            name,
+            store,
+            changes,
+            connection,
+            listing,
+            history,
+            reflector,
+        }
+    }
+
+    pub fn cluster_id(&self) -> &ClusterId {
+        &self.cluster_id
+    }
+
+    pub fn kind(&self) -> &K::DynamicType {
+        &self.kind
+    }
+
+    pub fn selectors(&self) -> &Selectors {
+        &self.selectors
+    }
+
+    /// Waits for the initial list to be in the store
+    pub async fn ready(&self) -> Result<()> {
+        self.store.wait_until_ready().await?;
+        Ok(())
+    }
+
+    /// Whether the initial list is already in the store
+    pub fn is_ready(&self) -> bool {
+        matches!(self.store.wait_until_ready().now_or_never(), Some(Ok(())))
+    }
+
+    pub fn connection(&self) -> ConnectionState {
+        self.connection.lock().clone()
+    }
+
+    /// Changes applied to the store after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<Change<K>> {
+        self.changes.subscribe()
+    }
+
+    /// Calls `on_change` after records were added, changed or removed, changes in the following
+    /// `interval` are collected into the same call. Runs until the task is aborted or the cache
+    /// stops, for view models computed from another kind, ex: the pods a budget covers
+    pub fn notify_changes(
+        &self,
+        interval: Duration,
+        on_change: impl Fn() + Send + 'static,
+    ) -> JoinHandle<()> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut changes = self.subscribe();
+
+        task::spawn(async move {
+            loop {
+                match changes.recv().await {
+                    Ok(Change::Listed(_) | Change::Connection(_)) => continue,
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return,
+                }
+
+                tokio::time::sleep(interval).await;
+
+                // the changes buffered while waiting are part of this call
+                changes = changes.resubscribe();
+                on_change();
+            }
+        })
+    }
+
+    /// Progress of the initial list, `None` once the store is ready or before the first page
+    pub fn progress(&self) -> Option<ListProgress> {
+        self.listing.lock().progress
+    }
+
+    /// Everything currently in the store, or listed so far while it isn't ready
+    pub fn objects(&self) -> Vec<Arc<K>> {
+        if self.is_ready() {
+            self.store.state()
+        } else {
+            self.listing.lock().objects.values().cloned().collect()
+        }
+    }
+}
+
+impl<K: KubeResource> ResourceCache<K> {
+    /// Records for everything currently in the store, or listed so far while it isn't ready
+    pub fn records(&self) -> HashMap<RecordId<K>, K::Record> {
+        let resources = self.objects();
+        let observed_at = Utc::now().timestamp();
+        let mut history = self.history.lock();
+
+        resources
+            .iter()
+            .map(|resource| observed(&mut history, resource, observed_at))
+            .map(|record| (record.id().clone(), record))
+            .collect()
+    }
+
+    /// The record of the resource, with the fields derived from its history as of now
+    pub fn record(&self, resource: &K) -> K::Record {
+        observed(&mut self.history.lock(), resource, Utc::now().timestamp())
+    }
+
+    /// What the cache observed on the object since it started, `None` if it never saw it
+    pub fn history(&self, id: &RecordId<K>) -> Option<RecordHistory<K>> {
+        self.history.lock().get(id).cloned()
+    }
+}
+
+impl<K: CachedResource> Drop for ResourceCache<K>
+where
+    K::DynamicType: Clone + Eq + Hash,
+{
+    fn drop(&mut self) {
+        debug!("stopping {} cache for {:?}", self.name, self.cluster_id);
+        self.reflector.abort();
+
+        // a new cache may have been started for the same key after this one lost its last
+        // subscriber, only remove the entry if it still points at this one
+        let key = Self::key(&self.cluster_id, &self.kind, &self.selectors);
+        let mut caches = CACHES.lock();
+
+        let stopped = caches
+            .get(&key)
+            .and_then(|cache| cache.downcast_ref::<Weak<Self>>())
+            .map_or(false, |cache| cache.strong_count() == 0);
+
+        if stopped {
+            caches.remove(&key);
+        }
+    }
+}
+
+/// Lists a page at a time then watches from the resource version of the list, instead of kube's
+/// watcher which only reports the initial list once every page is in
+struct Reflector<K: CachedResource>
+where
+    K::DynamicType: Clone + Eq + Hash,
+{
+    api: Api<K>,
+    name: String,
+    selectors: Selectors,
+    writer: Writer<K>,
+    changes: broadcast::Sender<Change<K>>,
+    connection: Arc<Mutex<ConnectionState>>,
+    listing: Arc<Mutex<Listing<K>>>,
+    history: Arc<Mutex<K::History>>,
+
+    /// Whether the initial list first lists only the metadata
+    metadata_first: bool,
+
+    /// Whether the initial list is in the store, later lists only replace it once complete
+    listed: bool,
+
+    failing_since: Option<Instant>,
+    backoff: DefaultBackoff,
+}
+
+impl<K: CachedResource> Reflector<K>
+where
+    K::DynamicType: Clone + Eq + Hash,
+{
+    async fn run(mut self) {
+        let mut resource_version = None;
+
+        loop {
+            let version = match resource_version.take() {
+                Some(version) => version,
+                None => match self.list().await {
+                    Ok(version) => version,
+                    Err(kube::Error::Api(response)) if REJECTED_CODES.contains(&response.code) => {
+                        return self.rejected(response.message);
+                    }
+                    Err(error) => {
+                        self.failed(error.to_string()).await;
+                        continue;
+                    }
+                },
+            };
+
+            resource_version = self.watch(version).await;
+        }
+    }
+
+    /// Lists everything a page at a time and replaces the store, returns the resource version to
+    /// watch from
+    async fn list(&mut self) -> kube::Result<String> {
+        let initial = !self.listed;
+        if initial {
+            *self.listing.lock() = Listing::default();
+
+            if self.metadata_first {
+                self.list_metadata().await?;
+            }
+        }
+
+        let mut objects = vec![];
+        let mut progress = ListProgress::default();
+        let mut continue_token: Option<String> = None;
+
+        let version = loop {
+            let mut params = resource::list_params(&self.selectors).limit(PAGE_SIZE);
+            if let Some(token) = &continue_token {
+                params = params.continue_token(token);
+            }
+
+            let page = self.api.list(&params).await?;
+            self.succeeded();
+
+            progress.loaded += page.items.len() as u64;
+            progress.remaining = page
+                .metadata
+                .remaining_item_count
+                .and_then(|count| u64::try_from(count).ok());
+
+            debug!("listed {} {}s", progress.loaded, self.name);
+
+            // the initial list is served page by page, re-lists keep serving the store instead
+            if initial {
+                self.listing
+                    .lock()
+                    .extend(page.items.iter().cloned(), progress);
+
+                let _ = self.changes.send(Change::Listed(progress));
+            }
+
+            objects.extend(page.items);
+
+            match page.metadata.continue_ {
+                Some(token) if !token.is_empty() => continue_token = Some(token),
+                _ => break page.metadata.resource_version.unwrap_or_default(),
+            }
+        };
+
+        self.apply(watcher::Event::Restarted(objects));
+        self.listed = true;
+        *self.listing.lock() = Listing::default();
+
+        Ok(version)
+    }
+
+    /// Lists the metadata of everything a page at a time into the listing, much smaller than the
+    /// full objects, skipped for kinds that can't be built from their metadata
+    async fn list_metadata(&mut self) -> kube::Result<()> {
+        let mut progress = ListProgress::default();
+        let mut continue_token: Option<String> = None;
+
+        loop {
+            let mut params = resource::list_params(&self.selectors).limit(PAGE_SIZE);
+            if let Some(token) = &continue_token {
+                params = params.continue_token(token);
+            }
+
+            let page = self.api.list_metadata(&params).await?;
+            self.succeeded();
+
+            let Some(objects) = page
+                .items
+                .into_iter()
+                .map(|partial| K::from_metadata(partial.metadata))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return Ok(());
+            };
+
+            progress.loaded += objects.len() as u64;
+            progress.remaining = page
+                .metadata
+                .remaining_item_count
+                .and_then(|count| u64::try_from(count).ok());
+
+            debug!("listed metadata of {} {}s", progress.loaded, self.name);
+
+            self.listing.lock().extend(objects, progress);
+            let _ = self.changes.send(Change::Listed(progress));
+
+            match page.metadata.continue_ {
+                Some(token) if !token.is_empty() => continue_token = Some(token),
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Applies changes until the watch ends, returns the resource version to resume from, `None`
+    /// when it expired and everything has to be listed again
+    async fn watch(&mut self, mut version: String) -> Option<String> {
+        let params = resource::watch_params(&self.selectors);
+
+        let stream = match self.api.watch(&params, &version).await {
+            Ok(stream) => stream,
+            Err(kube::Error::Api(response)) if response.code == 410 => return self.expired(),
+            Err(error) => {
+                self.failed(error.to_string()).await;
+                return Some(version);
+            }
+        };
+
+        let mut stream = stream.boxed();
+
+        while let Some(event) = stream.next().await {
+            let event = match event {
+                Ok(event) => event,
+                Err(error) => {
+                    self.failed(error.to_string()).await;
+                    return Some(version);
+                }
+            };
+
+            match event {
+                WatchEvent::Added(resource) | WatchEvent::Modified(resource) => {
+                    self.succeeded();
+                    version = resource.resource_version().unwrap_or(version);
+                    self.apply(watcher::Event::Applied(resource));
+                }
+                WatchEvent::Deleted(resource) => {
+                    self.succeeded();
+                    version = resource.resource_version().unwrap_or(version);
+                    self.apply(watcher::Event::Deleted(resource));
+                }
+                WatchEvent::Bookmark(bookmark) => {
+                    self.succeeded();
+                    version = bookmark.metadata.resource_version;
+                }
+                WatchEvent::Error(response) if response.code == 410 => return self.expired(),
+                WatchEvent::Error(response) => {
+                    self.failed(response.message).await;
+                    return Some(version);
+                }
+            }
+        }
+
+        // the api server ends every watch after its timeout
+        Some(version)
+    }
+
+    fn apply(&mut self, event: watcher::Event<K>) {
+        self.writer.apply_watcher_event(&event);
+        self.observe(&event);
+
+        let change = match event {
+            watcher::Event::Applied(resource) => Change::Applied(Arc::new(resource)),
+            watcher::Event::Deleted(resource) => Change::Deleted(Arc::new(resource)),
+            watcher::Event::Restarted(_) => Change::Restarted,
+        };
+
+        // having no subscribers is fine, new ones read the store when they subscribe
+        let _ = self.changes.send(change);
+    }
+
+    /// Records the change in the history, also while no window reads the records
+    fn observe(&self, event: &watcher::Event<K>) {
+        K::observe(&mut self.history.lock(), event, Utc::now().timestamp());
+    }
+
+    fn expired(&mut self) -> Option<String> {
+        debug!("{} resource version expired, re-listing", self.name);
+        None
+    }
+
+    /// Reports the error and waits before retrying, with kube's default watcher backoff
+    async fn failed(&mut self, error: String) {
+        let retry_in = self.backoff.next_backoff().unwrap_or(MAX_RETRY_DELAY);
+        warn!(
+            "{} watch failed, retrying in {retry_in:?}: {error}",
+            self.name
+        );
+
+        let failing_since = *self.failing_since.get_or_insert_with(Instant::now);
+        self.set_connection(if failing_since.elapsed() > DISCONNECTED_AFTER {
+            ConnectionState::Disconnected { error }
+        } else {
+            ConnectionState::Reconnecting { error }
+        });
+
+        tokio::time::sleep(retry_in).await;
+    }
+
+    /// Reports the list as failed for good and stops, a new cache is started once every
+    /// subscriber released this one
+    fn rejected(&mut self, error: String) {
+        warn!("{} list rejected, not retrying: {error}", self.name);
+        self.set_connection(ConnectionState::Disconnected { error });
+    }
+
+    fn succeeded(&mut self) {
+        self.failing_since = None;
+        self.backoff.reset();
+        self.set_connection(ConnectionState::Connected);
+    }
+
+    fn set_connection(&self, state: ConnectionState) {
+        let mut connection = self.connection.lock();
+        if *connection != state {
+            debug!("{} cache connection: {state:?}", self.name);
+            *connection = state.clone();
+            let _ = self.changes.send(Change::Connection(state));
+        }
+    }
+}
+
+/// The record of the resource with the fields derived from its history, recording what changed
+fn observed<K: KubeResource>(
+    history: &mut HistoryById<K>,
+    resource: &K,
+    observed_at: i64,
+) -> K::Record {
+    let mut record = K::clone(resource).into_record();
+    record.observe(history.entry(record.id().clone()).or_default(), observed_at);
+    record
+}
@@ -0,0 +1,288 @@
+//! Health of a container derived from its status, so every front end shows the same diagnosis
+
+use fake::Dummy;
+use uniffi::{Enum, Record};
+
+use super::pod::{ContainerKind, ContainerState};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct ContainerHealth {
+    pub status: HealthStatus,
+    /// Why the previous instance of the container terminated, ex: `OOMKilled`, `Error`
+    pub last_termination_reason: Option<String>,
+    pub last_exit_code: Option<i32>,
+    /// When the previous instance terminated and the container was restarted, the time since the
+    /// last restart is counted from it
+    pub last_restart_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Enum, Dummy)]
+pub enum HealthStatus {
+    Healthy,
+
+    /// No status reported yet
+    #[default]
+    Unknown,
+
+    /// Not running yet, ex: `ContainerCreating`, `PodInitializing`
+    Waiting {
+        reason: Option<String>,
+        message: Option<String>,
+    },
+
+    /// Running but its startup probe hasn't passed yet
+    Starting,
+
+    /// Running but its readiness probe is failing, it gets no traffic from services
+    NotReady,
+
+    /// Exited with 0, expected for init containers
+    Completed,
+
+    /// Keeps exiting and the kubelet waits longer before each restart, the last termination
+    /// says why
+    CrashLoopBackOff {
+        message: Option<String>,
+    },
+
+    /// The image can't be pulled, ex: a wrong tag or missing pull secret
+    ImagePullBackOff {
+        reason: String,
+        message: Option<String>,
+    },
+
+    /// Killed for using more memory than its limit
+    OomKilled,
+
+    /// Exited with an error, or the container couldn't be created, ex: `CreateContainerConfigError`
+    /// when a referenced ConfigMap doesn't exist
+    Error {
+        exit_code: Option<i32>,
+        reason: Option<String>,
+        message: Option<String>,
+    },
+}
+
+/// Waiting reasons of containers that won't start without a change
+const ERROR_WAITING_REASONS: &[&str] = &[
+    "CreateContainerConfigError",
+    "CreateContainerError",
+    "RunContainerError",
+    "PreStartHookError",
+    "PostStartHookError",
+];
+
+const IMAGE_PULL_REASONS: &[&str] = &[
+    "ImagePullBackOff",
+    "ErrImagePull",
+    "ErrImageNeverPull",
+    "InvalidImageName",
+];
+
+impl ContainerHealth {
+    pub fn new(
+        kind: ContainerKind,
+        state: Option<&ContainerState>,
+        last_state: Option<&ContainerState>,
+        ready: bool,
+        started: bool,
+        restart_count: i32,
+    ) -> Self {
+        let last_terminated = match last_state {
+            Some(ContainerState::Terminated { data }) => Some(data),
+            _ => None,
+        };
+
+        Self {
+            status: HealthStatus::new(kind, state, ready, started),
+            last_termination_reason: last_terminated.and_then(|data| data.reason.clone()),
+            last_exit_code: last_terminated.map(|data| data.exit_code),
+            last_restart_at: last_terminated
+                .filter(|_| restart_count > 0)
+                .map(|data| data.finished_at),
+        }
+    }
+}
+
+impl HealthStatus {
+    fn new(
+        kind: ContainerKind,
+        state: Option<&ContainerState>,
+        ready: bool,
+        started: bool,
+    ) -> Self {
+        let Some(state) = state else {
+            return Self::Unknown;
+        };
+
+        match state {
+            ContainerState::Waiting { data } => {
+                let reason = data.reason.as_deref().unwrap_or_default();
+
+                if reason == "CrashLoopBackOff" {
+                    Self::CrashLoopBackOff {
+                        message: data.message.clone(),
+                    }
+                } else if IMAGE_PULL_REASONS.contains(&reason) {
+                    Self::ImagePullBackOff {
+                        reason: reason.to_string(),
+                        message: data.message.clone(),
+                    }
+                } else if ERROR_WAITING_REASONS.contains(&reason) {
+                    Self::Error {
+                        exit_code: None,
+                        reason: data.reason.clone(),
+                        message: data.message.clone(),
+                    }
+                } else {
+                    Self::Waiting {
+                        reason: data.reason.clone(),
+                        message: data.message.clone(),
+                    }
+                }
+            }
+
+            ContainerState::Terminated { data } => match data.reason.as_deref() {
+                Some("OOMKilled") => Self::OomKilled,
+                _ if data.exit_code == 0 => Self::Completed,
+                _ => Self::Error {
+                    exit_code: Some(data.exit_code),
+                    reason: data.reason.clone(),
+                    message: data.message.clone(),
+                },
+            },
+
+            // only regular containers have probes, the others are ready once running
+            ContainerState::Running { .. } if kind != ContainerKind::Regular => Self::Healthy,
+            ContainerState::Running { .. } if !started => Self::Starting,
+            ContainerState::Running { .. } if !ready => Self::NotReady,
+            ContainerState::Running { .. } => Self::Healthy,
+        }
+    }
+
+    /// The container won't get better on its own
+    pub fn is_failing(&self) -> bool {
+        matches!(
+            self,
+            Self::CrashLoopBackOff { .. }
+                | Self::ImagePullBackOff { .. }
+                | Self::OomKilled
+                | Self::Error { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::pod::{
+        ContainerStateRunning, ContainerStateTerminated, ContainerStateWaiting,
+    };
+
+    fn waiting(reason: &str) -> ContainerState {
+        ContainerState::Waiting {
+            data: ContainerStateWaiting {
+                reason: Some(reason.to_string()),
+                message: None,
+            },
+        }
+    }
+
+    fn terminated(reason: &str, exit_code: i32, finished_at: i64) -> ContainerState {
+        ContainerState::Terminated {
+            data: ContainerStateTerminated {
+                finished_at,
+                exit_code,
+                reason: Some(reason.to_string()),
+                ..ContainerStateTerminated::default()
+            },
+        }
+    }
+
+    fn running() -> ContainerState {
+        ContainerState::Running {
+            data: ContainerStateRunning { started_at: 0 },
+        }
+    }
+
+    fn status(kind: ContainerKind, state: &ContainerState, ready: bool) -> HealthStatus {
+        ContainerHealth::new(kind, Some(state), None, ready, true, 0).status
+    }
+
+    #[test]
+    fn detects_back_offs_and_failed_exits() {
+        let regular = ContainerKind::Regular;
+
+        assert!(matches!(
+            status(regular, &waiting("CrashLoopBackOff"), false),
+            HealthStatus::CrashLoopBackOff { .. }
+        ));
+        assert!(matches!(
+            status(regular, &waiting("ErrImagePull"), false),
+            HealthStatus::ImagePullBackOff { .. }
+        ));
+        assert!(matches!(
+            status(regular, &waiting("CreateContainerConfigError"), false),
+            HealthStatus::Error {
+                exit_code: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            status(regular, &terminated("OOMKilled", 137, 0), false),
+            HealthStatus::OomKilled
+        );
+        assert!(matches!(
+            status(regular, &terminated("Error", 1, 0), false),
+            HealthStatus::Error {
+                exit_code: Some(1),
+                ..
+            }
+        ));
+        assert!(!status(regular, &waiting("ContainerCreating"), false).is_failing());
+    }
+
+    #[test]
+    fn readiness_only_applies_to_regular_containers() {
+        assert_eq!(
+            status(ContainerKind::Regular, &running(), false),
+            HealthStatus::NotReady
+        );
+        assert_eq!(
+            status(ContainerKind::Init, &running(), false),
+            HealthStatus::Healthy
+        );
+        assert_eq!(
+            status(ContainerKind::Init, &terminated("Completed", 0, 0), false),
+            HealthStatus::Completed
+        );
+        assert_eq!(
+            ContainerHealth::new(
+                ContainerKind::Regular,
+                Some(&running()),
+                None,
+                false,
+                false,
+                0
+            )
+            .status,
+            HealthStatus::Starting
+        );
+    }
+
+    #[test]
+    fn keeps_the_last_termination() {
+        let health = ContainerHealth::new(
+            ContainerKind::Regular,
+            Some(&waiting("CrashLoopBackOff")),
+            Some(&terminated("OOMKilled", 137, 1_700_000_000)),
+            false,
+            false,
+            4,
+        );
+
+        assert_eq!(health.last_termination_reason.as_deref(), Some("OOMKilled"));
+        assert_eq!(health.last_exit_code, Some(137));
+        assert_eq!(health.last_restart_at, Some(1_700_000_000));
+    }
+}
@@ -1,10 +1,19 @@
+use std::collections::HashMap;
+
 use fake::Dummy;
 use k8s_openapi::{
-    api::core::v1::Toleration as K8sToleration,
-    apimachinery::pkg::apis::meta::v1::OwnerReference as K8sOwnerReference,
+    api::core::v1::{
+        NodeSelectorRequirement as K8sNodeSelectorRequirement, Toleration as K8sToleration,
+    },
+    apimachinery::pkg::apis::meta::v1::{
+        LabelSelector as K8sLabelSelector, LabelSelectorRequirement as K8sLabelSelectorRequirement,
+        OwnerReference as K8sOwnerReference,
+    },
 };
 use uniffi::Record;
 
+use super::node::Taint;
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
 pub struct OwnerReference {
     pub api_version: String,
@@ -24,6 +33,20 @@ pub struct Toleration {
     pub value: Option<String>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct LabelSelector {
+    pub match_labels: HashMap<String, String>,
+    pub match_expressions: Vec<LabelSelectorRequirement>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct LabelSelectorRequirement {
+    pub key: String,
+    /// `In`, `NotIn`, `Exists` or `DoesNotExist`, node selectors also have `Gt` and `Lt`
+    pub operator: String,
+    pub values: Vec<String>,
+}
+
 impl From<K8sOwnerReference> for OwnerReference {
     fn from(owner_reference: K8sOwnerReference) -> Self {
         Self {
@@ -48,3 +71,96 @@ impl From<K8sToleration> for Toleration {
         }
     }
 }
+
+impl Toleration {
+    /// Same rules as the scheduler, an empty key with `Exists` tolerates every taint and an empty
+    /// effect tolerates every effect
+    pub fn tolerates(&self, taint: &Taint) -> bool {
+        let is_empty = |value: &Option<String>| value.as_deref().unwrap_or_default().is_empty();
+
+        if !is_empty(&self.effect) && self.effect.as_deref() != Some(taint.effect.as_str()) {
+            return false;
+        }
+
+        if !is_empty(&self.key) && self.key.as_deref() != Some(taint.key.as_str()) {
+            return false;
+        }
+
+        match self.operator.as_deref().unwrap_or_default() {
+            "Exists" => true,
+            "Equal" | "" => {
+                self.value.as_deref().unwrap_or_default()
+                    == taint.value.as_deref().unwrap_or_default()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<K8sLabelSelector> for LabelSelector {
+    fn from(selector: K8sLabelSelector) -> Self {
+        Self {
+            match_labels: selector
+                .match_labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            match_expressions: selector
+                .match_expressions
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl From<K8sLabelSelectorRequirement> for LabelSelectorRequirement {
+    fn from(requirement: K8sLabelSelectorRequirement) -> Self {
+        Self {
+            key: requirement.key,
+            operator: requirement.operator,
+            values: requirement.values.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<K8sNodeSelectorRequirement> for LabelSelectorRequirement {
+    fn from(requirement: K8sNodeSelectorRequirement) -> Self {
+        Self {
+            key: requirement.key,
+            operator: requirement.operator,
+            values: requirement.values.unwrap_or_default(),
+        }
+    }
+}
+
+impl LabelSelector {
+    /// An empty selector matches everything
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        let labels_match = self
+            .match_labels
+            .iter()
+            .all(|(key, value)| labels.get(key) == Some(value));
+
+        labels_match
+            && self
+                .match_expressions
+                .iter()
+                .all(|requirement| requirement.matches(labels))
+    }
+}
+
+impl LabelSelectorRequirement {
+    pub fn matches(&self, labels: &HashMap<String, String>) -> bool {
+        let value = labels.get(&self.key);
+
+        match self.operator.as_str() {
+            "In" => value.is_some_and(|value| self.values.contains(value)),
+            "NotIn" => !value.is_some_and(|value| self.values.contains(value)),
+            "Exists" => value.is_some(),
+            "DoesNotExist" => value.is_none(),
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use act_zero::{call, WeakAddr};
+use derive_more::{AsRef, Display, From};
+use eyre::Result;
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use kube::{
+    api::{ApiResource, DynamicObject},
+    discovery::{verbs, Discovery, Scope},
+    Api, Client, ResourceExt,
+};
+use log::debug;
+use serde_json::Value;
+use uniffi::Record;
+
+use crate::view_models::custom_resource::CustomResourceViewModel;
+
+use super::cache::{CachedResource, Change, ResourceCache};
+
+uniffi::custom_newtype!(CustomResourceKindId, String);
+/// `<plural>.<group>/<version>`, ex: `certificates.cert-manager.io/v1`
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct CustomResourceKindId(String);
+
+uniffi::custom_newtype!(CustomResourceId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct CustomResourceId(String);
+
+/// A served kind defined by a CRD
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Record, Dummy)]
+pub struct CustomResourceKind {
+    pub id: CustomResourceKindId,
+    pub group: String,
+    pub version: String,
+    pub kind: String,
+    pub plural: String,
+    pub namespaced: bool,
+    pub printer_columns: Vec<PrinterColumn>,
+}
+
+/// Column from the CRD's `additionalPrinterColumns`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Record, Dummy)]
+pub struct PrinterColumn {
+    pub name: String,
+    /// `string`, `integer`, `number`, `boolean` or `date`
+    pub type_: String,
+    pub json_path: String,
+    pub description: Option<String>,
+    /// columns with a priority above 0 are only shown in the wide view
+    pub priority: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct CustomResource {
+    pub id: CustomResourceId,
+    pub kind_id: CustomResourceKindId,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    /// value of each printer column of the kind, in the same order
+    pub cells: Vec<Option<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct CustomResourceDetail {
+    pub resource: CustomResource,
+    pub yaml: String,
+}
+
+impl CustomResourceKind {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    pub fn api_resource(&self) -> ApiResource {
+        let api_version = if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        };
+
+        ApiResource {
+            group: self.group.clone(),
+            version: self.version.clone(),
+            api_version,
+            kind: self.kind.clone(),
+            plural: self.plural.clone(),
+        }
+    }
+
+    pub fn resource(&self, object: &DynamicObject) -> CustomResource {
+        let name = object.name_any();
+        let namespace = object.namespace();
+
+        let id = match &namespace {
+            Some(namespace) => format!("{namespace}/{name}"),
+            None => name.clone(),
+        };
+
+        let value = serde_json::to_value(object).unwrap_or_default();
+        let cells = self
+            .printer_columns
+            .iter()
+            .map(|column| cell(&value, &column.json_path))
+            .collect();
+
+        CustomResource {
+            id: id.into(),
+            kind_id: self.id.clone(),
+            name,
+            namespace,
+            created_at: object
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|t| t.0.timestamp()),
+            labels: object.labels().clone().into_iter().collect(),
+            annotations: object.annotations().clone().into_iter().collect(),
+            cells,
+        }
+    }
+
+    pub fn detail(&self, object: &DynamicObject) -> CustomResourceDetail {
+        let mut object = object.clone();
+        object.metadata.managed_fields = None;
+
+        CustomResourceDetail {
+            resource: self.resource(&object),
+            yaml: serde_yaml::to_string(&object).unwrap_or_default(),
+        }
+    }
+}
+
+#[uniffi::export]
+pub fn custom_resource_kind_preview() -> CustomResourceKind {
+    CustomResourceKind::preview()
+}
+
+#[uniffi::export]
+pub fn custom_resource_preview() -> CustomResource {
+    Faker.fake()
+}
+
+fn kind_id(plural: &str, group: &str, version: &str) -> CustomResourceKindId {
+    format!("{plural}.{group}/{version}").into()
+}
+
+/// Every listable and watchable kind served by the cluster that comes from a CRD, at the version
+/// the api server prefers. Built-in kinds have their own tabs
+pub async fn kinds(client: Client) -> Result<Vec<CustomResourceKind>> {
+    let crds_api: Api<CustomResourceDefinition> = Api::all(client.clone());
+    let crds = crds_api.list(&Default::default()).await?;
+
+    let mut printer_columns: HashMap<CustomResourceKindId, Vec<PrinterColumn>> = HashMap::new();
+    let mut groups = HashSet::new();
+
+    for crd in crds {
+        let spec = crd.spec;
+        groups.insert(spec.group.clone());
+
+        for version in spec.versions {
+            let columns = version
+                .additional_printer_columns
+                .unwrap_or_default()
+                .into_iter()
+                .map(|column| PrinterColumn {
+                    name: column.name,
+                    type_: column.type_,
+                    json_path: column.json_path,
+                    description: column.description,
+                    priority: column.priority.unwrap_or_default(),
+                })
+                .collect();
+
+            printer_columns.insert(
+                kind_id(&spec.names.plural, &spec.group, &version.name),
+                columns,
+            );
+        }
+    }
+
+    if groups.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // only the groups of the CRDs, a full discovery fails when any aggregated api is down
+    let groups = groups.iter().map(String::as_str).collect::<Vec<_>>();
+    let discovery = Discovery::new(client).filter(&groups).run().await?;
+
+    let mut kinds = discovery
+        .groups()
+        .flat_map(|group| group.recommended_resources())
+        .filter(|(_, capabilities)| {
+            capabilities.supports_operation(verbs::LIST)
+                && capabilities.supports_operation(verbs::WATCH)
+        })
+        .filter_map(|(resource, capabilities)| {
+            let id = kind_id(&resource.plural, &resource.group, &resource.version);
+            let printer_columns = printer_columns.remove(&id)?;
+
+            Some(CustomResourceKind {
+                id,
+                group: resource.group,
+                version: resource.version,
+                kind: resource.kind,
+                plural: resource.plural,
+                namespaced: matches!(capabilities.scope, Scope::Namespaced),
+                printer_columns,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    kinds.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.group.cmp(&b.group)));
+
+    Ok(kinds)
+}
+
+/// Custom resources are cached as objects, the printer columns of the kind are read from them
+impl CachedResource for DynamicObject {
+    type History = ();
+}
+
+/// The objects by the id of their custom resource
+pub fn objects(
+    kind: &CustomResourceKind,
+    objects: &[Arc<DynamicObject>],
+) -> HashMap<CustomResourceId, DynamicObject> {
+    objects
+        .iter()
+        .map(|object| (kind.resource(object).id, DynamicObject::clone(object)))
+        .collect()
+}
+
+/// Forwards the changes of the shared cache of the kind to the view model until the task is
+/// aborted
+pub async fn watch(
+    addr: WeakAddr<CustomResourceViewModel>,
+    cache: Arc<ResourceCache<DynamicObject>>,
+    kind: CustomResourceKind,
+) -> Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+    debug!("watching {} cache for {:?}", kind.id, cache.cluster_id());
+
+    // subscribe before reading the store so no change is missed in between
+    let mut changes = cache.subscribe();
+    call!(addr.connection_changed(kind.id.clone(), cache.connection())).await?;
+
+    if cache.is_ready() {
+        call!(addr.replaced(kind.id.clone(), objects(&kind, &cache.objects()))).await?;
+    }
+
+    loop {
+        match changes.recv().await {
+            Ok(Change::Applied(object)) => {
+                call!(addr.applied(kind.id.clone(), DynamicObject::clone(&object))).await?;
+            }
+            Ok(Change::Deleted(object)) => {
+                call!(addr.deleted(kind.id.clone(), DynamicObject::clone(&object))).await?;
+            }
+            Ok(Change::Restarted) => {
+                debug!("{} re-listed on {:?}", kind.id, cache.cluster_id());
+                call!(addr.replaced(kind.id.clone(), objects(&kind, &cache.objects()))).await?;
+            }
+            Ok(Change::Connection(state)) => {
+                call!(addr.connection_changed(kind.id.clone(), state)).await?;
+            }
+            Ok(Change::Listed(_)) => {}
+            Err(RecvError::Lagged(skipped)) if cache.is_ready() => {
+                debug!("{} watcher lagged by {skipped} changes, reloading", kind.id);
+                call!(addr.replaced(kind.id.clone(), objects(&kind, &cache.objects()))).await?;
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Formats the value a printer column points to, multiple matches are joined with a comma
+fn cell(object: &Value, json_path: &str) -> Option<String> {
+    let values = evaluate_json_path(object, json_path);
+
+    if values.is_empty() {
+        return None;
+    }
+
+    let cell = values
+        .into_iter()
+        .map(|value| match value {
+            Value::String(string) => string.clone(),
+            Value::Null => String::new(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    Some(cell)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+    Wildcard,
+    /// `[?(@.key=="value")]`, `key` can be a nested path
+    Filter(Vec<String>, String),
+}
+
+/// The JSONPath subset used by printer columns: fields, indexes, wildcards and equality filters
+pub fn evaluate_json_path<'a>(object: &'a Value, json_path: &str) -> Vec<&'a Value> {
+    let Some(segments) = parse_json_path(json_path) else {
+        return vec![];
+    };
+
+    let mut current = vec![object];
+
+    for segment in &segments {
+        current = current
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (segment, value) {
+                    (PathSegment::Field(field), Value::Object(map)) => {
+                        map.get(field).into_iter().collect()
+                    }
+                    (PathSegment::Index(index), Value::Array(array)) => {
+                        array.get(*index).into_iter().collect()
+                    }
+                    (PathSegment::Wildcard, Value::Array(array)) => array.iter().collect(),
+                    (PathSegment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    (PathSegment::Filter(path, expected), Value::Array(array)) => array
+                        .iter()
+                        .filter(|item| {
+                            let found =
+                                path.iter().try_fold(*item, |value, field| value.get(field));
+
+                            match found {
+                                Some(Value::String(string)) => string == expected,
+                                Some(other) => &other.to_string() == expected,
+                                None => false,
+                            }
+                        })
+                        .collect(),
+                    _ => vec![],
+                }
+            })
+            .collect();
+    }
+
+    current
+}
+
+fn parse_json_path(json_path: &str) -> Option<Vec<PathSegment>> {
+    let path = json_path.trim();
+    let path = path
+        .strip_prefix('{')
+        .and_then(|path| path.strip_suffix('}'))
+        .unwrap_or(path);
+    let path = path.strip_prefix('$').unwrap_or(path);
+
+    let mut segments = Vec::new();
+    let mut chars = path.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '.' => {
+                let mut field = String::new();
+
+                while let Some(&next) = chars.peek() {
+                    match next {
+                        '.' | '[' => break,
+                        // keys like `cert-manager\.io/issuer` escape their dots
+                        '\\' => {
+                            chars.next();
+                            field.extend(chars.next());
+                        }
+                        _ => {
+                            field.push(next);
+                            chars.next();
+                        }
+                    }
+                }
+
+                if field == "*" {
+                    segments.push(PathSegment::Wildcard);
+                } else if !field.is_empty() {
+                    segments.push(PathSegment::Field(field));
+                }
+            }
+
+            '[' => {
+                let mut inner = String::new();
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        break;
+                    }
+                    inner.push(next);
+                }
+
+                segments.push(parse_bracket(inner.trim())?);
+            }
+
+            _ => return None,
+        }
+    }
+
+    Some(segments)
+}
+
+fn parse_bracket(inner: &str) -> Option<PathSegment> {
+    if inner == "*" {
+        return Some(PathSegment::Wildcard);
+    }
+
+    if let Ok(index) = inner.parse() {
+        return Some(PathSegment::Index(index));
+    }
+
+    if let Some(filter) = inner
+        .strip_prefix("?(")
+        .and_then(|filter| filter.strip_suffix(')'))
+    {
+        let (path, expected) = filter.split_once("==")?;
+        let path = path.trim().strip_prefix("@.")?;
+
+        return Some(PathSegment::Filter(
+            path.split('.').map(ToString::to_string).collect(),
+            unquote(expected.trim()).to_string(),
+        ));
+    }
+
+    Some(PathSegment::Field(unquote(inner).to_string()))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn certificate() -> Value {
+        json!({
+            "metadata": {
+                "name": "web-tls",
+                "annotations": {"cert-manager.io/issuer": "letsencrypt"}
+            },
+            "spec": {"dnsNames": ["example.com", "www.example.com"], "secretName": "web-tls"},
+            "status": {
+                "conditions": [
+                    {"type": "Issuing", "status": "False"},
+                    {"type": "Ready", "status": "True", "message": "Certificate is up to date"}
+                ],
+                "revision": 3
+            }
+        })
+    }
+
+    #[test]
+    fn test_printer_column_cells() {
+        let certificate = certificate();
+
+        assert_eq!(
+            cell(
+                &certificate,
+                ".status.conditions[?(@.type==\"Ready\")].status"
+            ),
+            Some("True".to_string())
+        );
+        assert_eq!(
+            cell(&certificate, ".spec.secretName"),
+            Some("web-tls".to_string())
+        );
+        assert_eq!(
+            cell(&certificate, ".status.revision"),
+            Some("3".to_string())
+        );
+        assert_eq!(
+            cell(&certificate, ".spec.dnsNames[*]"),
+            Some("example.com,www.example.com".to_string())
+        );
+        assert_eq!(
+            cell(&certificate, ".spec.dnsNames[1]"),
+            Some("www.example.com".to_string())
+        );
+        assert_eq!(
+            cell(
+                &certificate,
+                ".metadata.annotations.cert-manager\\.io/issuer"
+            ),
+            Some("letsencrypt".to_string())
+        );
+        assert_eq!(cell(&certificate, ".status.notThere"), None);
+    }
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(
+            parse_json_path("{$.status['phase']}"),
+            Some(vec![
+                PathSegment::Field("status".to_string()),
+                PathSegment::Field("phase".to_string())
+            ])
+        );
+
+        assert_eq!(parse_json_path("status"), None);
+    }
+}
@@ -0,0 +1,1080 @@
+//! Why a pod isn't running, diagnosed from the pod, its events, its node and its controller
+//!
+//! [`diagnose`] is a pure function over records, [`context`] loads what it looks at. Findings are
+//! ranked by severity, within the same severity the ones closest to the cause come first, ex: a
+//! pending claim before the pod it keeps from being scheduled
+
+use std::collections::HashSet;
+
+use eyre::Result;
+use k8s_openapi::api::{
+    apps::v1::{DaemonSet, ReplicaSet, StatefulSet},
+    batch::v1::Job,
+    core::v1::{
+        Event as K8sEvent, Node as K8sNode, PersistentVolumeClaim as K8sPersistentVolumeClaim,
+    },
+};
+use kube::{api::ListParams, Api, Client};
+use log::warn;
+use uniffi::{Enum, Record};
+
+use super::{
+    container_health::HealthStatus,
+    node::Node,
+    pod::{Container, Phase, Pod},
+    pod_spec::{self, ContainerEnv, ProbeHandler, VolumeSource},
+};
+
+/// Warning events about the pod worth showing on their own, the others are covered by the state
+/// of the pod and its containers
+const EVENT_REASONS: &[&str] = &[
+    "FailedMount",
+    "FailedAttachVolume",
+    "FailedCreatePodSandBox",
+    "Evicted",
+];
+
+/// Node conditions that are a problem when `True`
+const NODE_PRESSURE_CONDITIONS: &[&str] = &["MemoryPressure", "DiskPressure", "PIDPressure"];
+
+/// Controller conditions that are a problem when `True`, ex: `ReplicaFailure` when a quota keeps a
+/// ReplicaSet from creating pods
+const CONTROLLER_FAILURE_CONDITIONS: &[&str] = &["ReplicaFailure", "Failed"];
+
+/// What the diagnosis looks at besides the pod, everything is optional, a finding needing a
+/// missing record is left out
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct DiagnosisContext {
+    pub events: Vec<PodEvent>,
+    pub node: Option<Node>,
+    pub controller: Option<Controller>,
+    /// Claims used by the volumes of the pod, a claim missing from here doesn't exist
+    pub claims: Vec<VolumeClaim>,
+    /// Resolved env of the containers, see [`pod_spec::resolve_env`]
+    pub env: Vec<ContainerEnv>,
+    /// What couldn't be loaded, the diagnosis says so instead of looking healthy
+    pub unavailable: Vec<UnavailableSource>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct UnavailableSource {
+    /// ex: `events`, `node`
+    pub source: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct PodEvent {
+    /// `Normal` or `Warning`
+    pub type_: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    /// Set for events about a container, ex: `spec.containers{web}`
+    pub field_path: Option<String>,
+    pub count: i32,
+    pub last_seen: Option<i64>,
+}
+
+/// The controller owning the pod, ex: a ReplicaSet or a Job
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct Controller {
+    pub kind: String,
+    pub name: String,
+    pub desired_replicas: Option<i32>,
+    pub ready_replicas: Option<i32>,
+    pub conditions: Vec<ControllerCondition>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct ControllerCondition {
+    pub type_: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct VolumeClaim {
+    pub name: String,
+    /// `Pending`, `Bound` or `Lost`
+    pub phase: String,
+    pub storage_class: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Enum)]
+pub enum Severity {
+    /// Keeps the pod or a container from running
+    Critical,
+    /// Running but degraded, or will fail on the next restart
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Record)]
+pub struct Finding {
+    pub severity: Severity,
+    /// ex: `Not enough CPU on 3 of 3 nodes`
+    pub title: String,
+    /// The message from kubernetes the finding is based on, or what to check next
+    pub detail: Option<String>,
+    /// Set for findings about a single container
+    pub container_name: Option<String>,
+}
+
+impl Finding {
+    fn new(severity: Severity, title: impl Into<String>) -> Self {
+        Self {
+            severity,
+            title: title.into(),
+            detail: None,
+            container_name: None,
+        }
+    }
+
+    fn detail(mut self, detail: Option<impl Into<String>>) -> Self {
+        self.detail = detail.map(Into::into);
+        self
+    }
+
+    fn container(mut self, container: &Container) -> Self {
+        self.container_name = Some(container.name.clone());
+        self
+    }
+
+    /// The pod couldn't be diagnosed at all, ex: it was deleted or the cluster is unreachable
+    pub fn diagnosis_failed(error: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, "Unable to diagnose the pod").detail(Some(error))
+    }
+}
+
+#[uniffi::export]
+pub fn diagnose_pod(pod: Pod, context: DiagnosisContext) -> Vec<Finding> {
+    diagnose(&pod, &context)
+}
+
+/// Findings about the pod, most severe first
+pub fn diagnose(pod: &Pod, context: &DiagnosisContext) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    findings.extend(claim_findings(pod, &context.claims));
+    findings.extend(scheduling_findings(pod, &context.events));
+
+    if pod.phase == Phase::Failed {
+        findings
+            .push(Finding::new(Severity::Critical, "Pod failed").detail(pod.message.as_deref()));
+    }
+
+    for container in pod.all_containers() {
+        findings.extend(container_findings(container, context));
+    }
+
+    findings.extend(event_findings(&context.events));
+
+    if let Some(node) = &context.node {
+        findings.extend(node_findings(node));
+    }
+
+    if let Some(controller) = &context.controller {
+        findings.extend(controller_findings(controller));
+    }
+
+    findings.extend(context.unavailable.iter().map(|unavailable| {
+        Finding::new(
+            Severity::Info,
+            format!("Unable to load the {}", unavailable.source),
+        )
+        .detail(Some(&unavailable.error))
+    }));
+
+    // stable, the order of the checks is kept within a severity
+    findings.sort_by_key(|finding| finding.severity);
+    findings
+}
+
+/// Loads the events, node, controller, claims and env of the pod, a source that fails to load is
+/// left out and listed as unavailable, ex: not being allowed to read nodes
+pub async fn context(client: Client, pod: &Pod) -> DiagnosisContext {
+    let (events, node, controller, claims, env) = tokio::join!(
+        events(client.clone(), pod),
+        node(client.clone(), pod),
+        controller(client.clone(), pod),
+        claims(client.clone(), pod),
+        pod_spec::resolve_env(client, pod),
+    );
+
+    let mut unavailable = vec![];
+    let mut loaded = |source: &str, error: eyre::Report| {
+        warn!("failed to load the {source} of pod {}: {error}", pod.id);
+        unavailable.push(UnavailableSource {
+            source: source.to_string(),
+            error: error.to_string(),
+        });
+    };
+
+    let events = events.unwrap_or_else(|error| {
+        loaded("events", error);
+        vec![]
+    });
+    let node = node.unwrap_or_else(|error| {
+        loaded("node", error);
+        None
+    });
+    let controller = controller.unwrap_or_else(|error| {
+        loaded("controller", error);
+        None
+    });
+    let claims = claims.unwrap_or_else(|error| {
+        loaded("volume claims", error);
+        vec![]
+    });
+    let env = env.unwrap_or_else(|error| {
+        loaded("env", error);
+        vec![]
+    });
+
+    DiagnosisContext {
+        events,
+        node,
+        controller,
+        claims,
+        env,
+        unavailable,
+    }
+}
+
+fn claim_findings(pod: &Pod, claims: &[VolumeClaim]) -> Vec<Finding> {
+    claim_names(pod)
+        .filter_map(
+            |name| match claims.iter().find(|claim| claim.name == name) {
+                None => Some(Finding::new(
+                    Severity::Critical,
+                    format!("PersistentVolumeClaim {name} doesn't exist"),
+                )),
+                Some(claim) if claim.phase == "Pending" => Some(
+                    Finding::new(
+                        Severity::Critical,
+                        format!("PersistentVolumeClaim {name} is Pending"),
+                    )
+                    .detail(Some(match &claim.storage_class {
+                        Some(class) => {
+                            format!("No volume was provisioned by storage class {class}")
+                        }
+                        None => "No storage class, a matching volume has to be created".to_string(),
+                    })),
+                ),
+                Some(claim) if claim.phase == "Lost" => Some(Finding::new(
+                    Severity::Critical,
+                    format!("PersistentVolumeClaim {name} lost its volume"),
+                )),
+                Some(_) => None,
+            },
+        )
+        .collect()
+}
+
+fn claim_names(pod: &Pod) -> impl Iterator<Item = &str> {
+    pod.volumes
+        .iter()
+        .filter_map(|volume| match &volume.source {
+            VolumeSource::PersistentVolumeClaim { claim_name, .. } => Some(claim_name.as_str()),
+            _ => None,
+        })
+}
+
+fn scheduling_findings(pod: &Pod, events: &[PodEvent]) -> Vec<Finding> {
+    if pod.scheduling.scheduled || pod.node_name.is_some() {
+        return vec![];
+    }
+
+    let message = pod.scheduling.message.clone().or_else(|| {
+        latest_event(events, "FailedScheduling", None).and_then(|event| event.message.clone())
+    });
+
+    let Some(message) = message else {
+        return vec![];
+    };
+
+    let reasons = unschedulable_reasons(&message);
+    if reasons.is_empty() {
+        return vec![Finding::new(Severity::Critical, "Can't be scheduled").detail(Some(message))];
+    }
+
+    reasons
+        .into_iter()
+        .map(|(title, _)| {
+            Finding::new(Severity::Critical, format!("Can't be scheduled: {title}"))
+                .detail(Some(message.clone()))
+        })
+        .collect()
+}
+
+/// Parses the scheduler message into a reason per node count, ex: `0/3 nodes are available: 1
+/// node(s) had untolerated taint {dedicated: db}, 2 Insufficient cpu.`, most nodes first
+fn unschedulable_reasons(message: &str) -> Vec<(String, u32)> {
+    let Some((nodes, reasons)) = message.split_once(" nodes are available: ") else {
+        return vec![];
+    };
+
+    let total = nodes
+        .split_once('/')
+        .and_then(|(_, total)| total.trim().parse::<u32>().ok());
+
+    // the preemption attempt is appended as another sentence
+    let reasons = reasons
+        .split_once(". ")
+        .map_or(reasons, |(reasons, _)| reasons)
+        .trim_end_matches('.');
+
+    let mut reasons = reasons
+        .split(", ")
+        .filter_map(|reason| {
+            let (count, reason) = reason.split_once(' ')?;
+            let count = count.parse::<u32>().ok()?;
+            let reason = reason.trim_start_matches("node(s) ");
+
+            let nodes = match total {
+                Some(total) if total == count => format!("all {total} nodes"),
+                Some(total) => format!("{count} of {total} nodes"),
+                None => format!("{count} nodes"),
+            };
+
+            let title = match reason.strip_prefix("Insufficient ") {
+                Some("cpu") => format!("not enough CPU on {nodes}"),
+                Some("memory") => format!("not enough memory on {nodes}"),
+                Some(resource) => format!("not enough {resource} on {nodes}"),
+                None if reason.starts_with("had untolerated taint") => {
+                    format!("{nodes} have a taint the pod doesn't tolerate")
+                }
+                None if reason.contains("node affinity/selector") => {
+                    format!("{nodes} don't match its node selector or affinity")
+                }
+                None if reason.contains("unbound immediate PersistentVolumeClaims") => {
+                    "its PersistentVolumeClaims aren't bound".to_string()
+                }
+                None if reason.contains("unschedulable") => format!("{nodes} are cordoned"),
+                None => format!("{reason} ({nodes})"),
+            };
+
+            Some((title, count))
+        })
+        .collect::<Vec<_>>();
+
+    reasons.sort_by(|a, b| b.1.cmp(&a.1));
+    reasons
+}
+
+fn container_findings(container: &Container, context: &DiagnosisContext) -> Vec<Finding> {
+    let mut findings = vec![];
+    let health = &container.health;
+
+    let env_errors = context
+        .env
+        .iter()
+        .filter(|env| env.container_kind == container.kind && env.container_name == container.name)
+        .flat_map(|env| &env.env)
+        .filter_map(|var| Some((&var.name, var.error.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    let finding = match &health.status {
+        HealthStatus::ImagePullBackOff { message, .. } => {
+            // the back off message doesn't say why, the pull failure event does
+            let message = latest_event(&context.events, "Failed", Some(container))
+                .and_then(|event| event.message.as_deref())
+                .or(message.as_deref());
+
+            Some(
+                Finding::new(Severity::Critical, image_pull_title(container, message))
+                    .detail(message),
+            )
+        }
+
+        HealthStatus::CrashLoopBackOff { .. } => {
+            let title = match health.last_termination_reason.as_deref() {
+                Some("OOMKilled") => format!("Crash looping, {}", out_of_memory(container)),
+                _ => match health.last_exit_code {
+                    Some(exit_code) => format!("Crash looping, last exited with code {exit_code}"),
+                    None => "Crash looping".to_string(),
+                },
+            };
+
+            Some(
+                Finding::new(Severity::Critical, title)
+                    .detail(Some(format!("Restarted {} times", container.restart_count))),
+            )
+        }
+
+        HealthStatus::OomKilled => Some(Finding::new(
+            Severity::Critical,
+            format!("Container was {}", out_of_memory(container)),
+        )),
+
+        // config errors are explained by the env errors below
+        HealthStatus::Error {
+            exit_code: None,
+            reason,
+            ..
+        } if reason.as_deref() == Some("CreateContainerConfigError") && !env_errors.is_empty() => {
+            None
+        }
+
+        HealthStatus::Error {
+            exit_code,
+            reason,
+            message,
+        } => {
+            let title = match (exit_code, reason) {
+                (Some(exit_code), _) => format!("Exited with code {exit_code}"),
+                (None, Some(reason)) => format!("Can't be started: {reason}"),
+                (None, None) => "Can't be started".to_string(),
+            };
+
+            Some(Finding::new(Severity::Critical, title).detail(message.as_deref()))
+        }
+
+        HealthStatus::NotReady => {
+            let message = latest_event(&context.events, "Unhealthy", Some(container))
+                .and_then(|event| event.message.as_deref())
+                .filter(|message| message.starts_with("Readiness probe failed"));
+
+            let title = match container
+                .readiness_probe
+                .as_ref()
+                .map(|probe| &probe.handler)
+            {
+                Some(ProbeHandler::HttpGet { port, path, .. }) => format!(
+                    "Readiness probe failing on port {port}{}",
+                    path.as_deref().unwrap_or_default()
+                ),
+                Some(ProbeHandler::TcpSocket { port, .. }) => {
+                    format!("Readiness probe failing on port {port}")
+                }
+                Some(ProbeHandler::Grpc { port, .. }) => {
+                    format!("Readiness probe failing on gRPC port {port}")
+                }
+                _ => "Readiness probe failing".to_string(),
+            };
+
+            Some(Finding::new(Severity::Warning, title).detail(message))
+        }
+
+        HealthStatus::Healthy if container.restart_count > 0 => Some(
+            Finding::new(
+                Severity::Info,
+                format!("Restarted {} times", container.restart_count),
+            )
+            .detail(
+                health
+                    .last_termination_reason
+                    .as_ref()
+                    .map(|reason| format!("Last terminated with {reason}")),
+            ),
+        ),
+
+        HealthStatus::Healthy
+        | HealthStatus::Unknown
+        | HealthStatus::Waiting { .. }
+        | HealthStatus::Starting
+        | HealthStatus::Completed => None,
+    };
+
+    findings.extend(finding.map(|finding| finding.container(container)));
+
+    // a running container got its env when it started, it only fails on the next restart
+    let severity = match &health.status {
+        HealthStatus::Error {
+            exit_code: None, ..
+        } => Severity::Critical,
+        _ => Severity::Warning,
+    };
+
+    findings.extend(env_errors.into_iter().map(|(name, error)| {
+        Finding::new(severity, format!("Env var {name}: {error}")).container(container)
+    }));
+
+    findings
+}
+
+fn image_pull_title(container: &Container, message: Option<&str>) -> String {
+    let message = message.unwrap_or_default().to_lowercase();
+    let image = &container.image;
+
+    let unauthorized = [
+        "unauthorized",
+        "authentication required",
+        "pull access denied",
+        "no basic auth credentials",
+        "403 forbidden",
+    ];
+
+    if unauthorized.iter().any(|text| message.contains(text)) {
+        format!("Not authorized to pull image {image}, check the image pull secrets")
+    } else if message.contains("not found") || message.contains("manifest unknown") {
+        format!("Image {image} doesn't exist")
+    } else {
+        format!("Can't pull image {image}")
+    }
+}
+
+fn out_of_memory(container: &Container) -> String {
+    match container.limits.memory_bytes {
+        Some(bytes) => format!(
+            "killed for using more than its {}Mi memory limit",
+            bytes / 1024 / 1024
+        ),
+        None => "killed when the node ran out of memory".to_string(),
+    }
+}
+
+fn event_findings(events: &[PodEvent]) -> Vec<Finding> {
+    let mut seen = HashSet::new();
+
+    events
+        .iter()
+        .rev()
+        .filter(|event| event.type_ == "Warning")
+        .filter_map(|event| {
+            let reason = event.reason.as_deref()?;
+            (EVENT_REASONS.contains(&reason) && seen.insert(reason)).then_some(event)
+        })
+        .map(|event| {
+            let reason = event.reason.as_deref().unwrap_or_default();
+            let title = match reason {
+                "FailedMount" => "Volume can't be mounted",
+                "FailedAttachVolume" => "Volume can't be attached to the node",
+                "FailedCreatePodSandBox" => "Pod network or sandbox can't be created",
+                "Evicted" => "Evicted from the node",
+                reason => reason,
+            };
+
+            Finding::new(Severity::Critical, title).detail(event.message.as_deref())
+        })
+        .collect()
+}
+
+fn node_findings(node: &Node) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    if !node.is_ready() {
+        let ready = node
+            .conditions
+            .iter()
+            .find(|condition| condition.name == "Ready");
+
+        findings.push(
+            Finding::new(
+                Severity::Critical,
+                format!("Node {} is not ready", node.name),
+            )
+            .detail(ready.and_then(|condition| condition.message.as_deref())),
+        );
+    }
+
+    findings.extend(
+        node.conditions
+            .iter()
+            .filter(|condition| {
+                NODE_PRESSURE_CONDITIONS.contains(&condition.name.as_str())
+                    && condition.status == "True"
+            })
+            .map(|condition| {
+                Finding::new(
+                    Severity::Warning,
+                    format!("Node {} has {}", node.name, condition.name),
+                )
+                .detail(condition.message.as_deref())
+            }),
+    );
+
+    if node.flapping {
+        findings.push(Finding::new(
+            Severity::Warning,
+            format!(
+                "Node {} is flapping, Ready changed {} times recently",
+                node.name, node.ready_flaps
+            ),
+        ));
+    }
+
+    if node.unschedulable {
+        findings.push(Finding::new(
+            Severity::Info,
+            format!("Node {} is cordoned", node.name),
+        ));
+    }
+
+    findings
+}
+
+fn controller_findings(controller: &Controller) -> Vec<Finding> {
+    let name = format!("{} {}", controller.kind, controller.name);
+
+    let mut findings = controller
+        .conditions
+        .iter()
+        .filter(|condition| {
+            CONTROLLER_FAILURE_CONDITIONS.contains(&condition.type_.as_str())
+                && condition.status == "True"
+        })
+        .map(|condition| {
+            let reason = condition.reason.as_deref().unwrap_or(&condition.type_);
+            Finding::new(Severity::Critical, format!("{name} failed: {reason}"))
+                .detail(condition.message.as_deref())
+        })
+        .collect::<Vec<_>>();
+
+    if let (Some(desired), Some(ready)) = (controller.desired_replicas, controller.ready_replicas) {
+        if ready < desired {
+            findings.push(Finding::new(
+                Severity::Info,
+                format!("{name} has {ready} of {desired} replicas ready"),
+            ));
+        }
+    }
+
+    findings
+}
+
+/// The most recent event with the reason, about the container if given
+fn latest_event<'a>(
+    events: &'a [PodEvent],
+    reason: &str,
+    container: Option<&Container>,
+) -> Option<&'a PodEvent> {
+    events
+        .iter()
+        .filter(|event| event.reason.as_deref() == Some(reason))
+        .filter(|event| match container {
+            Some(container) => event
+                .field_path
+                .as_deref()
+                .is_some_and(|path| path.ends_with(&format!("{{{}}}", container.name))),
+            None => true,
+        })
+        .max_by_key(|event| event.last_seen)
+}
+
+async fn events(client: Client, pod: &Pod) -> Result<Vec<PodEvent>> {
+    let api: Api<K8sEvent> = Api::namespaced(client, &pod.namespace);
+    let params = ListParams::default().fields(&format!(
+        "involvedObject.kind=Pod,involvedObject.name={}",
+        pod.name
+    ));
+
+    let mut events = api
+        .list(&params)
+        .await?
+        .items
+        .into_iter()
+        .map(PodEvent::from)
+        .collect::<Vec<_>>();
+
+    events.sort_by_key(|event| event.last_seen);
+    Ok(events)
+}
+
+async fn node(client: Client, pod: &Pod) -> Result<Option<Node>> {
+    let Some(node_name) = &pod.node_name else {
+        return Ok(None);
+    };
+
+    let api: Api<K8sNode> = Api::all(client);
+    Ok(api.get_opt(node_name).await?.map(Node::from))
+}
+
+async fn controller(client: Client, pod: &Pod) -> Result<Option<Controller>> {
+    let Some(owner) = pod.controlled_by.iter().find(|owner| owner.controller) else {
+        return Ok(None);
+    };
+
+    let namespace = &pod.namespace;
+    let controller = match owner.kind.as_str() {
+        "ReplicaSet" => Api::<ReplicaSet>::namespaced(client, namespace)
+            .get_opt(&owner.name)
+            .await?
+            .map(Controller::from),
+        "StatefulSet" => Api::<StatefulSet>::namespaced(client, namespace)
+            .get_opt(&owner.name)
+            .await?
+            .map(Controller::from),
+        "DaemonSet" => Api::<DaemonSet>::namespaced(client, namespace)
+            .get_opt(&owner.name)
+            .await?
+            .map(Controller::from),
+        "Job" => Api::<Job>::namespaced(client, namespace)
+            .get_opt(&owner.name)
+            .await?
+            .map(Controller::from),
+        _ => None,
+    };
+
+    Ok(controller)
+}
+
+async fn claims(client: Client, pod: &Pod) -> Result<Vec<VolumeClaim>> {
+    let api: Api<K8sPersistentVolumeClaim> = Api::namespaced(client, &pod.namespace);
+
+    let mut claims = vec![];
+    for name in claim_names(pod) {
+        if let Some(claim) = api.get_opt(name).await? {
+            claims.push(VolumeClaim::from(claim));
+        }
+    }
+
+    Ok(claims)
+}
+
+impl From<K8sEvent> for PodEvent {
+    fn from(event: K8sEvent) -> Self {
+        let last_seen = event
+            .last_timestamp
+            .map(|t| t.0.timestamp())
+            .or_else(|| event.event_time.map(|t| t.0.timestamp()));
+
+        Self {
+            type_: event.type_.unwrap_or_default(),
+            reason: event.reason,
+            message: event.message,
+            field_path: event.involved_object.field_path,
+            count: event.count.unwrap_or(1),
+            last_seen,
+        }
+    }
+}
+
+impl From<K8sPersistentVolumeClaim> for VolumeClaim {
+    fn from(claim: K8sPersistentVolumeClaim) -> Self {
+        Self {
+            name: claim.metadata.name.unwrap_or_default(),
+            phase: claim
+                .status
+                .and_then(|status| status.phase)
+                .unwrap_or_default(),
+            storage_class: claim.spec.and_then(|spec| spec.storage_class_name),
+        }
+    }
+}
+
+macro_rules! controller_conditions {
+    ($conditions:expr) => {
+        $conditions
+            .unwrap_or_default()
+            .into_iter()
+            .map(|condition| ControllerCondition {
+                type_: condition.type_,
+                status: condition.status,
+                reason: condition.reason,
+                message: condition.message,
+            })
+            .collect()
+    };
+}
+
+impl From<ReplicaSet> for Controller {
+    fn from(replica_set: ReplicaSet) -> Self {
+        let status = replica_set.status.unwrap_or_default();
+
+        Self {
+            kind: "ReplicaSet".to_string(),
+            name: replica_set.metadata.name.unwrap_or_default(),
+            desired_replicas: replica_set.spec.and_then(|spec| spec.replicas),
+            ready_replicas: Some(status.ready_replicas.unwrap_or_default()),
+            conditions: controller_conditions!(status.conditions),
+        }
+    }
+}
+
+impl From<StatefulSet> for Controller {
+    fn from(stateful_set: StatefulSet) -> Self {
+        let status = stateful_set.status.unwrap_or_default();
+
+        Self {
+            kind: "StatefulSet".to_string(),
+            name: stateful_set.metadata.name.unwrap_or_default(),
+            desired_replicas: stateful_set.spec.and_then(|spec| spec.replicas),
+            ready_replicas: Some(status.ready_replicas.unwrap_or_default()),
+            conditions: controller_conditions!(status.conditions),
+        }
+    }
+}
+
+impl From<DaemonSet> for Controller {
+    fn from(daemon_set: DaemonSet) -> Self {
+        let status = daemon_set.status.unwrap_or_default();
+
+        Self {
+            kind: "DaemonSet".to_string(),
+            name: daemon_set.metadata.name.unwrap_or_default(),
+            desired_replicas: Some(status.desired_number_scheduled),
+            ready_replicas: Some(status.number_ready),
+            conditions: controller_conditions!(status.conditions),
+        }
+    }
+}
+
+/// Jobs run to completion, they have no replicas to be ready
+impl From<Job> for Controller {
+    fn from(job: Job) -> Self {
+        let status = job.status.unwrap_or_default();
+
+        Self {
+            kind: "Job".to_string(),
+            name: job.metadata.name.unwrap_or_default(),
+            desired_replicas: None,
+            ready_replicas: None,
+            conditions: controller_conditions!(status.conditions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::{
+        container_health::ContainerHealth,
+        node::NodeCondition,
+        pod::{ContainerKind, Scheduling},
+        pod_spec::{Probe, ResolvedEnvVar, Volume},
+    };
+
+    fn container(name: &str, status: HealthStatus) -> Container {
+        Container {
+            name: name.to_string(),
+            image: "registry.example.com/web:1.0".to_string(),
+            health: ContainerHealth {
+                status,
+                ..ContainerHealth::default()
+            },
+            ..Container::default()
+        }
+    }
+
+    fn pod(containers: Vec<Container>) -> Pod {
+        Pod {
+            name: "web-1".to_string(),
+            node_name: Some("node-1".to_string()),
+            scheduling: Scheduling {
+                scheduled: true,
+                ..Scheduling::default()
+            },
+            containers,
+            ..Pod::default()
+        }
+    }
+
+    fn event(reason: &str, message: &str, field_path: Option<&str>) -> PodEvent {
+        PodEvent {
+            type_: "Warning".to_string(),
+            reason: Some(reason.to_string()),
+            message: Some(message.to_string()),
+            field_path: field_path.map(str::to_string),
+            count: 1,
+            last_seen: Some(0),
+        }
+    }
+
+    fn titles(findings: Vec<Finding>) -> Vec<String> {
+        findings.into_iter().map(|finding| finding.title).collect()
+    }
+
+    #[test]
+    fn explains_unschedulable_pods() {
+        let mut pod = pod(vec![]);
+        pod.node_name = None;
+        pod.scheduling = Scheduling {
+            scheduled: false,
+            reason: Some("Unschedulable".to_string()),
+            message: Some(
+                "0/3 nodes are available: 1 node(s) had untolerated taint {dedicated: db}, 3 Insufficient cpu. preemption: 0/3 nodes are available: 3 No preemption victims found for incoming pod.."
+                    .to_string(),
+            ),
+            ..Scheduling::default()
+        };
+
+        assert_eq!(
+            titles(diagnose(&pod, &DiagnosisContext::default())),
+            vec![
+                "Can't be scheduled: not enough CPU on all 3 nodes",
+                "Can't be scheduled: 1 of 3 nodes have a taint the pod doesn't tolerate",
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_the_pull_failure_event_for_image_pull_back_offs() {
+        let pod = pod(vec![container(
+            "web",
+            HealthStatus::ImagePullBackOff {
+                reason: "ImagePullBackOff".to_string(),
+                message: Some("Back-off pulling image".to_string()),
+            },
+        )]);
+
+        let context = DiagnosisContext {
+            events: vec![event(
+                "Failed",
+                "Failed to pull image: 401 Unauthorized: authentication required",
+                Some("spec.containers{web}"),
+            )],
+            ..DiagnosisContext::default()
+        };
+
+        let findings = diagnose(&pod, &context);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0]
+            .title
+            .starts_with("Not authorized to pull image"));
+        assert_eq!(findings[0].container_name.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn explains_config_errors_with_the_missing_env() {
+        let pod = pod(vec![container(
+            "web",
+            HealthStatus::Error {
+                exit_code: None,
+                reason: Some("CreateContainerConfigError".to_string()),
+                message: Some("couldn't find key PORT in ConfigMap default/web".to_string()),
+            },
+        )]);
+
+        let context = DiagnosisContext {
+            env: vec![
+                ContainerEnv {
+                    container_name: "web".to_string(),
+                    container_kind: ContainerKind::Regular,
+                    env: vec![ResolvedEnvVar {
+                        name: "PORT".to_string(),
+                        error: Some("key port not found in configmap web".to_string()),
+                        ..ResolvedEnvVar::default()
+                    }],
+                },
+                // an init container with the same name isn't the one failing
+                ContainerEnv {
+                    container_name: "web".to_string(),
+                    container_kind: ContainerKind::Init,
+                    env: vec![ResolvedEnvVar {
+                        name: "MIGRATE".to_string(),
+                        error: Some("configmap migrations not found".to_string()),
+                        ..ResolvedEnvVar::default()
+                    }],
+                },
+            ],
+            ..DiagnosisContext::default()
+        };
+
+        let findings = diagnose(&pod, &context);
+        assert_eq!(
+            titles(findings.clone()),
+            vec!["Env var PORT: key port not found in configmap web"]
+        );
+        assert_eq!(findings[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn reports_what_could_not_be_loaded() {
+        let pod = pod(vec![container("web", HealthStatus::Healthy)]);
+        let context = DiagnosisContext {
+            unavailable: vec![UnavailableSource {
+                source: "node".to_string(),
+                error: "nodes \"node-1\" is forbidden".to_string(),
+            }],
+            ..DiagnosisContext::default()
+        };
+
+        let findings = diagnose(&pod, &context);
+        assert_eq!(titles(findings.clone()), vec!["Unable to load the node"]);
+        assert_eq!(findings[0].severity, Severity::Info);
+    }
+
+    #[test]
+    fn describes_the_failing_readiness_probe() {
+        let mut web = container("web", HealthStatus::NotReady);
+        web.readiness_probe = Some(Probe {
+            handler: ProbeHandler::HttpGet {
+                scheme: None,
+                host: None,
+                port: "8080".to_string(),
+                path: Some("/healthz".to_string()),
+            },
+            ..Probe::default()
+        });
+
+        let context = DiagnosisContext {
+            events: vec![event(
+                "Unhealthy",
+                "Readiness probe failed: HTTP probe failed with statuscode: 503",
+                Some("spec.containers{web}"),
+            )],
+            ..DiagnosisContext::default()
+        };
+
+        let findings = diagnose(&pod(vec![web]), &context);
+        assert_eq!(
+            titles(findings.clone()),
+            vec!["Readiness probe failing on port 8080/healthz"]
+        );
+        assert_eq!(
+            findings[0].detail.as_deref(),
+            Some("Readiness probe failed: HTTP probe failed with statuscode: 503")
+        );
+    }
+
+    #[test]
+    fn ranks_by_severity() {
+        let mut pod = pod(vec![container("web", HealthStatus::NotReady)]);
+        pod.volumes = vec![
+            Volume {
+                name: "data".to_string(),
+                source: VolumeSource::PersistentVolumeClaim {
+                    claim_name: "data".to_string(),
+                    read_only: false,
+                },
+            },
+            Volume {
+                name: "cache".to_string(),
+                source: VolumeSource::PersistentVolumeClaim {
+                    claim_name: "cache".to_string(),
+                    read_only: false,
+                },
+            },
+        ];
+
+        let context = DiagnosisContext {
+            node: Some(Node {
+                name: "node-1".to_string(),
+                conditions: vec![NodeCondition {
+                    name: "Ready".to_string(),
+                    status: "True".to_string(),
+                    ..NodeCondition::default()
+                }],
+                unschedulable: true,
+                ..Node::default()
+            }),
+            claims: vec![VolumeClaim {
+                name: "data".to_string(),
+                phase: "Pending".to_string(),
+                storage_class: Some("standard".to_string()),
+            }],
+            ..DiagnosisContext::default()
+        };
+
+        let findings = diagnose(&pod, &context);
+        assert_eq!(
+            findings
+                .iter()
+                .map(|finding| finding.severity)
+                .collect::<Vec<_>>(),
+            vec![
+                Severity::Critical,
+                Severity::Critical,
+                Severity::Warning,
+                Severity::Info
+            ]
+        );
+        assert_eq!(findings[0].title, "PersistentVolumeClaim data is Pending");
+        assert_eq!(
+            findings[1].title,
+            "PersistentVolumeClaim cache doesn't exist"
+        );
+    }
+}
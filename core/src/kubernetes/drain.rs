@@ -0,0 +1,427 @@
+//! Cordoning and draining nodes, like `kubectl cordon` and `kubectl drain`
+//!
+//! Pods are evicted through the eviction api so pod disruption budgets are respected, an eviction
+//! refused by a budget is retried until the budget allows it or the drain times out. DaemonSet pods
+//! are skipped as their controller would put them right back on the node, and mirror pods as only
+//! the kubelet can remove them.
+
+use std::time::Duration;
+
+use futures::{stream::FuturesUnordered, StreamExt};
+use k8s_openapi::api::core::v1::{Node as K8sNode, Pod as K8sPod};
+use kube::{
+    api::{DeleteParams, EvictParams, ListParams, Patch, PatchParams},
+    Api, Client,
+};
+use log::{debug, warn};
+use serde_json::json;
+use tokio::time::{sleep, Instant};
+use uniffi::Record;
+
+use super::{
+    node::NodeId,
+    pod::{Pod, PodId},
+};
+
+/// Wait between evictions refused by a pod disruption budget
+const EVICTION_RETRY: Duration = Duration::from_secs(5);
+
+/// Wait between checks that an evicted pod is gone
+const DELETION_POLL: Duration = Duration::from_secs(2);
+
+const DEFAULT_TIMEOUT_SECONDS: u64 = 300;
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Unable to update node {0}: {1}")]
+    PatchError(String, kube::Error),
+
+    #[error("Unable to list the pods on node {0}: {1}")]
+    ListError(String, kube::Error),
+
+    #[error("Nothing was evicted, {0}")]
+    Refused(String),
+
+    #[error("Timed out with {0} pods not evicted")]
+    TimedOut(usize),
+
+    #[error("{0} pods could not be evicted")]
+    EvictionFailed(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Record)]
+pub struct DrainOptions {
+    /// Pods not evicted after this long are given up on, the node stays cordoned
+    pub timeout_seconds: u64,
+    /// Evict pods using `emptyDir` volumes, their data is lost
+    pub delete_emptydir_data: bool,
+    /// Evict pods without a controller, nothing recreates them on another node
+    pub delete_unmanaged: bool,
+    /// Overrides the grace period of the pods, `None` uses their own
+    pub grace_period_seconds: Option<u32>,
+}
+
+impl Default for DrainOptions {
+    fn default() -> Self {
+        Self {
+            timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+            delete_emptydir_data: false,
+            delete_unmanaged: false,
+            grace_period_seconds: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum DrainPodStatus {
+    Pending,
+    Evicting,
+
+    /// A pod disruption budget refused the eviction, it is retried
+    Blocked {
+        reason: String,
+    },
+
+    /// Evicted and terminated
+    Evicted,
+
+    /// Left on the node, ex: DaemonSet pods
+    Skipped {
+        reason: String,
+    },
+
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Record)]
+pub struct DrainPodProgress {
+    pub pod_id: PodId,
+    pub namespace: String,
+    pub status: DrainPodStatus,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Plan {
+    Evict,
+    Skip(String),
+    Refuse(String),
+}
+
+enum Outcome {
+    Evicted,
+    TimedOut,
+    Failed,
+}
+
+#[uniffi::export]
+pub fn default_drain_options() -> DrainOptions {
+    DrainOptions::default()
+}
+
+/// Cordons or uncordons the node, a cordoned node gets no new pods
+pub async fn set_unschedulable(
+    client: Client,
+    node_id: &NodeId,
+    unschedulable: bool,
+) -> Result<(), Error> {
+    let api: Api<K8sNode> = Api::all(client);
+    let patch = json!({ "spec": { "unschedulable": unschedulable } });
+
+    api.patch(
+        &node_id.raw_value,
+        &PatchParams::default(),
+        &Patch::Merge(&patch),
+    )
+    .await
+    .map_err(|error| Error::PatchError(node_id.raw_value.clone(), error))?;
+
+    Ok(())
+}
+
+/// Cordons the node and evicts its pods, reporting the progress of every pod on the node
+///
+/// Like `kubectl drain`, nothing is evicted if any pod needs an option that isn't set
+pub async fn drain(
+    client: Client,
+    node_id: &NodeId,
+    options: &DrainOptions,
+    on_progress: impl Fn(DrainPodProgress),
+) -> Result<(), Error> {
+    debug!("draining node {}: {options:?}", node_id.raw_value);
+    set_unschedulable(client.clone(), node_id, true).await?;
+
+    let api: Api<K8sPod> = Api::all(client.clone());
+    let params = ListParams::default().fields(&format!("spec.nodeName={}", node_id.raw_value));
+    let pods = api
+        .list(&params)
+        .await
+        .map_err(|error| Error::ListError(node_id.raw_value.clone(), error))?
+        .items;
+
+    let mut evict = vec![];
+    let mut refused = vec![];
+
+    for pod in pods {
+        let uses_empty_dir = pod
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.volumes.as_ref())
+            .is_some_and(|volumes| volumes.iter().any(|volume| volume.empty_dir.is_some()));
+
+        let uid = pod.metadata.uid.clone();
+        let pod = Pod::from(pod);
+        let report = |status| on_progress(progress(&pod, status));
+
+        match plan(&pod, uses_empty_dir, options) {
+            Plan::Evict => {
+                report(DrainPodStatus::Pending);
+                evict.push((pod, uid));
+            }
+            Plan::Skip(reason) => report(DrainPodStatus::Skipped { reason }),
+            Plan::Refuse(error) => {
+                report(DrainPodStatus::Failed {
+                    error: error.clone(),
+                });
+                refused.push(format!("{}: {error}", pod.name));
+            }
+        }
+    }
+
+    if !refused.is_empty() {
+        return Err(Error::Refused(refused.join(", ")));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(options.timeout_seconds);
+    let outcomes = evict
+        .iter()
+        .map(|(pod, uid)| evict_pod(&client, pod, uid, options, deadline, &on_progress))
+        .collect::<FuturesUnordered<_>>()
+        .collect::<Vec<_>>()
+        .await;
+
+    let timed_out = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, Outcome::TimedOut))
+        .count();
+
+    let failed = outcomes
+        .iter()
+        .filter(|outcome| matches!(outcome, Outcome::Failed))
+        .count();
+
+    match (timed_out, failed) {
+        (0, 0) => Ok(()),
+        (0, failed) => Err(Error::EvictionFailed(failed)),
+        (timed_out, failed) => Err(Error::TimedOut(timed_out + failed)),
+    }
+}
+
+fn plan(pod: &Pod, uses_empty_dir: bool, options: &DrainOptions) -> Plan {
+    if pod.is_terminated() {
+        return Plan::Skip("Already finished".to_string());
+    }
+
+    if pod.is_mirror_pod() {
+        return Plan::Skip("Static pod managed by the kubelet".to_string());
+    }
+
+    if pod.is_daemon_set_pod() {
+        return Plan::Skip("Managed by a DaemonSet".to_string());
+    }
+
+    let managed = pod.controlled_by.iter().any(|owner| owner.controller);
+    if !managed && !options.delete_unmanaged {
+        return Plan::Refuse("Not managed by a controller, it would not be recreated".to_string());
+    }
+
+    if uses_empty_dir && !options.delete_emptydir_data {
+        return Plan::Refuse("Uses emptyDir volumes, their data would be lost".to_string());
+    }
+
+    Plan::Evict
+}
+
+fn progress(pod: &Pod, status: DrainPodStatus) -> DrainPodProgress {
+    DrainPodProgress {
+        pod_id: pod.id.clone(),
+        namespace: pod.namespace.clone(),
+        status,
+    }
+}
+
+async fn evict_pod(
+    client: &Client,
+    pod: &Pod,
+    uid: &Option<String>,
+    options: &DrainOptions,
+    deadline: Instant,
+    on_progress: &impl Fn(DrainPodProgress),
+) -> Outcome {
+    let api: Api<K8sPod> = Api::namespaced(client.clone(), &pod.namespace);
+    let report = |status| on_progress(progress(pod, status));
+
+    let params = EvictParams {
+        delete_options: Some(DeleteParams {
+            grace_period_seconds: options.grace_period_seconds,
+            ..DeleteParams::default()
+        }),
+        ..EvictParams::default()
+    };
+
+    report(DrainPodStatus::Evicting);
+
+    loop {
+        match api.evict(&pod.name, &params).await {
+            Ok(_) => break,
+
+            // deleted since the pods were listed
+            Err(kube::Error::Api(response)) if response.code == 404 => {
+                report(DrainPodStatus::Evicted);
+                return Outcome::Evicted;
+            }
+
+            // too many pods covered by the same budget are down, wait for them to come back
+            Err(kube::Error::Api(response)) if response.code == 429 => {
+                report(DrainPodStatus::Blocked {
+                    reason: response.message,
+                });
+            }
+
+            Err(error) => {
+                report(DrainPodStatus::Failed {
+                    error: error.to_string(),
+                });
+                return Outcome::Failed;
+            }
+        }
+
+        if Instant::now() + EVICTION_RETRY > deadline {
+            report(DrainPodStatus::Failed {
+                error: "Timed out waiting for the disruption budget".to_string(),
+            });
+            return Outcome::TimedOut;
+        }
+
+        sleep(EVICTION_RETRY).await;
+    }
+
+    // the eviction only starts the graceful deletion, a pod with the same name but another uid is
+    // a replacement
+    loop {
+        match api.get_opt(&pod.name).await {
+            Ok(None) => break,
+            Ok(Some(current)) if &current.metadata.uid != uid => break,
+            Ok(Some(_)) => {}
+            Err(error) => warn!("failed to check if pod {} is gone: {error}", pod.id),
+        }
+
+        if Instant::now() >= deadline {
+            report(DrainPodStatus::Failed {
+                error: "Timed out waiting for the pod to terminate".to_string(),
+            });
+            return Outcome::TimedOut;
+        }
+
+        sleep(DELETION_POLL).await;
+    }
+
+    report(DrainPodStatus::Evicted);
+    Outcome::Evicted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::{core::OwnerReference, pod::Phase};
+
+    fn owner(kind: &str) -> OwnerReference {
+        OwnerReference {
+            api_version: "apps/v1".to_string(),
+            controller: true,
+            kind: kind.to_string(),
+            name: "owner".to_string(),
+            ..OwnerReference::default()
+        }
+    }
+
+    fn pod(owner_kind: Option<&str>) -> Pod {
+        Pod {
+            name: "pod-1".to_string(),
+            phase: Phase::Running,
+            controlled_by: owner_kind.map(owner).into_iter().collect(),
+            ..Pod::default()
+        }
+    }
+
+    fn is_skipped(plan: Plan) -> bool {
+        matches!(plan, Plan::Skip(_))
+    }
+
+    fn is_refused(plan: Plan) -> bool {
+        matches!(plan, Plan::Refuse(_))
+    }
+
+    #[test]
+    fn evicts_managed_pods() {
+        let options = DrainOptions::default();
+        assert_eq!(plan(&pod(Some("ReplicaSet")), false, &options), Plan::Evict);
+    }
+
+    #[test]
+    fn skips_daemon_set_mirror_and_finished_pods() {
+        let options = DrainOptions::default();
+
+        assert!(is_skipped(plan(&pod(Some("DaemonSet")), false, &options)));
+
+        let mut mirror = pod(None);
+        mirror.annotations.insert(
+            "kubernetes.io/config.mirror".to_string(),
+            "hash".to_string(),
+        );
+        assert!(is_skipped(plan(&mirror, false, &options)));
+
+        for phase in [Phase::Succeeded, Phase::Failed] {
+            let finished = Pod { phase, ..pod(None) };
+            assert!(is_skipped(plan(&finished, true, &options)));
+        }
+    }
+
+    #[test]
+    fn refuses_unmanaged_pods_unless_allowed() {
+        let unmanaged = pod(None);
+        assert!(is_refused(plan(
+            &unmanaged,
+            false,
+            &DrainOptions::default()
+        )));
+
+        let options = DrainOptions {
+            delete_unmanaged: true,
+            ..DrainOptions::default()
+        };
+        assert_eq!(plan(&unmanaged, false, &options), Plan::Evict);
+
+        // an owner that isn't the controller doesn't manage the pod
+        let mut not_controller = pod(Some("ReplicaSet"));
+        not_controller.controlled_by[0].controller = false;
+        assert!(is_refused(plan(
+            &not_controller,
+            false,
+            &DrainOptions::default()
+        )));
+    }
+
+    #[test]
+    fn refuses_empty_dir_pods_unless_allowed() {
+        let managed = pod(Some("ReplicaSet"));
+        assert!(is_refused(plan(&managed, true, &DrainOptions::default())));
+
+        let options = DrainOptions {
+            delete_emptydir_data: true,
+            ..DrainOptions::default()
+        };
+        assert_eq!(plan(&managed, true, &options), Plan::Evict);
+    }
+}
@@ -0,0 +1,850 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{Read, Write},
+    sync::Arc,
+};
+
+use act_zero::{call, WeakAddr};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, SecondsFormat, Utc};
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use k8s_openapi::{
+    api::core::v1::Secret as K8sSecret, apimachinery::pkg::apis::meta::v1::ObjectMeta, ByteString,
+};
+use kube::{
+    api::{ApiResource, DeleteParams, DynamicObject, ListParams, Patch, PatchParams, PostParams},
+    core::GroupVersionKind,
+    discovery::{self, Scope},
+    Api, Client,
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uniffi::Record;
+
+use crate::{query::Selectors, view_models::helm::HelmViewModel};
+
+use super::{
+    cache::{CachedResource, Change, ResourceCache},
+    manifest::{self, ManifestObject, ManifestObjectDiff, ManifestObjectKey},
+};
+
+/// Secret type helm 3 uses for its release storage
+pub const RELEASE_SECRET_TYPE: &str = "helm.sh/release.v1";
+
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+
+/// Field manager used when applying a rollback, the same one helm uses for its own writes
+const FIELD_MANAGER: &str = "helm";
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("secret {0} has no release data")]
+    MissingReleaseData(String),
+
+    #[error("unable to base64 decode release: {0}")]
+    Base64(#[from] base64::DecodeError),
+
+    #[error("unable to decompress release: {0}")]
+    Gzip(#[from] std::io::Error),
+
+    #[error("unable to parse release json: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unable to read release values: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("release {0} revision {1} not found: {2}")]
+    RevisionNotFound(HelmReleaseId, i32, kube::Error),
+
+    #[error("release {0} has no revision {1}")]
+    RevisionMissing(HelmReleaseId, i32),
+
+    #[error("unable to list helm releases: {0}")]
+    ListError(kube::Error),
+
+    #[error("unable to find api resource {0}: {1}")]
+    DiscoveryError(String, kube::Error),
+
+    #[error("unable to read {0}: {1}")]
+    ObjectReadError(String, kube::Error),
+
+    #[error("unable to apply {0}: {1}")]
+    ApplyError(String, kube::Error),
+
+    #[error("unable to write release revision {0}: {1}")]
+    StorageError(i32, kube::Error),
+}
+
+uniffi::custom_newtype!(HelmReleaseId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct HelmReleaseId(String);
+
+impl HelmReleaseId {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        Self(format!("{namespace}/{name}"))
+    }
+}
+
+/// A release with the details of its latest revision
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HelmRelease {
+    pub id: HelmReleaseId,
+    pub name: String,
+    pub namespace: String,
+    pub chart_name: String,
+    pub chart_version: String,
+    pub app_version: Option<String>,
+    pub revision: i32,
+    pub status: String,
+    pub updated_at: Option<i64>,
+    pub description: Option<String>,
+    /// every stored revision, newest first
+    pub history: Vec<HelmReleaseRevision>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HelmReleaseRevision {
+    pub revision: i32,
+    pub status: String,
+    pub chart_name: String,
+    pub chart_version: String,
+    pub app_version: Option<String>,
+    pub updated_at: Option<i64>,
+    pub description: Option<String>,
+}
+
+/// The rendered manifest and user supplied values of a single revision
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HelmRevisionDetail {
+    pub release_id: HelmReleaseId,
+    pub revision: i32,
+    pub manifest: String,
+    /// user supplied values as yaml, the chart defaults are not included
+    pub values: String,
+    pub notes: Option<String>,
+}
+
+/// A chart and the releases deployed from it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HelmChart {
+    pub name: String,
+    pub versions: Vec<String>,
+    pub releases: Vec<HelmReleaseId>,
+}
+
+/// Release as stored by helm, unknown fields are kept so the release can be written back as is
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseData {
+    pub name: String,
+    #[serde(default)]
+    pub info: ReleaseInfo,
+    #[serde(default)]
+    pub chart: ChartData,
+    #[serde(default)]
+    pub config: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub manifest: String,
+    pub version: i32,
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_deployed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_deployed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChartData {
+    #[serde(default)]
+    pub metadata: ChartMetadata,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChartMetadata {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(
+        rename = "appVersion",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub app_version: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Decodes a release from the `release` key of a release secret: base64, then gzip, then json
+pub fn decode_release(data: &[u8]) -> Result<ReleaseData, Error> {
+    let decoded = STANDARD.decode(data)?;
+
+    let json = if decoded.starts_with(&GZIP_MAGIC) {
+        let mut json = Vec::new();
+        GzDecoder::new(decoded.as_slice()).read_to_end(&mut json)?;
+        json
+    } else {
+        // helm 3 always compresses, but older releases written by other tools might not be
+        decoded
+    };
+
+    Ok(serde_json::from_slice(&json)?)
+}
+
+pub fn decode_release_secret(secret: &K8sSecret) -> Result<ReleaseData, Error> {
+    let secret_name = secret.metadata.name.clone().unwrap_or_default();
+
+    let data = secret
+        .data
+        .as_ref()
+        .and_then(|data| data.get("release"))
+        .ok_or(Error::MissingReleaseData(secret_name))?;
+
+    decode_release(&data.0)
+}
+
+/// Encodes a release the way helm stores it: json, then gzip, then base64
+pub fn encode_release(release: &ReleaseData) -> Result<Vec<u8>, Error> {
+    let json = serde_json::to_vec(release)?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&json)?;
+    let compressed = encoder.finish()?;
+
+    Ok(STANDARD.encode(compressed).into_bytes())
+}
+
+/// Builds the secret storing a release revision, with the labels helm uses to query its storage
+pub fn encode_release_secret(
+    release: &ReleaseData,
+    timestamp_label: (&str, i64),
+) -> Result<K8sSecret, Error> {
+    let (timestamp_key, timestamp) = timestamp_label;
+
+    let labels = [
+        ("name", release.name.clone()),
+        ("owner", "helm".to_string()),
+        ("status", release.info.status.clone()),
+        ("version", release.version.to_string()),
+        (timestamp_key, timestamp.to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+
+    Ok(K8sSecret {
+        metadata: ObjectMeta {
+            name: Some(release_secret_name(&release.name, release.version)),
+            namespace: Some(release.namespace.clone()),
+            labels: Some(labels),
+            ..Default::default()
+        },
+        type_: Some(RELEASE_SECRET_TYPE.to_string()),
+        data: Some(BTreeMap::from([(
+            "release".to_string(),
+            ByteString(encode_release(release)?),
+        )])),
+        ..Default::default()
+    })
+}
+
+/// Name helm gives the secret storing a release revision
+pub fn release_secret_name(name: &str, revision: i32) -> String {
+    format!("sh.helm.release.v1.{name}.v{revision}")
+}
+
+fn parse_time(time: &Option<String>) -> Option<i64> {
+    let time = time.as_deref()?;
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|t| t.timestamp())
+}
+
+impl ReleaseData {
+    pub fn id(&self) -> HelmReleaseId {
+        HelmReleaseId::new(&self.namespace, &self.name)
+    }
+
+    pub fn revision(&self) -> HelmReleaseRevision {
+        HelmReleaseRevision {
+            revision: self.version,
+            status: self.info.status.clone(),
+            chart_name: self.chart.metadata.name.clone(),
+            chart_version: self.chart.metadata.version.clone(),
+            app_version: self.chart.metadata.app_version.clone(),
+            updated_at: parse_time(&self.info.last_deployed)
+                .or_else(|| parse_time(&self.info.first_deployed)),
+            description: self.info.description.clone(),
+        }
+    }
+
+    pub fn manifest_objects(&self) -> Result<Vec<ManifestObject>, Error> {
+        Ok(manifest::parse(&self.manifest)?)
+    }
+
+    pub fn detail(&self) -> Result<HelmRevisionDetail, Error> {
+        let values = match &self.config {
+            Some(config) if !config.is_empty() => serde_yaml::to_string(config)?,
+            _ => String::new(),
+        };
+
+        Ok(HelmRevisionDetail {
+            release_id: self.id(),
+            revision: self.version,
+            manifest: self.manifest.clone(),
+            values,
+            notes: self.info.notes.clone(),
+        })
+    }
+}
+
+impl HelmRelease {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    /// Builds the release from its revisions, returns `None` if there are no revisions
+    pub fn from_revisions(
+        namespace: &str,
+        name: &str,
+        mut history: Vec<HelmReleaseRevision>,
+    ) -> Option<Self> {
+        history.sort_by(|a, b| b.revision.cmp(&a.revision));
+        let latest = history.first()?.clone();
+
+        Some(Self {
+            id: HelmReleaseId::new(namespace, name),
+            name: name.to_string(),
+            namespace: namespace.to_string(),
+            chart_name: latest.chart_name,
+            chart_version: latest.chart_version,
+            app_version: latest.app_version,
+            revision: latest.revision,
+            status: latest.status,
+            updated_at: latest.updated_at,
+            description: latest.description,
+            history,
+        })
+    }
+
+    /// Adds or replaces a revision, returns the updated release
+    pub fn with_revision(self, revision: HelmReleaseRevision) -> Self {
+        let mut history = self.history;
+        history.retain(|existing| existing.revision != revision.revision);
+        history.push(revision);
+
+        Self::from_revisions(&self.namespace, &self.name, history)
+            .expect("history has at least one revision")
+    }
+
+    /// Removes a revision, returns `None` if it was the last one
+    pub fn without_revision(self, revision: i32) -> Option<Self> {
+        let mut history = self.history;
+        history.retain(|existing| existing.revision != revision);
+
+        Self::from_revisions(&self.namespace, &self.name, history)
+    }
+}
+
+/// Charts deployed in the cluster, grouped from the latest revision of each release
+pub fn charts<'a>(releases: impl IntoIterator<Item = &'a HelmRelease>) -> Vec<HelmChart> {
+    let mut charts: BTreeMap<&str, HelmChart> = BTreeMap::new();
+
+    for release in releases {
+        let chart = charts
+            .entry(release.chart_name.as_str())
+            .or_insert_with(|| HelmChart {
+                name: release.chart_name.clone(),
+                ..Default::default()
+            });
+
+        if !chart.versions.contains(&release.chart_version) {
+            chart.versions.push(release.chart_version.clone());
+        }
+
+        chart.releases.push(release.id.clone());
+    }
+
+    charts
+        .into_values()
+        .map(|mut chart| {
+            chart.versions.sort();
+            chart.releases.sort();
+            chart
+        })
+        .collect()
+}
+
+#[uniffi::export]
+pub fn helm_release_preview() -> HelmRelease {
+    HelmRelease::preview()
+}
+
+/// Release secrets are cached as objects and decoded into releases when read
+impl CachedResource for K8sSecret {
+    type History = ();
+}
+
+/// Selects the secrets helm stores its releases in
+pub fn release_secret_selectors() -> Selectors {
+    Selectors {
+        labels: Some("owner=helm".to_string()),
+        fields: Some(format!("type={RELEASE_SECRET_TYPE}")),
+    }
+}
+
+/// Releases stored in the secrets, secrets which can't be decoded are skipped
+pub fn releases(secrets: &[Arc<K8sSecret>]) -> HashMap<HelmReleaseId, HelmRelease> {
+    let mut revisions: HashMap<(String, String), Vec<HelmReleaseRevision>> = HashMap::new();
+
+    for secret in secrets {
+        match decode_release_secret(secret) {
+            Ok(release) => revisions
+                .entry((release.namespace.clone(), release.name.clone()))
+                .or_default()
+                .push(release.revision()),
+
+            Err(error) => warn!(
+                "skipping helm release secret {:?}: {error}",
+                secret.metadata.name
+            ),
+        }
+    }
+
+    revisions
+        .into_iter()
+        .filter_map(|((namespace, name), history)| {
+            HelmRelease::from_revisions(&namespace, &name, history)
+        })
+        .map(|release| (release.id.clone(), release))
+        .collect()
+}
+
+pub async fn get_revision(
+    client: Client,
+    namespace: &str,
+    name: &str,
+    revision: i32,
+) -> Result<ReleaseData, Error> {
+    let secrets_api: Api<K8sSecret> = Api::namespaced(client, namespace);
+
+    let secret = secrets_api
+        .get(&release_secret_name(name, revision))
+        .await
+        .map_err(|error| {
+            Error::RevisionNotFound(HelmReleaseId::new(namespace, name), revision, error)
+        })?;
+
+    decode_release_secret(&secret)
+}
+
+/// Every stored revision of a release, oldest first
+pub async fn get_revisions(
+    client: Client,
+    namespace: &str,
+    name: &str,
+) -> Result<Vec<ReleaseData>, Error> {
+    let secrets_api: Api<K8sSecret> = Api::namespaced(client, namespace);
+    let list_params = ListParams::default()
+        .fields(&format!("type={RELEASE_SECRET_TYPE}"))
+        .labels(&format!("name={name},owner=helm"));
+
+    let secrets = secrets_api
+        .list(&list_params)
+        .await
+        .map_err(Error::ListError)?;
+
+    let mut revisions = secrets
+        .iter()
+        .filter_map(|secret| decode_release_secret(secret).ok())
+        .collect::<Vec<_>>();
+
+    revisions.sort_by_key(|release| release.version);
+
+    Ok(revisions)
+}
+
+/// Diffs the manifests of two revisions of a release
+pub async fn revision_diff(
+    client: Client,
+    namespace: &str,
+    name: &str,
+    from: i32,
+    to: i32,
+) -> Result<Vec<ManifestObjectDiff>, Error> {
+    let from = get_revision(client.clone(), namespace, name, from).await?;
+    let to = get_revision(client, namespace, name, to).await?;
+
+    Ok(manifest::diff(
+        &from.manifest_objects()?,
+        &to.manifest_objects()?,
+    ))
+}
+
+/// Diffs the manifest of a revision against the objects in the cluster, showing drift
+pub async fn live_diff(
+    client: Client,
+    namespace: &str,
+    name: &str,
+    revision: i32,
+) -> Result<Vec<ManifestObjectDiff>, Error> {
+    let release = get_revision(client.clone(), namespace, name, revision).await?;
+    let mut resolver = ObjectApiResolver::new(client, namespace);
+
+    let mut desired = release.manifest_objects()?;
+    let mut live = Vec::with_capacity(desired.len());
+
+    for object in &mut desired {
+        let (api, namespace) = resolver.api(&object.key).await?;
+        // match the live object, which always has its namespace set
+        object.key.namespace = namespace;
+
+        let live_object = api
+            .get_opt(&object.key.name)
+            .await
+            .map_err(|error| Error::ObjectReadError(object_label(&object.key), error))?
+            .map(serde_json::to_value)
+            .transpose()?;
+
+        live.push((object.key.clone(), live_object));
+    }
+
+    Ok(manifest::diff_live(&desired, &live))
+}
+
+/// Rolls a release back to a previous revision, mirroring `helm rollback`.
+///
+/// The manifest of the revision is applied with server side apply, objects only in the current
+/// revision are deleted, and a new revision is stored. Chart hooks are not run
+pub async fn rollback(
+    client: Client,
+    namespace: &str,
+    name: &str,
+    revision: i32,
+) -> Result<ReleaseData, Error> {
+    let revisions = get_revisions(client.clone(), namespace, name).await?;
+
+    let current = revisions
+        .last()
+        .cloned()
+        .ok_or_else(|| Error::RevisionMissing(HelmReleaseId::new(namespace, name), revision))?;
+
+    let previous = revisions
+        .iter()
+        .find(|release| release.version == revision)
+        .cloned()
+        .ok_or_else(|| Error::RevisionMissing(HelmReleaseId::new(namespace, name), revision))?;
+
+    let now = Utc::now();
+    let secrets_api: Api<K8sSecret> = Api::namespaced(client.clone(), namespace);
+
+    let mut target = previous.clone();
+    target.version = current.version + 1;
+    target.info.first_deployed = current.info.first_deployed.clone();
+    target.info.last_deployed = Some(now.to_rfc3339_opts(SecondsFormat::Nanos, true));
+    target.info.description = Some(format!("Rollback to {revision}"));
+    target.info.status = "pending-rollback".to_string();
+
+    let secret = encode_release_secret(&target, ("createdAt", now.timestamp()))?;
+    secrets_api
+        .create(&PostParams::default(), &secret)
+        .await
+        .map_err(|error| Error::StorageError(target.version, error))?;
+
+    if let Err(error) = apply_rollback(client, namespace, &current, &target).await {
+        target.info.status = "failed".to_string();
+        target.info.description = Some(format!("Rollback \"{revision}\" failed: {error}"));
+        update_release(&secrets_api, &target).await?;
+
+        return Err(error);
+    }
+
+    // only one revision can be deployed, helm marks the others as superseded, a failure here
+    // must not leave the new revision pending so it is finalized before returning the error
+    let mut supersede_error = None;
+    for mut release in revisions {
+        if release.info.status == "deployed" {
+            release.info.status = "superseded".to_string();
+            if let Err(error) = update_release(&secrets_api, &release).await {
+                warn!("unable to supersede revision {}: {error}", release.version);
+                supersede_error.get_or_insert(error);
+            }
+        }
+    }
+
+    target.info.status = "deployed".to_string();
+    update_release(&secrets_api, &target).await?;
+
+    match supersede_error {
+        Some(error) => Err(error),
+        None => Ok(target),
+    }
+}
+
+async fn apply_rollback(
+    client: Client,
+    namespace: &str,
+    current: &ReleaseData,
+    target: &ReleaseData,
+) -> Result<(), Error> {
+    let mut resolver = ObjectApiResolver::new(client, namespace);
+    let patch_params = PatchParams::apply(FIELD_MANAGER).force();
+
+    let target_objects = target.manifest_objects()?;
+    let mut applied = Vec::with_capacity(target_objects.len());
+
+    for object in target_objects {
+        let (api, namespace) = resolver.api(&object.key).await?;
+        let label = object_label(&object.key);
+
+        api.patch(
+            &object.key.name,
+            &patch_params,
+            &Patch::Apply(&object.value),
+        )
+        .await
+        .map_err(|error| Error::ApplyError(label, error))?;
+
+        applied.push((object.key.kind, namespace, object.key.name));
+    }
+
+    for object in current.manifest_objects()? {
+        let (api, namespace) = resolver.api(&object.key).await?;
+
+        let key = (object.key.kind.clone(), namespace, object.key.name.clone());
+        if applied.contains(&key) {
+            continue;
+        }
+
+        debug!("deleting {} removed by rollback", object_label(&object.key));
+        if let Err(error) = api
+            .delete(&object.key.name, &DeleteParams::background())
+            .await
+        {
+            warn!("unable to delete {}: {error}", object_label(&object.key));
+        }
+    }
+
+    Ok(())
+}
+
+async fn update_release(secrets_api: &Api<K8sSecret>, release: &ReleaseData) -> Result<(), Error> {
+    let secret = encode_release_secret(release, ("modifiedAt", Utc::now().timestamp()))?;
+
+    secrets_api
+        .replace(
+            &release_secret_name(&release.name, release.version),
+            &PostParams::default(),
+            &secret,
+        )
+        .await
+        .map_err(|error| Error::StorageError(release.version, error))?;
+
+    Ok(())
+}
+
+fn object_label(key: &ManifestObjectKey) -> String {
+    format!("{} {}", key.kind, key.name)
+}
+
+/// Finds the api for manifest objects, caching discovery per kind
+struct ObjectApiResolver {
+    client: Client,
+    release_namespace: String,
+    resources: HashMap<(String, String), (ApiResource, Scope)>,
+}
+
+impl ObjectApiResolver {
+    fn new(client: Client, release_namespace: &str) -> Self {
+        Self {
+            client,
+            release_namespace: release_namespace.to_string(),
+            resources: HashMap::new(),
+        }
+    }
+
+    /// The api for the object and the namespace it lives in, objects without a namespace are
+    /// installed in the release namespace, like helm does
+    async fn api(
+        &mut self,
+        key: &ManifestObjectKey,
+    ) -> Result<(Api<DynamicObject>, Option<String>), Error> {
+        let cache_key = (key.api_version.clone(), key.kind.clone());
+
+        if !self.resources.contains_key(&cache_key) {
+            let (group, version) = key
+                .api_version
+                .split_once('/')
+                .unwrap_or(("", key.api_version.as_str()));
+
+            let gvk = GroupVersionKind::gvk(group, version, &key.kind);
+            let (resource, capabilities) = discovery::pinned_kind(&self.client, &gvk)
+                .await
+                .map_err(|error| {
+                    Error::DiscoveryError(format!("{}/{}", key.api_version, key.kind), error)
+                })?;
+
+            self.resources
+                .insert(cache_key.clone(), (resource, capabilities.scope));
+        }
+
+        let (resource, scope) = &self.resources[&cache_key];
+
+        match scope {
+            Scope::Cluster => Ok((Api::all_with(self.client.clone(), resource), None)),
+            Scope::Namespaced => {
+                let namespace = key
+                    .namespace
+                    .clone()
+                    .unwrap_or_else(|| self.release_namespace.clone());
+
+                let api = Api::namespaced_with(self.client.clone(), &namespace, resource);
+                Ok((api, Some(namespace)))
+            }
+        }
+    }
+}
+
+/// Forwards the release secrets changed in the shared cache to the view model until the task is
+/// aborted
+pub async fn watch(
+    addr: WeakAddr<HelmViewModel>,
+    cache: Arc<ResourceCache<K8sSecret>>,
+) -> eyre::Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+    debug!("watching helm release secrets for {:?}", cache.cluster_id());
+
+    // subscribe before reading the store so no change is missed in between
+    let mut changes = cache.subscribe();
+    call!(addr.connection_changed(cache.connection())).await?;
+
+    if cache.is_ready() {
+        call!(addr.replaced(releases(&cache.objects()))).await?;
+    }
+
+    loop {
+        match changes.recv().await {
+            Ok(Change::Applied(secret)) => match decode_release_secret(&secret) {
+                Ok(release) => call!(addr.applied(release.id(), release.revision())).await?,
+                Err(error) => warn!("unable to decode helm release secret: {error}"),
+            },
+            Ok(Change::Deleted(secret)) => match decode_release_secret(&secret) {
+                Ok(release) => call!(addr.deleted(release.id(), release.version)).await?,
+                Err(error) => warn!("unable to decode helm release secret: {error}"),
+            },
+            Ok(Change::Restarted) => {
+                debug!("helm release secrets re-listed on {:?}", cache.cluster_id());
+                call!(addr.replaced(releases(&cache.objects()))).await?;
+            }
+            Ok(Change::Connection(state)) => call!(addr.connection_changed(state)).await?,
+            Ok(Change::Listed(_)) => {}
+            Err(RecvError::Lagged(skipped)) if cache.is_ready() => {
+                debug!("helm release watcher lagged by {skipped} changes, reloading");
+                call!(addr.replaced(releases(&cache.objects()))).await?;
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    const RELEASE_JSON: &str = r#"{
+        "name": "web",
+        "namespace": "apps",
+        "version": 3,
+        "info": {
+            "first_deployed": "2023-08-01T10:00:00.123456-04:00",
+            "last_deployed": "2023-08-02T10:00:00Z",
+            "deleted": "",
+            "description": "Upgrade complete",
+            "status": "deployed"
+        },
+        "chart": {
+            "metadata": {"name": "nginx", "version": "15.1.0", "appVersion": "1.25.1"},
+            "templates": []
+        },
+        "config": {"replicaCount": 2},
+        "manifest": "---\napiVersion: v1\nkind: Service\n",
+        "hooks": []
+    }"#;
+
+    fn encode(json: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        STANDARD.encode(compressed).into_bytes()
+    }
+
+    #[test]
+    fn test_decode_release() {
+        let release = decode_release(&encode(RELEASE_JSON)).unwrap();
+
+        assert_eq!(release.id(), HelmReleaseId::new("apps", "web"));
+        assert_eq!(
+            release.chart.metadata.app_version.as_deref(),
+            Some("1.25.1")
+        );
+        assert!(release.extra.contains_key("hooks"));
+
+        let revision = release.revision();
+        assert_eq!(revision.revision, 3);
+        assert_eq!(revision.status, "deployed");
+        assert_eq!(revision.chart_version, "15.1.0");
+        assert_eq!(revision.updated_at, Some(1690970400));
+
+        let detail = release.detail().unwrap();
+        assert_eq!(detail.values, "replicaCount: 2\n");
+    }
+
+    #[test]
+    fn test_release_history() {
+        let revision = |revision, status: &str| HelmReleaseRevision {
+            revision,
+            status: status.to_string(),
+            ..Default::default()
+        };
+
+        let release = HelmRelease::from_revisions(
+            "apps",
+            "web",
+            vec![revision(1, "superseded"), revision(2, "deployed")],
+        )
+        .unwrap();
+
+        let release = release.with_revision(revision(3, "pending-upgrade"));
+        assert_eq!(release.revision, 3);
+        assert_eq!(release.status, "pending-upgrade");
+
+        let release = release.without_revision(3).unwrap();
+        assert_eq!(release.revision, 2);
+        assert_eq!(release.history.len(), 2);
+    }
+}
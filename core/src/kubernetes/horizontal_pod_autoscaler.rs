@@ -0,0 +1,442 @@
+use std::collections::{HashMap, VecDeque};
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::autoscaling::v2::{
+    HorizontalPodAutoscaler as K8sHorizontalPodAutoscaler,
+    HorizontalPodAutoscalerCondition as K8sHorizontalPodAutoscalerCondition, MetricSpec,
+    MetricStatus, MetricTarget, MetricValueStatus,
+};
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    quantity,
+    resource::{KubeResource, ResourceRecord},
+};
+
+/// Max number of samples kept per autoscaler, at the default 15s sync period this is ~3 hours
+const MAX_HISTORY_SAMPLES: usize = 720;
+
+uniffi::custom_newtype!(HorizontalPodAutoscalerId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct HorizontalPodAutoscalerId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HorizontalPodAutoscaler {
+    pub id: HorizontalPodAutoscalerId,
+    pub name: String,
+    pub namespace: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub scale_target: ScaleTarget,
+    pub min_replicas: Option<i32>,
+    pub max_replicas: i32,
+    pub current_replicas: Option<i32>,
+    pub desired_replicas: i32,
+    pub last_scale_time: Option<i64>,
+    pub metrics: Vec<HpaMetric>,
+    pub conditions: Vec<HpaCondition>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct ScaleTarget {
+    pub api_version: Option<String>,
+    pub kind: String,
+    pub name: String,
+}
+
+/// A metric the autoscaler scales on, with its target and the last observed value
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HpaMetric {
+    /// `Resource`, `ContainerResource`, `Pods`, `Object` or `External`
+    pub type_: String,
+    /// ex: `cpu`, `memory`, `nginx/cpu` for a container resource, or the custom metric name
+    pub name: String,
+    pub target: HpaMetricValue,
+    pub current: Option<HpaMetricValue>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HpaMetricValue {
+    pub average_utilization: Option<i32>,
+    pub average_value: Option<String>,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct HpaCondition {
+    /// `AbleToScale`, `ScalingActive` or `ScalingLimited`
+    pub type_: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub last_transition_time: Option<i64>,
+}
+
+/// Replica counts and metric readings observed at a point in time
+#[derive(Debug, Clone, Default, PartialEq, Record, Dummy)]
+pub struct ScalingSample {
+    pub observed_at: i64,
+    pub current_replicas: Option<i32>,
+    pub desired_replicas: i32,
+    pub readings: Vec<MetricReading>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Record, Dummy)]
+pub struct MetricReading {
+    pub name: String,
+    pub current: Option<f64>,
+    pub target: Option<f64>,
+}
+
+/// Time series of samples for a single autoscaler, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct ScalingHistory(VecDeque<ScalingSample>);
+
+impl From<K8sHorizontalPodAutoscaler> for HorizontalPodAutoscaler {
+    fn from(hpa: K8sHorizontalPodAutoscaler) -> Self {
+        let name = hpa
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown autoscaler name".to_string());
+
+        let namespace = hpa
+            .metadata
+            .namespace
+            .unwrap_or_else(|| "default".to_string());
+
+        let spec = hpa.spec.unwrap_or_default();
+        let status = hpa.status.unwrap_or_default();
+
+        let current_metrics = status.current_metrics.unwrap_or_default();
+        let metrics = spec
+            .metrics
+            .unwrap_or_default()
+            .into_iter()
+            .map(|metric| HpaMetric::new(metric, &current_metrics))
+            .collect();
+
+        Self {
+            id: format!("{namespace}/{name}").into(),
+            name,
+            namespace,
+            created_at: hpa.metadata.creation_timestamp.map(|t| t.0.timestamp()),
+            labels: hpa
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: hpa
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            scale_target: ScaleTarget {
+                api_version: spec.scale_target_ref.api_version,
+                kind: spec.scale_target_ref.kind,
+                name: spec.scale_target_ref.name,
+            },
+            min_replicas: spec.min_replicas,
+            max_replicas: spec.max_replicas,
+            current_replicas: status.current_replicas,
+            desired_replicas: status.desired_replicas,
+            last_scale_time: status.last_scale_time.map(|t| t.0.timestamp()),
+            metrics,
+            conditions: status
+                .conditions
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl From<K8sHorizontalPodAutoscalerCondition> for HpaCondition {
+    fn from(condition: K8sHorizontalPodAutoscalerCondition) -> Self {
+        Self {
+            type_: condition.type_,
+            status: condition.status,
+            reason: condition.reason,
+            message: condition.message,
+            last_transition_time: condition.last_transition_time.map(|t| t.0.timestamp()),
+        }
+    }
+}
+
+impl From<MetricTarget> for HpaMetricValue {
+    fn from(target: MetricTarget) -> Self {
+        Self {
+            average_utilization: target.average_utilization,
+            average_value: target.average_value.map(|q| q.0),
+            value: target.value.map(|q| q.0),
+        }
+    }
+}
+
+impl From<MetricValueStatus> for HpaMetricValue {
+    fn from(current: MetricValueStatus) -> Self {
+        Self {
+            average_utilization: current.average_utilization,
+            average_value: current.average_value.map(|q| q.0),
+            value: current.value.map(|q| q.0),
+        }
+    }
+}
+
+impl HpaMetric {
+    fn new(metric: MetricSpec, current_metrics: &[MetricStatus]) -> Self {
+        let (name, target) = if let Some(resource) = metric.resource {
+            (resource.name, resource.target)
+        } else if let Some(container_resource) = metric.container_resource {
+            (
+                format!(
+                    "{}/{}",
+                    container_resource.container, container_resource.name
+                ),
+                container_resource.target,
+            )
+        } else if let Some(pods) = metric.pods {
+            (pods.metric.name, pods.target)
+        } else if let Some(object) = metric.object {
+            (object.metric.name, object.target)
+        } else if let Some(external) = metric.external {
+            (external.metric.name, external.target)
+        } else {
+            (String::new(), MetricTarget::default())
+        };
+
+        let current = current_metrics
+            .iter()
+            .filter(|status| status.type_ == metric.type_)
+            .find_map(|status| {
+                let (status_name, current) = metric_status_name_and_value(status)?;
+                (status_name == name).then(|| current.clone().into())
+            });
+
+        Self {
+            type_: metric.type_,
+            name,
+            target: target.into(),
+            current,
+        }
+    }
+}
+
+fn metric_status_name_and_value(status: &MetricStatus) -> Option<(String, &MetricValueStatus)> {
+    if let Some(resource) = &status.resource {
+        return Some((resource.name.clone(), &resource.current));
+    }
+
+    if let Some(container_resource) = &status.container_resource {
+        let name = format!(
+            "{}/{}",
+            container_resource.container, container_resource.name
+        );
+        return Some((name, &container_resource.current));
+    }
+
+    if let Some(pods) = &status.pods {
+        return Some((pods.metric.name.clone(), &pods.current));
+    }
+
+    if let Some(object) = &status.object {
+        return Some((object.metric.name.clone(), &object.current));
+    }
+
+    if let Some(external) = &status.external {
+        return Some((external.metric.name.clone(), &external.current));
+    }
+
+    None
+}
+
+impl HpaMetricValue {
+    /// Numeric value for charting, utilization is preferred since it's what the target is usually
+    /// expressed in
+    pub fn as_f64(&self) -> Option<f64> {
+        if let Some(utilization) = self.average_utilization {
+            return Some(utilization as f64);
+        }
+
+        self.average_value
+            .as_deref()
+            .or(self.value.as_deref())
+            .and_then(quantity::parse)
+    }
+}
+
+impl HorizontalPodAutoscaler {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    pub fn condition(&self, type_: &str) -> Option<&HpaCondition> {
+        self.conditions
+            .iter()
+            .find(|condition| condition.type_ == type_)
+    }
+
+    pub fn able_to_scale(&self) -> Option<bool> {
+        self.condition("AbleToScale")
+            .map(|condition| condition.status == "True")
+    }
+
+    pub fn scaling_limited(&self) -> Option<bool> {
+        self.condition("ScalingLimited")
+            .map(|condition| condition.status == "True")
+    }
+
+    pub fn sample(&self, observed_at: i64) -> ScalingSample {
+        ScalingSample {
+            observed_at,
+            current_replicas: self.current_replicas,
+            desired_replicas: self.desired_replicas,
+            readings: self
+                .metrics
+                .iter()
+                .map(|metric| MetricReading {
+                    name: metric.name.clone(),
+                    current: metric.current.as_ref().and_then(HpaMetricValue::as_f64),
+                    target: metric.target.as_f64(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl ScalingHistory {
+    /// Records a sample, skipped if nothing changed since the last one so a watcher restart
+    /// re-sending every object doesn't add flat points
+    pub fn record(&mut self, sample: ScalingSample) {
+        if let Some(last) = self.0.back() {
+            if last.current_replicas == sample.current_replicas
+                && last.desired_replicas == sample.desired_replicas
+                && last.readings == sample.readings
+            {
+                return;
+            }
+        }
+
+        if self.0.len() == MAX_HISTORY_SAMPLES {
+            self.0.pop_front();
+        }
+
+        self.0.push_back(sample);
+    }
+
+    pub fn samples(&self) -> Vec<ScalingSample> {
+        self.0.iter().cloned().collect()
+    }
+}
+
+#[uniffi::export]
+pub fn horizontal_pod_autoscaler_preview() -> HorizontalPodAutoscaler {
+    HorizontalPodAutoscaler::preview()
+}
+
+impl ResourceRecord for HorizontalPodAutoscaler {
+    type Id = HorizontalPodAutoscalerId;
+    type Usage = ();
+    type History = ScalingHistory;
+
+    fn id(&self) -> &HorizontalPodAutoscalerId {
+        &self.id
+    }
+
+    fn observe(&mut self, history: &mut ScalingHistory, observed_at: i64) {
+        history.record(self.sample(observed_at));
+    }
+}
+
+impl Sortable for HorizontalPodAutoscaler {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "namespace",
+        "target",
+        "replicas",
+        "desired",
+        "min",
+        "max",
+        "age",
+    ];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "target" => SortValue::Text(&self.scale_target.name),
+            "replicas" => self
+                .current_replicas
+                .map_or(SortValue::Missing, |replicas| {
+                    SortValue::Number(replicas.into())
+                }),
+            "desired" => SortValue::Number(self.desired_replicas.into()),
+            "min" => self.min_replicas.map_or(SortValue::Missing, |replicas| {
+                SortValue::Number(replicas.into())
+            }),
+            "max" => SortValue::Number(self.max_replicas.into()),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for HorizontalPodAutoscaler {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("namespace"),
+        QueryField::text("ns"),
+        QueryField::text("target"),
+        QueryField::text("kind"),
+        QueryField::number("replicas"),
+        QueryField::number("desired"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "target" => FieldValue::Text(&self.scale_target.name),
+            "kind" => FieldValue::Text(&self.scale_target.kind),
+            "replicas" => self
+                .current_replicas
+                .map_or(FieldValue::Missing, |replicas| {
+                    FieldValue::Number(replicas.into())
+                }),
+            "desired" => FieldValue::Number(self.desired_replicas.into()),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sHorizontalPodAutoscaler {
+    type Record = HorizontalPodAutoscaler;
+    const NAME: &'static str = "horizontal pod autoscaler";
+}
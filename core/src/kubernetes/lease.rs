@@ -0,0 +1,201 @@
+use std::{collections::HashMap, time::Duration};
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::coordination::v1::Lease as K8sLease;
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::resource::{KubeResource, ResourceRecord};
+
+/// Namespace the kubelets use for their heartbeat leases, every other lease is a leader election
+const NODE_LEASE_NAMESPACE: &str = "kube-node-lease";
+
+uniffi::custom_newtype!(LeaseId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct LeaseId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct Lease {
+    pub id: LeaseId,
+    pub name: String,
+    pub namespace: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub holder_identity: Option<String>,
+    pub lease_duration_seconds: Option<i32>,
+    pub acquire_time: Option<i64>,
+    pub renew_time: Option<i64>,
+    pub lease_transitions: Option<i32>,
+    pub is_leader_election: bool,
+    /// held, but not renewed within its lease duration, as of when the lease was last observed
+    pub stale: bool,
+}
+
+impl From<K8sLease> for Lease {
+    fn from(lease: K8sLease) -> Self {
+        let name = lease
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown lease name".to_string());
+
+        let namespace = lease
+            .metadata
+            .namespace
+            .unwrap_or_else(|| "default".to_string());
+
+        let spec = lease.spec.unwrap_or_default();
+
+        Self {
+            id: format!("{namespace}/{name}").into(),
+            is_leader_election: namespace != NODE_LEASE_NAMESPACE,
+            name,
+            namespace,
+            created_at: lease.metadata.creation_timestamp.map(|t| t.0.timestamp()),
+            labels: lease
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: lease
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            holder_identity: spec.holder_identity.filter(|holder| !holder.is_empty()),
+            lease_duration_seconds: spec.lease_duration_seconds,
+            acquire_time: spec.acquire_time.map(|t| t.0.timestamp()),
+            renew_time: spec.renew_time.map(|t| t.0.timestamp()),
+            lease_transitions: spec.lease_transitions,
+            stale: false,
+        }
+    }
+}
+
+impl Lease {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    /// A released lease (no holder) is never stale, nobody is expected to renew it
+    pub fn is_stale(&self, now: i64) -> bool {
+        if self.holder_identity.is_none() {
+            return false;
+        }
+
+        match (self.renew_time, self.lease_duration_seconds) {
+            (Some(renew_time), Some(duration)) => now - renew_time > duration as i64,
+            _ => false,
+        }
+    }
+
+    pub fn with_staleness(mut self, now: i64) -> Self {
+        self.stale = self.is_stale(now);
+        self
+    }
+}
+
+#[uniffi::export]
+pub fn lease_preview() -> Lease {
+    Lease::preview()
+}
+
+impl ResourceRecord for Lease {
+    type Id = LeaseId;
+    type Usage = ();
+    type History = ();
+
+    // a lease goes stale without changing, once its holder stops renewing it
+    const REOBSERVE_INTERVAL: Option<Duration> = Some(Duration::from_secs(15));
+
+    fn id(&self) -> &LeaseId {
+        &self.id
+    }
+
+    fn observe(&mut self, _history: &mut (), observed_at: i64) {
+        self.stale = self.is_stale(observed_at);
+    }
+}
+
+impl Sortable for Lease {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "namespace",
+        "holder",
+        "renewed",
+        "transitions",
+        "age",
+    ];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "holder" => SortValue::optional(&self.holder_identity),
+            "renewed" => self.renew_time.map_or(SortValue::Missing, |renew_time| {
+                SortValue::Number(-renew_time)
+            }),
+            "transitions" => self
+                .lease_transitions
+                .map_or(SortValue::Missing, |transitions| {
+                    SortValue::Number(transitions.into())
+                }),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for Lease {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("namespace"),
+        QueryField::text("ns"),
+        QueryField::text("holder"),
+        QueryField::number("transitions"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "holder" => FieldValue::optional(&self.holder_identity),
+            "transitions" => self
+                .lease_transitions
+                .map_or(FieldValue::Missing, |transitions| {
+                    FieldValue::Number(transitions.into())
+                }),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sLease {
+    type Record = Lease;
+    const NAME: &'static str = "lease";
+}
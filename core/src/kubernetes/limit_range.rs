@@ -0,0 +1,310 @@
+use std::collections::{BTreeMap, HashMap};
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::core::v1::{
+    LimitRange as K8sLimitRange, LimitRangeItem as K8sLimitRangeItem,
+};
+use uniffi::{Enum, Record};
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    quantity,
+    resource::{KubeResource, ResourceRecord},
+};
+
+uniffi::custom_newtype!(LimitRangeId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct LimitRangeId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct LimitRange {
+    pub id: LimitRangeId,
+    pub name: String,
+    pub namespace: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub limits: Vec<LimitRangeItem>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct LimitRangeItem {
+    /// `Container`, `Pod` or `PersistentVolumeClaim`
+    pub type_: String,
+    pub default: HashMap<String, String>,
+    pub default_request: HashMap<String, String>,
+    pub max: HashMap<String, String>,
+    pub min: HashMap<String, String>,
+    pub max_limit_request_ratio: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Enum, Dummy)]
+pub enum ResourceRequirementKind {
+    #[default]
+    Request,
+    Limit,
+}
+
+/// A value the LimitRanger admission plugin will set on a container that doesn't specify it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct InjectedDefault {
+    pub limit_range_id: LimitRangeId,
+    pub limit_range_name: String,
+    pub resource: String,
+    pub kind: ResourceRequirementKind,
+    pub value: String,
+    pub explanation: String,
+}
+
+impl From<K8sLimitRange> for LimitRange {
+    fn from(limit_range: K8sLimitRange) -> Self {
+        let name = limit_range
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown limit range name".to_string());
+
+        let namespace = limit_range
+            .metadata
+            .namespace
+            .unwrap_or_else(|| "default".to_string());
+
+        Self {
+            id: format!("{namespace}/{name}").into(),
+            name,
+            namespace,
+            created_at: limit_range
+                .metadata
+                .creation_timestamp
+                .map(|t| t.0.timestamp()),
+            labels: limit_range
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: limit_range
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            limits: limit_range
+                .spec
+                .map(|spec| spec.limits)
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl ResourceRecord for LimitRange {
+    type Id = LimitRangeId;
+    type Usage = ();
+    type History = ();
+
+    fn id(&self) -> &LimitRangeId {
+        &self.id
+    }
+}
+
+impl Sortable for LimitRange {
+    const COLUMNS: &'static [&'static str] = &["name", "namespace", "limits", "age"];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "limits" => SortValue::Number(self.limits.len() as i64),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for LimitRange {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("namespace"),
+        QueryField::text("ns"),
+        QueryField::text("type"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "type" => {
+                FieldValue::Texts(self.limits.iter().map(|item| item.type_.as_str()).collect())
+            }
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sLimitRange {
+    type Record = LimitRange;
+    const NAME: &'static str = "limit range";
+}
+
+impl From<K8sLimitRangeItem> for LimitRangeItem {
+    fn from(item: K8sLimitRangeItem) -> Self {
+        Self {
+            type_: item.type_,
+            default: quantity::to_string_map(item.default),
+            default_request: quantity::to_string_map(item.default_request),
+            max: quantity::to_string_map(item.max),
+            min: quantity::to_string_map(item.min),
+            max_limit_request_ratio: quantity::to_string_map(item.max_limit_request_ratio),
+        }
+    }
+}
+
+impl LimitRange {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+}
+
+/// Explains which requests and limits the LimitRanger admission plugin will inject into a
+/// container that has no resources set, given all the limit ranges in its namespace
+///
+/// Mirrors the api server defaulting: a missing default limit falls back to `max`, a missing
+/// default request falls back to the default limit and then to `min`. When several limit ranges
+/// set the same resource the first one wins, so at most one value is returned per resource.
+pub fn injected_defaults(limit_ranges: &[LimitRange]) -> Vec<InjectedDefault> {
+    let mut limits: BTreeMap<String, InjectedDefault> = BTreeMap::new();
+    let mut requests: BTreeMap<String, InjectedDefault> = BTreeMap::new();
+
+    let container_items = limit_ranges.iter().flat_map(|limit_range| {
+        limit_range
+            .limits
+            .iter()
+            .filter(|item| item.type_ == "Container")
+            .map(move |item| (limit_range, item))
+    });
+
+    for (limit_range, item) in container_items {
+        let injected = |resource: &str, kind, value: &str, explanation: String| InjectedDefault {
+            limit_range_id: limit_range.id.clone(),
+            limit_range_name: limit_range.name.clone(),
+            resource: resource.to_string(),
+            kind,
+            value: value.to_string(),
+            explanation,
+        };
+
+        let mut item_limits: BTreeMap<&str, InjectedDefault> = BTreeMap::new();
+        for (resource, value) in &item.default {
+            let explanation = format!(
+                "{resource} limit of {value} from default in LimitRange {}",
+                limit_range.name
+            );
+
+            item_limits.insert(
+                resource,
+                injected(resource, ResourceRequirementKind::Limit, value, explanation),
+            );
+        }
+
+        for (resource, value) in &item.max {
+            item_limits.entry(resource).or_insert_with(|| {
+                let explanation = format!(
+                    "{resource} limit of {value} from max in LimitRange {}, no default is set",
+                    limit_range.name
+                );
+
+                injected(resource, ResourceRequirementKind::Limit, value, explanation)
+            });
+        }
+
+        let mut item_requests: BTreeMap<&str, InjectedDefault> = BTreeMap::new();
+        for (resource, value) in &item.default_request {
+            let explanation = format!(
+                "{resource} request of {value} from defaultRequest in LimitRange {}",
+                limit_range.name
+            );
+
+            item_requests.insert(
+                resource,
+                injected(
+                    resource,
+                    ResourceRequirementKind::Request,
+                    value,
+                    explanation,
+                ),
+            );
+        }
+
+        for (&resource, limit) in &item_limits {
+            item_requests.entry(resource).or_insert_with(|| {
+                let explanation = format!(
+                    "{resource} request of {} set to the injected limit, LimitRange {} has no defaultRequest",
+                    limit.value, limit_range.name
+                );
+
+                injected(
+                    resource,
+                    ResourceRequirementKind::Request,
+                    &limit.value,
+                    explanation,
+                )
+            });
+        }
+
+        for (resource, value) in &item.min {
+            item_requests.entry(resource).or_insert_with(|| {
+                let explanation = format!(
+                    "{resource} request of {value} from min in LimitRange {}, no default is set",
+                    limit_range.name
+                );
+
+                injected(
+                    resource,
+                    ResourceRequirementKind::Request,
+                    value,
+                    explanation,
+                )
+            });
+        }
+
+        for (resource, default) in item_limits {
+            limits.entry(resource.to_string()).or_insert(default);
+        }
+
+        for (resource, default) in item_requests {
+            requests.entry(resource.to_string()).or_insert(default);
+        }
+    }
+
+    requests.into_values().chain(limits.into_values()).collect()
+}
+
+#[uniffi::export]
+pub fn limit_range_preview() -> LimitRange {
+    LimitRange::preview()
+}
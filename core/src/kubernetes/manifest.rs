@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use similar::TextDiff;
+use uniffi::{Enum, Record};
+
+/// Identifies an object in a rendered manifest, ordered so diffs are listed by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ManifestObjectKey {
+    pub api_version: String,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestObject {
+    pub key: ManifestObjectKey,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+pub enum ManifestChange {
+    Added,
+    Removed,
+    Modified,
+    Unchanged,
+}
+
+/// Change of a single object going from the old side to the new side, when comparing against the
+/// cluster the live objects are the new side
+#[derive(Debug, Clone, PartialEq, Eq, Record)]
+pub struct ManifestObjectDiff {
+    pub api_version: String,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub change: ManifestChange,
+    /// unified diff of the object as yaml, empty when unchanged
+    pub diff: String,
+}
+
+impl ManifestObjectKey {
+    fn from_value(value: &Value) -> Option<Self> {
+        let metadata = value.get("metadata")?;
+
+        Some(Self {
+            api_version: value.get("apiVersion")?.as_str()?.to_string(),
+            kind: value.get("kind")?.as_str()?.to_string(),
+            namespace: metadata
+                .get("namespace")
+                .and_then(Value::as_str)
+                .map(ToString::to_string),
+            name: metadata.get("name")?.as_str()?.to_string(),
+        })
+    }
+
+    fn label(&self) -> String {
+        match &self.namespace {
+            Some(namespace) => format!("{} {}/{}", self.kind, namespace, self.name),
+            None => format!("{} {}", self.kind, self.name),
+        }
+    }
+}
+
+/// Splits a multi document manifest into its objects, empty documents and documents that are not
+/// kubernetes objects are skipped
+pub fn parse(manifest: &str) -> Result<Vec<ManifestObject>, serde_yaml::Error> {
+    let mut objects = Vec::new();
+
+    for document in serde_yaml::Deserializer::from_str(manifest) {
+        let value = Value::deserialize(document)?;
+
+        if let Some(key) = ManifestObjectKey::from_value(&value) {
+            objects.push(ManifestObject { key, value });
+        }
+    }
+
+    Ok(objects)
+}
+
+/// Diffs two sets of objects, matched by their key
+pub fn diff(old: &[ManifestObject], new: &[ManifestObject]) -> Vec<ManifestObjectDiff> {
+    let mut pairs: BTreeMap<&ManifestObjectKey, (Option<&Value>, Option<&Value>)> = BTreeMap::new();
+
+    for object in old {
+        pairs.entry(&object.key).or_default().0 = Some(&object.value);
+    }
+
+    for object in new {
+        pairs.entry(&object.key).or_default().1 = Some(&object.value);
+    }
+
+    pairs
+        .into_iter()
+        .map(|(key, (old, new))| object_diff(key, old, new))
+        .collect()
+}
+
+/// Diffs the desired objects against the live ones, `None` if the object is not in the cluster.
+///
+/// Live objects carry defaults, status and server managed metadata, so they are first reduced to
+/// the fields set in the desired object, what remains is drift
+pub fn diff_live(
+    desired: &[ManifestObject],
+    live: &[(ManifestObjectKey, Option<Value>)],
+) -> Vec<ManifestObjectDiff> {
+    let live: BTreeMap<&ManifestObjectKey, &Option<Value>> =
+        live.iter().map(|(key, value)| (key, value)).collect();
+
+    let mut diffs = desired
+        .iter()
+        .map(|object| {
+            let projected = live
+                .get(&object.key)
+                .and_then(|live| live.as_ref())
+                .map(|live| project(&object.value, live));
+
+            object_diff(&object.key, Some(&object.value), projected.as_ref())
+        })
+        .collect::<Vec<_>>();
+
+    diffs.sort_by(|a, b| {
+        (&a.api_version, &a.kind, &a.namespace, &a.name).cmp(&(
+            &b.api_version,
+            &b.kind,
+            &b.namespace,
+            &b.name,
+        ))
+    });
+
+    diffs
+}
+
+/// Keeps only the parts of `live` that are also set in `desired`
+pub fn project(desired: &Value, live: &Value) -> Value {
+    match (desired, live) {
+        (Value::Object(desired), Value::Object(live)) => Value::Object(
+            desired
+                .iter()
+                .filter_map(|(key, desired)| {
+                    let live = live.get(key)?;
+                    Some((key.clone(), project(desired, live)))
+                })
+                .collect(),
+        ),
+
+        // lists of different lengths can't be matched up, show the whole live list
+        (Value::Array(desired), Value::Array(live)) if desired.len() == live.len() => Value::Array(
+            desired
+                .iter()
+                .zip(live)
+                .map(|(desired, live)| project(desired, live))
+                .collect(),
+        ),
+
+        _ => live.clone(),
+    }
+}
+
+fn object_diff(
+    key: &ManifestObjectKey,
+    old: Option<&Value>,
+    new: Option<&Value>,
+) -> ManifestObjectDiff {
+    let change = match (old, new) {
+        (None, _) => ManifestChange::Added,
+        (_, None) => ManifestChange::Removed,
+        (Some(old), Some(new)) if old == new => ManifestChange::Unchanged,
+        _ => ManifestChange::Modified,
+    };
+
+    let diff = match change {
+        ManifestChange::Unchanged => String::new(),
+        _ => {
+            let old = old.map(to_yaml).unwrap_or_default();
+            let new = new.map(to_yaml).unwrap_or_default();
+
+            TextDiff::from_lines(&old, &new)
+                .unified_diff()
+                .header(&key.label(), &key.label())
+                .to_string()
+        }
+    };
+
+    ManifestObjectDiff {
+        api_version: key.api_version.clone(),
+        kind: key.kind.clone(),
+        namespace: key.namespace.clone(),
+        name: key.name.clone(),
+        change,
+        diff,
+    }
+}
+
+fn to_yaml(value: &Value) -> String {
+    serde_yaml::to_string(value).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const OLD: &str = r#"
+---
+# Source: web/templates/service.yaml
+apiVersion: v1
+kind: Service
+metadata:
+  name: web
+spec:
+  ports:
+    - port: 80
+---
+# Source: web/templates/deployment.yaml
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 2
+"#;
+
+    const NEW: &str = r#"
+---
+apiVersion: apps/v1
+kind: Deployment
+metadata:
+  name: web
+spec:
+  replicas: 3
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: web
+data:
+  key: value
+"#;
+
+    #[test]
+    fn test_parse() {
+        let objects = parse(OLD).unwrap();
+
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].key.kind, "Service");
+        assert_eq!(objects[1].key.api_version, "apps/v1");
+        assert_eq!(objects[1].key.namespace, None);
+    }
+
+    #[test]
+    fn test_diff_revisions() {
+        let diffs = diff(&parse(OLD).unwrap(), &parse(NEW).unwrap());
+        let changes = diffs
+            .iter()
+            .map(|diff| (diff.kind.as_str(), diff.change))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                ("Deployment", ManifestChange::Modified),
+                ("ConfigMap", ManifestChange::Added),
+                ("Service", ManifestChange::Removed),
+            ]
+        );
+
+        assert!(diffs[0].diff.contains("-  replicas: 2"));
+        assert!(diffs[0].diff.contains("+  replicas: 3"));
+    }
+
+    #[test]
+    fn test_diff_live() {
+        let desired = parse(OLD).unwrap();
+
+        let service = json!({
+            "apiVersion": "v1",
+            "kind": "Service",
+            "metadata": {"name": "web", "uid": "1234", "annotations": {"meta.helm.sh/release-name": "web"}},
+            "spec": {"ports": [{"port": 80, "protocol": "TCP"}], "clusterIP": "10.0.0.1"},
+            "status": {}
+        });
+
+        let live = vec![
+            (desired[0].key.clone(), Some(service)),
+            (desired[1].key.clone(), None),
+        ];
+
+        let diffs = diff_live(&desired, &live);
+
+        assert_eq!(diffs[0].kind, "Deployment");
+        assert_eq!(diffs[0].change, ManifestChange::Removed);
+        assert_eq!(diffs[1].kind, "Service");
+        assert_eq!(diffs[1].change, ManifestChange::Unchanged);
+    }
+
+    #[test]
+    fn test_project() {
+        let desired =
+            json!({"spec": {"replicas": 2, "template": {"containers": [{"name": "web"}]}}});
+        let live = json!({
+            "spec": {"replicas": 5, "paused": false, "template": {"containers": [{"name": "web", "imagePullPolicy": "Always"}]}},
+            "status": {"replicas": 5}
+        });
+
+        assert_eq!(
+            project(&desired, &live),
+            json!({"spec": {"replicas": 5, "template": {"containers": [{"name": "web"}]}}})
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! Cpu and memory requests, limits and live usage
+//!
+//! Usage comes from the `metrics.k8s.io` api, which is only served when metrics-server (or another
+//! metrics adapter) is installed, without it the usage is `None` and only the requests and limits
+//! are shown
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use eyre::Result;
+use fake::Dummy;
+use k8s_openapi::{api::core::v1::Pod as K8sPod, apimachinery::pkg::api::resource::Quantity};
+use kube::{
+    api::{ApiResource, DynamicObject, ListParams},
+    core::GroupVersionKind,
+    Api, Client,
+};
+use log::warn;
+use serde::Deserialize;
+use uniffi::Record;
+
+use super::{cache::ResourceCache, node::NodeId, pod::PodId, quantity};
+
+/// Cpu in millicores and memory in bytes, `None` when not set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Record, Dummy)]
+pub struct Resources {
+    pub cpu_millis: Option<i64>,
+    pub memory_bytes: Option<i64>,
+}
+
+/// Live usage and how it compares to the requests and limits, in percent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Record, Dummy)]
+pub struct Usage {
+    pub used: Resources,
+
+    /// over 100 the pod uses more than it reserved, it is evicted first when the node runs low
+    pub cpu_of_request: Option<u32>,
+    /// close to 100 the pod is being throttled
+    pub cpu_of_limit: Option<u32>,
+    pub memory_of_request: Option<u32>,
+    /// close to 100 the pod is about to be OOM killed
+    pub memory_of_limit: Option<u32>,
+}
+
+/// Usage of each container of a pod, by container name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PodUsage {
+    pub containers: HashMap<String, Resources>,
+}
+
+/// Usage of the pods sharing a name, by namespace, pods are identified by their name alone so the
+/// namespace picks the right one
+pub type NamespacedPodUsage = HashMap<String, PodUsage>;
+
+/// Pods scheduled on a node that haven't terminated, and the live usage of the node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeUsage {
+    pub pod_count: u32,
+    pub requests: Resources,
+    pub limits: Resources,
+    pub used: Option<Resources>,
+}
+
+/// What is left on a node, like the allocated resources of `kubectl describe node`, the
+/// percentages are of the allocatable resources
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Record, Dummy)]
+pub struct NodeAllocation {
+    pub pod_count: u32,
+    pub requests: Resources,
+    pub limits: Resources,
+    /// `None` if metrics-server isn't installed
+    pub used: Option<Resources>,
+
+    /// close to 100 no more pods requesting cpu can be scheduled on the node
+    pub cpu_requests_percent: Option<u32>,
+    pub memory_requests_percent: Option<u32>,
+    /// over 100 the node is overcommitted
+    pub cpu_limits_percent: Option<u32>,
+    pub memory_limits_percent: Option<u32>,
+    pub cpu_used_percent: Option<u32>,
+    pub memory_used_percent: Option<u32>,
+}
+
+impl Resources {
+    pub fn from_quantities(quantities: Option<&BTreeMap<String, Quantity>>) -> Self {
+        let get = |name: &str| {
+            quantities
+                .and_then(|quantities| quantities.get(name))
+                .and_then(|quantity| quantity::parse(&quantity.0))
+        };
+
+        Self {
+            cpu_millis: get("cpu").map(|cores| (cores * 1000.0).round() as i64),
+            memory_bytes: get("memory").map(|bytes| bytes.round() as i64),
+        }
+    }
+
+    /// Sum of the values that are set, a value is only `None` if none of the resources set it
+    pub fn sum<'a>(resources: impl IntoIterator<Item = &'a Resources>) -> Self {
+        let add = |total: Option<i64>, value: Option<i64>| match (total, value) {
+            (None, None) => None,
+            (total, value) => Some(total.unwrap_or_default() + value.unwrap_or_default()),
+        };
+
+        resources
+            .into_iter()
+            .fold(Self::default(), |total, resources| Self {
+                cpu_millis: add(total.cpu_millis, resources.cpu_millis),
+                memory_bytes: add(total.memory_bytes, resources.memory_bytes),
+            })
+    }
+
+    /// What the scheduler reserves for a pod, like `kubectl describe node`: init containers run
+    /// one at a time before the containers, so the largest of them counts when it is more than
+    /// the containers together, and the pod overhead comes on top
+    pub fn effective<'a>(
+        containers: impl IntoIterator<Item = &'a Resources>,
+        init_containers: impl IntoIterator<Item = &'a Resources>,
+        overhead: &Resources,
+    ) -> Self {
+        let containers = Self::sum(containers);
+        let init_containers =
+            init_containers
+                .into_iter()
+                .fold(Self::default(), |largest, resources| Self {
+                    cpu_millis: largest.cpu_millis.max(resources.cpu_millis),
+                    memory_bytes: largest.memory_bytes.max(resources.memory_bytes),
+                });
+
+        let largest = Self {
+            cpu_millis: containers.cpu_millis.max(init_containers.cpu_millis),
+            memory_bytes: containers.memory_bytes.max(init_containers.memory_bytes),
+        };
+
+        Self::sum([&largest, overhead])
+    }
+
+    /// Like [`Resources::sum`], but a value is `None` if any of the resources doesn't set it, a
+    /// pod with one container without a limit can use as much as the node has
+    pub fn sum_limits<'a>(resources: impl IntoIterator<Item = &'a Resources>) -> Self {
+        let resources = resources.into_iter().collect::<Vec<_>>();
+        if resources.is_empty() {
+            return Self::default();
+        }
+
+        Self {
+            cpu_millis: resources.iter().map(|r| r.cpu_millis).sum(),
+            memory_bytes: resources.iter().map(|r| r.memory_bytes).sum(),
+        }
+    }
+}
+
+impl Usage {
+    pub fn new(used: Resources, requests: &Resources, limits: &Resources) -> Self {
+        Self {
+            used,
+            cpu_of_request: percent(used.cpu_millis, requests.cpu_millis),
+            cpu_of_limit: percent(used.cpu_millis, limits.cpu_millis),
+            memory_of_request: percent(used.memory_bytes, requests.memory_bytes),
+            memory_of_limit: percent(used.memory_bytes, limits.memory_bytes),
+        }
+    }
+}
+
+impl NodeAllocation {
+    pub fn new(usage: &NodeUsage, allocatable: &Resources) -> Self {
+        let used = usage.used.unwrap_or_default();
+
+        Self {
+            pod_count: usage.pod_count,
+            requests: usage.requests,
+            limits: usage.limits,
+            used: usage.used,
+            cpu_requests_percent: percent(usage.requests.cpu_millis, allocatable.cpu_millis),
+            memory_requests_percent: percent(usage.requests.memory_bytes, allocatable.memory_bytes),
+            cpu_limits_percent: percent(usage.limits.cpu_millis, allocatable.cpu_millis),
+            memory_limits_percent: percent(usage.limits.memory_bytes, allocatable.memory_bytes),
+            cpu_used_percent: percent(used.cpu_millis, allocatable.cpu_millis),
+            memory_used_percent: percent(used.memory_bytes, allocatable.memory_bytes),
+        }
+    }
+}
+
+fn percent(used: Option<i64>, total: Option<i64>) -> Option<u32> {
+    let (used, total) = (used?, total?);
+    if total <= 0 {
+        return None;
+    }
+
+    Some(quantity::percentage(used as f64, total as f64).round() as u32)
+}
+
+#[derive(Debug, Deserialize)]
+struct ContainerMetrics {
+    name: String,
+    usage: BTreeMap<String, Quantity>,
+}
+
+/// Usage of every pod, `None` when the metrics api isn't served
+pub async fn pod_usage(client: Client) -> Result<Option<HashMap<PodId, NamespacedPodUsage>>> {
+    let Some(metrics) = list_metrics(client, "PodMetrics", "pods").await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(pod_usage_from_metrics(metrics)))
+}
+
+fn pod_usage_from_metrics(metrics: Vec<DynamicObject>) -> HashMap<PodId, NamespacedPodUsage> {
+    let mut usage: HashMap<PodId, NamespacedPodUsage> = HashMap::new();
+
+    for metrics in metrics {
+        let (Some(name), Some(namespace)) = (metrics.metadata.name, metrics.metadata.namespace)
+        else {
+            continue;
+        };
+
+        let Some(containers) = metrics.data.get("containers").and_then(|containers| {
+            serde_json::from_value::<Vec<ContainerMetrics>>(containers.clone()).ok()
+        }) else {
+            continue;
+        };
+
+        let containers = containers
+            .into_iter()
+            .map(|container| {
+                let used = Resources::from_quantities(Some(&container.usage));
+                (container.name, used)
+            })
+            .collect();
+
+        usage
+            .entry(PodId::from(name))
+            .or_default()
+            .insert(namespace, PodUsage { containers });
+    }
+
+    usage
+}
+
+/// Requests and limits of the pods on every node from the pod cache, with the usage of the nodes
+/// when the metrics api is served, nodes without pods are left out
+pub async fn node_usage(
+    client: Client,
+    pods: Arc<ResourceCache<K8sPod>>,
+) -> Result<Option<HashMap<NodeId, NodeUsage>>> {
+    // the requests and limits are still sent while the metrics api is down
+    let used = match list_metrics(client, "NodeMetrics", "nodes").await {
+        Ok(used) => used,
+        Err(error) => {
+            warn!("failed to get node metrics: {error}");
+            None
+        }
+    };
+    pods.ready().await?;
+
+    let mut usage: HashMap<NodeId, NodeUsage> = HashMap::new();
+    for pod in pods.records().into_values() {
+        let Some(node_name) = &pod.node_name else {
+            continue;
+        };
+
+        if pod.is_terminated() {
+            continue;
+        }
+
+        let node = usage.entry(NodeId::from(node_name.clone())).or_default();
+        node.pod_count += 1;
+        node.requests = Resources::sum([&node.requests, &pod.requests]);
+        node.limits = Resources::sum([&node.limits, &pod.container_limits()]);
+    }
+
+    for metrics in used.unwrap_or_default() {
+        let Some(name) = metrics.metadata.name else {
+            continue;
+        };
+
+        let used: Option<BTreeMap<String, Quantity>> = metrics
+            .data
+            .get("usage")
+            .and_then(|usage| serde_json::from_value(usage.clone()).ok());
+
+        usage.entry(NodeId::from(name)).or_default().used =
+            Some(Resources::from_quantities(used.as_ref()));
+    }
+
+    Ok(Some(usage))
+}
+
+async fn list_metrics(
+    client: Client,
+    kind: &str,
+    plural: &str,
+) -> Result<Option<Vec<DynamicObject>>> {
+    let gvk = GroupVersionKind::gvk("metrics.k8s.io", "v1beta1", kind);
+    let resource = ApiResource::from_gvk_with_plural(&gvk, plural);
+    let api: Api<DynamicObject> = Api::all_with(client, &resource);
+
+    match api.list(&ListParams::default()).await {
+        Ok(list) => Ok(Some(list.items)),
+
+        // not installed, a 503 means its backend is down for now and is retried like any error
+        Err(kube::Error::Api(response)) if response.code == 404 => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    fn resources(cpu_millis: Option<i64>, memory_bytes: Option<i64>) -> Resources {
+        Resources {
+            cpu_millis,
+            memory_bytes,
+        }
+    }
+
+    #[test]
+    fn parses_quantities() {
+        let quantities = BTreeMap::from([
+            ("cpu".to_string(), Quantity("250m".to_string())),
+            ("memory".to_string(), Quantity("64Mi".to_string())),
+        ]);
+
+        assert_eq!(
+            Resources::from_quantities(Some(&quantities)),
+            resources(Some(250), Some(64 * 1024 * 1024))
+        );
+        assert_eq!(Resources::from_quantities(None), Resources::default());
+    }
+
+    #[test]
+    fn sums_requests_and_limits() {
+        let containers = [resources(Some(100), None), resources(Some(200), Some(10))];
+
+        assert_eq!(Resources::sum(&containers), resources(Some(300), Some(10)));
+        assert_eq!(
+            Resources::sum_limits(&containers),
+            resources(Some(300), None)
+        );
+        assert_eq!(
+            Resources::sum_limits(&[] as &[Resources]),
+            Resources::default()
+        );
+    }
+
+    #[test]
+    fn counts_the_largest_init_container_and_the_overhead() {
+        let containers = [resources(Some(100), Some(10)), resources(Some(100), None)];
+        let init_containers = [resources(Some(500), Some(5)), resources(None, Some(8))];
+        let overhead = resources(Some(50), None);
+
+        assert_eq!(
+            Resources::effective(&containers, &init_containers, &overhead),
+            resources(Some(550), Some(10))
+        );
+        assert_eq!(
+            Resources::effective(&containers, &[] as &[Resources], &Resources::default()),
+            resources(Some(200), Some(10))
+        );
+        assert_eq!(
+            Resources::effective(
+                &[] as &[Resources],
+                &[] as &[Resources],
+                &Resources::default()
+            ),
+            Resources::default()
+        );
+    }
+
+    #[test]
+    fn keys_pod_usage_by_namespace() {
+        let metrics = |namespace: &str, cpu: &str| DynamicObject {
+            types: None,
+            metadata: ObjectMeta {
+                name: Some("web".to_string()),
+                namespace: Some(namespace.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: serde_json::json!({
+                "containers": [{ "name": "app", "usage": { "cpu": cpu, "memory": "1Ki" } }]
+            }),
+        };
+
+        let usage = pod_usage_from_metrics(vec![metrics("prod", "200m"), metrics("dev", "10m")]);
+        let web = &usage[&PodId::from("web".to_string())];
+
+        assert_eq!(web.len(), 2);
+        assert_eq!(web["prod"].containers["app"].cpu_millis, Some(200));
+        assert_eq!(web["dev"].containers["app"].cpu_millis, Some(10));
+    }
+
+    #[test]
+    fn compares_usage_to_requests_and_limits() {
+        let usage = Usage::new(
+            resources(Some(150), Some(90)),
+            &resources(Some(100), None),
+            &resources(Some(0), Some(100)),
+        );
+
+        assert_eq!(usage.cpu_of_request, Some(150));
+        assert_eq!(usage.cpu_of_limit, None);
+        assert_eq!(usage.memory_of_request, None);
+        assert_eq!(usage.memory_of_limit, Some(90));
+    }
+}
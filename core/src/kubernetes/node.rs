@@ -1,19 +1,31 @@
-use crate::{cluster::ClusterId, view_models::node::Worker as NodeWorker};
-use act_zero::*;
 use derive_more::From;
-use eyre::Result;
 use fake::{Dummy, Fake, Faker};
-use futures::{StreamExt, TryStreamExt};
-use log::debug;
+use futures::FutureExt;
+use kube::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
 use uniffi::Record;
 
 use k8s_openapi::api::core::v1::{
     Node as K8sNode, NodeAddress as K8sNodeAddress, NodeCondition as K8sNodeCondition,
     NodeSystemInfo, Taint as K8sTaint,
 };
-use kube::{Api, Client};
+
+use crate::{
+    cluster::ClusterId,
+    query::{FieldValue, QueryField, Queryable, Selectors},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    cache::ResourceCache,
+    metrics::{self, NodeAllocation, NodeUsage, Resources},
+    quantity,
+    resource::{KubeResource, ResourceRecord, UsageFetcher},
+};
 
 #[derive(
     Debug,
@@ -34,12 +46,43 @@ pub struct NodeId {
     pub raw_value: String,
 }
 
+/// Transitions kept per node, enough for a few days of a node flapping every hour
+const MAX_CONDITION_TRANSITIONS: usize = 100;
+
+/// A node whose Ready condition changed status more than this many times within
+/// [`FLAP_WINDOW_SECONDS`] is flapping
+pub const FLAP_THRESHOLD: u32 = 3;
+pub const FLAP_WINDOW_SECONDS: i64 = 30 * 60;
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
 pub struct NodeCondition {
     pub name: String,
     pub status: String,
     pub reason: Option<String>,
     pub message: Option<String>,
+    /// Last time the kubelet reported the condition
+    pub last_heartbeat_time: Option<i64>,
+    pub last_transition_time: Option<i64>,
+}
+
+/// A condition changing status, as observed by the node watcher
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct ConditionTransition {
+    pub condition: String,
+    /// `None` the first time the condition is observed
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+    pub last_transition_time: Option<i64>,
+    pub observed_at: i64,
+}
+
+/// Transitions of every condition of a node, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct NodeHistory {
+    transitions: VecDeque<ConditionTransition>,
+    current: HashMap<String, NodeCondition>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
@@ -47,6 +90,8 @@ pub struct Node {
     pub id: NodeId,
     pub name: String,
     pub created_at: Option<i64>,
+    /// Sent with taint and label edits, so they fail instead of overwriting a change made since
+    pub resource_version: Option<String>,
     pub labels: HashMap<String, String>,
     pub annotations: HashMap<String, String>,
     pub taints: Vec<Taint>,
@@ -58,6 +103,21 @@ pub struct Node {
     pub container_runtime: Option<String>,
     pub kubelet_version: Option<String>,
     pub conditions: Vec<NodeCondition>,
+    /// Cordoned, no new pods are scheduled on it
+    pub unschedulable: bool,
+    /// Times the Ready condition changed status in the last [`FLAP_WINDOW_SECONDS`], as seen by
+    /// the watcher
+    pub ready_flaps: u32,
+    /// Flapped more than [`FLAP_THRESHOLD`] times, the pods on it are likely being churned
+    pub flapping: bool,
+
+    pub capacity: Resources,
+    /// Capacity minus what is reserved for the system and the kubelet, what pods can request
+    pub allocatable: Resources,
+    /// Most pods the node runs
+    pub pod_capacity: Option<i64>,
+    /// Pods on the node and the usage of the node, `None` until loaded from the pod cache
+    pub allocation: Option<NodeAllocation>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
@@ -101,8 +161,65 @@ impl From<K8sNodeCondition> for NodeCondition {
             status: condition.status,
             reason: condition.reason,
             message: condition.message,
+            last_heartbeat_time: condition.last_heartbeat_time.map(|time| time.0.timestamp()),
+            last_transition_time: condition
+                .last_transition_time
+                .map(|time| time.0.timestamp()),
+        }
+    }
+}
+
+impl NodeHistory {
+    /// Records the conditions whose status or transition time changed since they were last
+    /// observed, heartbeats alone are not a transition
+    pub fn record(&mut self, conditions: &[NodeCondition], observed_at: i64) {
+        for condition in conditions {
+            let previous = self
+                .current
+                .insert(condition.name.clone(), condition.clone());
+
+            let changed = previous.as_ref().map_or(true, |previous| {
+                previous.status != condition.status
+                    || previous.last_transition_time != condition.last_transition_time
+            });
+
+            if !changed {
+                continue;
+            }
+
+            if self.transitions.len() == MAX_CONDITION_TRANSITIONS {
+                self.transitions.pop_front();
+            }
+
+            self.transitions.push_back(ConditionTransition {
+                condition: condition.name.clone(),
+                from_status: previous.map(|previous| previous.status),
+                to_status: condition.status.clone(),
+                reason: condition.reason.clone(),
+                message: condition.message.clone(),
+                last_transition_time: condition.last_transition_time,
+                observed_at,
+            });
         }
     }
+
+    /// Times the Ready condition changed status since `since`. The same status with a new
+    /// transition time went the other way and back between two observations, it counts twice
+    pub fn ready_flaps(&self, since: i64) -> u32 {
+        self.transitions
+            .iter()
+            .filter(|transition| transition.condition == "Ready" && transition.observed_at >= since)
+            .map(|transition| match &transition.from_status {
+                None => 0,
+                Some(from) if *from == transition.to_status => 2,
+                Some(_) => 1,
+            })
+            .sum()
+    }
+
+    pub fn transitions(&self) -> Vec<ConditionTransition> {
+        self.transitions.iter().cloned().collect()
+    }
 }
 
 impl From<K8sNode> for Node {
@@ -128,6 +245,23 @@ impl From<K8sNode> for Node {
             })
             .unwrap_or_default();
 
+        let capacity = node
+            .status
+            .as_ref()
+            .and_then(|status| status.capacity.as_ref());
+        let allocatable = node
+            .status
+            .as_ref()
+            .and_then(|status| status.allocatable.as_ref());
+
+        let pod_capacity = allocatable
+            .and_then(|allocatable| allocatable.get("pods"))
+            .and_then(|pods| quantity::parse(&pods.0))
+            .map(|pods| pods as i64);
+
+        let capacity = Resources::from_quantities(capacity);
+        let allocatable = Resources::from_quantities(allocatable);
+
         let node_info: Option<NodeSystemInfo> =
             node.status.and_then(|status| status.node_info).take();
 
@@ -144,6 +278,12 @@ impl From<K8sNode> for Node {
             })
             .unwrap_or((None, None, None, None, None, None));
 
+        let unschedulable = node
+            .spec
+            .as_ref()
+            .and_then(|spec| spec.unschedulable)
+            .unwrap_or_default();
+
         let node_name = node
             .metadata
             .name
@@ -156,6 +296,7 @@ impl From<K8sNode> for Node {
                 .metadata
                 .creation_timestamp
                 .map(|time| time.0.timestamp()),
+            resource_version: node.metadata.resource_version,
             labels: node
                 .metadata
                 .labels
@@ -181,6 +322,13 @@ impl From<K8sNode> for Node {
             container_runtime,
             kubelet_version,
             conditions,
+            unschedulable,
+            ready_flaps: 0,
+            flapping: false,
+            capacity,
+            allocatable,
+            pod_capacity,
+            allocation: None,
         }
     }
 }
@@ -189,6 +337,19 @@ impl Node {
     pub fn preview() -> Self {
         Faker.fake()
     }
+
+    fn allocation_value(&self, value: impl Fn(&NodeAllocation) -> Option<u32>) -> SortValue<'_> {
+        self.allocation
+            .as_ref()
+            .and_then(value)
+            .map_or(SortValue::Missing, |value| SortValue::Number(value.into()))
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.conditions
+            .iter()
+            .any(|condition| condition.name == "Ready" && condition.status == "True")
+    }
 }
 
 #[uniffi::export]
@@ -196,47 +357,134 @@ pub fn node_preview() -> Node {
     Node::preview()
 }
 
-pub async fn get_all(client: Client) -> Result<HashMap<NodeId, Node>> {
-    let nodes_api: Api<K8sNode> = Api::all(client);
-    let nodes = nodes_api.list(&Default::default()).await?;
+impl ResourceRecord for Node {
+    type Id = NodeId;
+    type Usage = NodeUsage;
+    type History = NodeHistory;
 
-    let nodes_hash_map = nodes
-        .into_iter()
-        .map(Into::<Node>::into)
-        .map(|node| (node.id.clone(), node))
-        .collect();
+    // flaps age out of the window without the node changing
+    const REOBSERVE_INTERVAL: Option<Duration> = Some(Duration::from_secs(60));
 
-    Ok(nodes_hash_map)
-}
+    fn id(&self) -> &NodeId {
+        &self.id
+    }
 
-pub async fn watch(
-    addr: WeakAddr<NodeWorker>,
-    selected_cluster: ClusterId,
-    client: Client,
-) -> Result<()> {
-    use kube::runtime::watcher;
-    debug!("watch_nodes called");
+    fn set_usage(&mut self, usage: Option<&NodeUsage>) {
+        // nodes without pods aren't in the usage, they still have all of their allocatable free
+        let usage = usage.cloned().unwrap_or_default();
+        self.allocation = Some(NodeAllocation::new(&usage, &self.allocatable));
+    }
 
-    let nodes_api: Api<K8sNode> = Api::all(client);
+    fn observe(&mut self, history: &mut NodeHistory, observed_at: i64) {
+        history.record(&self.conditions, observed_at);
 
-    let mut stream = watcher(nodes_api, watcher::Config::default()).boxed();
+        self.ready_flaps = history.ready_flaps(observed_at - FLAP_WINDOW_SECONDS);
+        self.flapping = self.ready_flaps > FLAP_THRESHOLD;
+    }
+}
 
-    while let Some(status) = stream.try_next().await? {
-        match status {
-            watcher::Event::Applied(node) => {
-                debug!("applied event received on cluster {:?}", selected_cluster);
-                send!(addr.applied(node.into()))
-            }
-            watcher::Event::Deleted(node) => {
-                debug!("deleted event received on cluster {:?}", selected_cluster);
-                send!(addr.deleted(node.into()))
-            }
-            watcher::Event::Restarted(_) => {
-                debug!("restarted event received on cluster {:?}", selected_cluster);
-                send!(addr.load_nodes(selected_cluster.clone()))
+impl Sortable for Node {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "version",
+        "taints",
+        "age",
+        "conditions",
+        "arch",
+        "os",
+        "runtime",
+        "flaps",
+        "pods",
+        "cpu_requests",
+        "memory_requests",
+        "cpu",
+        "memory",
+    ];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "version" => SortValue::optional(&self.kubelet_version),
+            "taints" => SortValue::Number(self.taints.len() as i64),
+            // youngest first, like the age column counting up
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            // nodes that aren't ready sort first
+            "conditions" => SortValue::Number(self.is_ready().into()),
+            "arch" => SortValue::optional(&self.arch),
+            "os" => SortValue::optional(&self.os),
+            "runtime" => SortValue::optional(&self.container_runtime),
+            "flaps" => SortValue::Number(self.ready_flaps.into()),
+            "pods" => self.allocation_value(|allocation| Some(allocation.pod_count.into())),
+            "cpu_requests" => self.allocation_value(|allocation| allocation.cpu_requests_percent),
+            "memory_requests" => {
+                self.allocation_value(|allocation| allocation.memory_requests_percent)
             }
+            "cpu" => self.allocation_value(|allocation| allocation.cpu_used_percent),
+            "memory" => self.allocation_value(|allocation| allocation.memory_used_percent),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for Node {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::labels("label"),
+        QueryField::text("taint"),
+        QueryField::text("ip"),
+        QueryField::text("arch"),
+        QueryField::text("os"),
+        QueryField::text("kubelet"),
+        QueryField::text("runtime"),
+        QueryField::text("ready"),
+        QueryField::number("flaps"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "label" => FieldValue::Labels(&self.labels),
+            "taint" => FieldValue::Texts(self.taints.iter().map(|t| t.key.as_str()).collect()),
+            "ip" => FieldValue::Texts(self.addresses.iter().map(|a| a.address.as_str()).collect()),
+            "arch" => FieldValue::optional(&self.arch),
+            "os" => FieldValue::optional(&self.os),
+            "kubelet" => FieldValue::optional(&self.kubelet_version),
+            "runtime" => FieldValue::optional(&self.container_runtime),
+            "ready" => self
+                .conditions
+                .iter()
+                .find(|condition| condition.name == "Ready")
+                .map_or(FieldValue::Missing, |condition| {
+                    FieldValue::Text(&condition.status)
+                }),
+            "flaps" => FieldValue::Number(self.ready_flaps.into()),
+            _ => FieldValue::Missing,
         }
     }
 
-    Ok(())
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sNode {
+    type Record = Node;
+    const NAME: &'static str = "node";
+
+    /// The pod cache is kept running while the nodes are shown, shared with the pods tab
+    fn usage_fetcher(cluster_id: &ClusterId, client: Client) -> Option<UsageFetcher<Self>> {
+        let pods = ResourceCache::get_or_start(cluster_id, &Selectors::default(), client.clone());
+        Some(Box::new(move || {
+            metrics::node_usage(client.clone(), pods.clone()).boxed()
+        }))
+    }
 }
@@ -0,0 +1,319 @@
+//! Editing the taints and labels of a node
+//!
+//! Edits are sent as a JSON merge patch with the resource version of the node they were made on,
+//! the api server refuses the patch if the node changed since instead of overwriting the change. A
+//! merge patch replaces the taints as a whole, without the precondition a taint added by someone
+//! else in between would be dropped
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use k8s_openapi::api::core::v1::Node as K8sNode;
+use kube::{
+    api::{Patch, PatchParams},
+    Api, Client,
+};
+use serde_json::{json, Map, Value};
+
+use super::{
+    node::{Node, Taint},
+    pod::Pod,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Node {0} has no resource version, reload it and try again")]
+    MissingResourceVersion(String),
+
+    #[error("Node {0} changed since it was loaded, reload it and try again")]
+    Conflict(String),
+
+    #[error("Unable to update node {0}: {1}")]
+    PatchError(String, kube::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Enum)]
+pub enum NodeEdit {
+    /// Adds the taint, or replaces the one with the same key and effect
+    SetTaint {
+        taint: Taint,
+    },
+    RemoveTaint {
+        key: String,
+        effect: String,
+    },
+
+    /// Adds the label or changes its value
+    SetLabel {
+        key: String,
+        value: String,
+    },
+    RemoveLabel {
+        key: String,
+    },
+}
+
+/// Applies the edits to the node as it was when loaded, fails with [`Error::Conflict`] if it changed
+/// since
+pub async fn edit(client: Client, node: &Node, edits: &[NodeEdit]) -> Result<(), Error> {
+    let patch = merge_patch(node, edits)?;
+    let api: Api<K8sNode> = Api::all(client);
+
+    api.patch(&node.name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await
+        .map_err(|error| match error {
+            kube::Error::Api(response) if response.code == 409 => {
+                Error::Conflict(node.name.clone())
+            }
+            error => Error::PatchError(node.name.clone(), error),
+        })?;
+
+    Ok(())
+}
+
+/// Pods that a new `NoExecute` taint would evict, the ones without a toleration for it, including
+/// the ones tolerating it for a limited time. Other effects only affect scheduling, they never
+/// evict a running pod
+pub fn untolerated_pods(taint: &Taint, pods: Vec<Pod>) -> Vec<Pod> {
+    if taint.effect != "NoExecute" {
+        return vec![];
+    }
+
+    pods.into_iter()
+        .filter(|pod| !pod.is_terminated())
+        .filter(|pod| {
+            !pod.tolerations.iter().any(|toleration| {
+                toleration.tolerates(taint) && toleration.toleration_seconds.is_none()
+            })
+        })
+        .collect()
+}
+
+fn merge_patch(node: &Node, edits: &[NodeEdit]) -> Result<Value, Error> {
+    let resource_version = node
+        .resource_version
+        .as_ref()
+        .ok_or_else(|| Error::MissingResourceVersion(node.name.clone()))?;
+
+    let mut taints = node.taints.clone();
+    let mut taints_changed = false;
+    let mut labels = Map::new();
+
+    let same = |lhs: &Taint, key: &str, effect: &str| lhs.key == key && lhs.effect == effect;
+
+    for edit in edits {
+        match edit {
+            NodeEdit::SetTaint { taint } => {
+                taints_changed = true;
+
+                match taints
+                    .iter_mut()
+                    .find(|existing| same(existing, &taint.key, &taint.effect))
+                {
+                    Some(existing) => *existing = taint.clone(),
+                    None => taints.push(taint.clone()),
+                }
+            }
+            NodeEdit::RemoveTaint { key, effect } => {
+                taints_changed = true;
+                taints.retain(|existing| !same(existing, key, effect));
+            }
+            NodeEdit::SetLabel { key, value } => {
+                labels.insert(key.clone(), Value::String(value.clone()));
+            }
+            // null removes the key in a merge patch
+            NodeEdit::RemoveLabel { key } => {
+                labels.insert(key.clone(), Value::Null);
+            }
+        }
+    }
+
+    let mut patch = json!({ "metadata": { "resourceVersion": resource_version } });
+
+    if !labels.is_empty() {
+        patch["metadata"]["labels"] = Value::Object(labels);
+    }
+
+    if taints_changed {
+        let taints = taints.iter().map(taint_json).collect::<Vec<_>>();
+        patch["spec"] = json!({ "taints": taints });
+    }
+
+    Ok(patch)
+}
+
+fn taint_json(taint: &Taint) -> Value {
+    let mut value = json!({ "key": taint.key, "effect": taint.effect });
+
+    if let Some(taint_value) = &taint.value {
+        value["value"] = json!(taint_value);
+    }
+
+    // kept as it was, tolerations with toleration seconds count from it
+    let time_added = taint
+        .time_added
+        .as_deref()
+        .and_then(|time_added| DateTime::parse_from_rfc2822(time_added).ok());
+
+    if let Some(time_added) = time_added {
+        value["timeAdded"] = json!(time_added
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, true));
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kubernetes::core::Toleration;
+
+    fn taint(key: &str, value: Option<&str>, effect: &str) -> Taint {
+        Taint {
+            effect: effect.to_string(),
+            key: key.to_string(),
+            time_added: None,
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn toleration(key: Option<&str>, operator: &str, value: Option<&str>) -> Toleration {
+        Toleration {
+            effect: None,
+            key: key.map(str::to_string),
+            operator: Some(operator.to_string()),
+            toleration_seconds: None,
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn node(taints: Vec<Taint>) -> Node {
+        Node {
+            name: "node-1".to_string(),
+            resource_version: Some("42".to_string()),
+            taints,
+            ..Node::default()
+        }
+    }
+
+    #[test]
+    fn patches_labels_with_the_resource_version() {
+        let patch = merge_patch(
+            &node(vec![]),
+            &[
+                NodeEdit::SetLabel {
+                    key: "zone".to_string(),
+                    value: "a".to_string(),
+                },
+                NodeEdit::RemoveLabel {
+                    key: "old".to_string(),
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            patch,
+            json!({
+                "metadata": {
+                    "resourceVersion": "42",
+                    "labels": { "zone": "a", "old": null }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn replaces_taints_with_the_same_key_and_effect() {
+        let mut existing = taint("dedicated", Some("db"), "NoExecute");
+        existing.time_added = Some("Tue, 1 Aug 2023 10:00:00 +0000".to_string());
+
+        let node = node(vec![existing, taint("gpu", None, "NoSchedule")]);
+        let patch = merge_patch(
+            &node,
+            &[
+                NodeEdit::SetTaint {
+                    taint: taint("dedicated", Some("web"), "NoSchedule"),
+                },
+                NodeEdit::SetTaint {
+                    taint: taint("gpu", Some("true"), "NoSchedule"),
+                },
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            patch["spec"]["taints"],
+            json!([
+                { "key": "dedicated", "value": "db", "effect": "NoExecute", "timeAdded": "2023-08-01T10:00:00Z" },
+                { "key": "gpu", "value": "true", "effect": "NoSchedule" },
+                { "key": "dedicated", "value": "web", "effect": "NoSchedule" },
+            ])
+        );
+
+        let patch = merge_patch(
+            &node,
+            &[NodeEdit::RemoveTaint {
+                key: "gpu".to_string(),
+                effect: "NoSchedule".to_string(),
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(patch["spec"]["taints"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn requires_a_resource_version() {
+        let mut node = node(vec![]);
+        node.resource_version = None;
+
+        assert!(matches!(
+            merge_patch(&node, &[]),
+            Err(Error::MissingResourceVersion(_))
+        ));
+    }
+
+    #[test]
+    fn matches_tolerations_to_taints() {
+        let taint = taint("dedicated", Some("db"), "NoExecute");
+
+        assert!(toleration(Some("dedicated"), "Equal", Some("db")).tolerates(&taint));
+        assert!(toleration(Some("dedicated"), "Exists", None).tolerates(&taint));
+        assert!(toleration(None, "Exists", None).tolerates(&taint));
+        assert!(!toleration(Some("dedicated"), "Equal", Some("web")).tolerates(&taint));
+        assert!(!toleration(Some("other"), "Exists", None).tolerates(&taint));
+
+        let mut other_effect = toleration(Some("dedicated"), "Exists", None);
+        other_effect.effect = Some("NoSchedule".to_string());
+        assert!(!other_effect.tolerates(&taint));
+    }
+
+    #[test]
+    fn previews_pods_evicted_by_a_taint() {
+        let pod = |name: &str, tolerations: Vec<Toleration>| Pod {
+            name: name.to_string(),
+            tolerations,
+            ..Pod::default()
+        };
+
+        let mut limited = toleration(Some("dedicated"), "Exists", None);
+        limited.toleration_seconds = Some(300);
+
+        let pods = vec![
+            pod("tolerates", vec![toleration(None, "Exists", None)]),
+            pod("untolerated", vec![]),
+            pod("limited", vec![limited]),
+        ];
+
+        let names = |pods: Vec<Pod>| pods.into_iter().map(|pod| pod.name).collect::<Vec<_>>();
+
+        assert_eq!(
+            names(untolerated_pods(
+                &taint("dedicated", None, "NoExecute"),
+                pods.clone()
+            )),
+            vec!["untolerated", "limited"]
+        );
+        assert!(untolerated_pods(&taint("dedicated", None, "NoSchedule"), pods).is_empty());
+    }
+}
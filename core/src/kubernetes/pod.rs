@@ -1,25 +1,42 @@
 use std::{borrow::Cow, collections::HashMap};
 
-use crate::{cluster::ClusterId, view_models::pod::PodViewModel};
-use act_zero::{call, Addr};
 use derive_more::{AsRef, Display, From};
 use either::Either;
 use eyre::Result;
 use fake::{Dummy, Fake, Faker};
-use futures::{stream::FuturesUnordered, StreamExt, TryStreamExt};
+use futures::{stream::FuturesUnordered, FutureExt, StreamExt};
 use k8s_openapi::api::core::v1::Pod as K8sPod;
-use kube::{api::DeleteParams, core::Status, Api, Client};
-use log::debug;
+use kube::{
+    api::{DeleteParams, ListParams},
+    core::Status,
+    Api, Client,
+};
+use log::warn;
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use uniffi::{Enum, Record};
 
 use k8s_openapi::api::core::v1::{
     Container as K8sContainer, ContainerState as K8sContainerState,
-    ContainerStatus as K8sContainerStatus, PodCondition as K8sPodCondition,
+    ContainerStatus as K8sContainerStatus, EphemeralContainer as K8sEphemeralContainer,
+    PodCondition as K8sPodCondition,
 };
 
-use super::{core::OwnerReference, core::Toleration};
+use crate::{
+    cluster::ClusterId,
+    query::{FieldValue, QueryField, Queryable, Selectors},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    cache::ResourceCache,
+    container_health::ContainerHealth,
+    core::{OwnerReference, Toleration},
+    metrics::{self, NamespacedPodUsage, Resources, Usage},
+    node::NodeId,
+    pod_spec::{Affinity, EnvFromSource, EnvVar, Probe, SecurityContext, Volume, VolumeMount},
+    resource::{KubeResource, ResourceRecord, UsageFetcher},
+};
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -73,12 +90,22 @@ pub struct Pod {
     pub labels: HashMap<String, String>,
     pub annotations: HashMap<String, String>,
     pub containers: Vec<Container>,
+    /// Run to completion one after the other before the containers start
+    pub init_containers: Vec<Container>,
+    /// Added to the running pod to debug it, never restarted
+    pub ephemeral_containers: Vec<Container>,
+    pub volumes: Vec<Volume>,
     pub pod_ip: Option<String>,
     pub host_ip: Option<String>,
     pub pod_ips: Vec<String>,
     pub qos_class: Option<String>,
     pub message: Option<String>,
     pub phase: Phase,
+    /// Node the pod runs on, `None` until it is scheduled
+    pub node_name: Option<String>,
+    pub scheduling: Scheduling,
+    pub priority: Option<i32>,
+    pub priority_class_name: Option<String>,
     // TODO: link to owner
     pub controlled_by: Vec<OwnerReference>,
 
@@ -87,20 +114,61 @@ pub struct Pod {
 
     pub conditions: Vec<PodCondition>,
     pub tolerations: Vec<Toleration>,
+    pub node_selector: HashMap<String, String>,
+    pub affinity: Option<Affinity>,
+    pub security_context: Option<SecurityContext>,
+
+    /// What the scheduler reserves for the pod, see [`Resources::effective`]
+    pub requests: Resources,
+    /// Sum of the container limits, unset if any container has no limit
+    pub limits: Resources,
+    /// Reserved for the pod's runtime by its RuntimeClass, added to the requests
+    pub overhead: Resources,
+    /// `None` until loaded from the metrics api, or if metrics-server isn't installed
+    pub usage: Option<Usage>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
 pub struct Container {
-    id: ContainerId,
-    name: String,
-    image: String,
-    image_id: Option<String>,
-    last_state: Option<ContainerState>,
-    ready: bool,
-    restart_count: i32,
-    started: bool,
-    state: Option<ContainerState>,
-    ports: Vec<u32>,
+    pub id: ContainerId,
+    pub name: String,
+    pub kind: ContainerKind,
+    pub image: String,
+    pub image_id: Option<String>,
+    /// `Always`, `IfNotPresent` or `Never`
+    pub image_pull_policy: Option<String>,
+    pub command: Vec<String>,
+    pub args: Vec<String>,
+    pub last_state: Option<ContainerState>,
+    pub ready: bool,
+    pub restart_count: i32,
+    /// Set once the startup probe passed, or as soon as it runs without one
+    pub started: bool,
+    pub state: Option<ContainerState>,
+    /// Derived from the state, readiness and last termination
+    pub health: ContainerHealth,
+    pub ports: Vec<u32>,
+    pub env: Vec<EnvVar>,
+    pub env_from: Vec<EnvFromSource>,
+    pub volume_mounts: Vec<VolumeMount>,
+    pub liveness_probe: Option<Probe>,
+    pub readiness_probe: Option<Probe>,
+    pub startup_probe: Option<Probe>,
+    pub security_context: Option<SecurityContext>,
+    /// Container whose namespaces an ephemeral container shares, ex: for `kubectl debug`
+    pub target_container_name: Option<String>,
+    pub requests: Resources,
+    pub limits: Resources,
+    pub usage: Option<Usage>,
+}
+
+/// Init, regular and ephemeral containers can share a name, their statuses are reported separately
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Enum, Dummy)]
+pub enum ContainerKind {
+    Init,
+    #[default]
+    Regular,
+    Ephemeral,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Enum, Dummy)]
@@ -151,6 +219,21 @@ pub struct ContainerStateWaiting {
     pub reason: Option<String>,
 }
 
+/// Where the scheduler placed the pod, or why it couldn't
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct Scheduling {
+    pub scheduler_name: Option<String>,
+    /// Node the scheduler is making room on by preempting lower priority pods
+    pub nominated_node_name: Option<String>,
+    /// From the `PodScheduled` condition
+    pub scheduled: bool,
+    pub scheduled_at: Option<i64>,
+    /// Set while the pod can't be scheduled, ex: `Unschedulable`
+    pub reason: Option<String>,
+    /// ex: `0/3 nodes are available: 3 Insufficient cpu.`
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
 pub struct PodCondition {
     pub last_probe_time: Option<i64>,
@@ -163,20 +246,65 @@ pub struct PodCondition {
 
 impl From<K8sPod> for Pod {
     fn from(pod: K8sPod) -> Self {
-        let containers = pod
-            .spec
-            .as_ref()
-            .map(|s| s.containers.clone())
-            .unwrap_or_default();
-
+        let spec = pod.spec.as_ref();
         let pod_status = pod.status.as_ref();
 
-        let container_statuses = pod_status
-            .and_then(|s| s.container_statuses.clone())
+        let statuses = [
+            (
+                ContainerKind::Init,
+                pod_status.and_then(|s| s.init_container_statuses.as_ref()),
+            ),
+            (
+                ContainerKind::Regular,
+                pod_status.and_then(|s| s.container_statuses.as_ref()),
+            ),
+            (
+                ContainerKind::Ephemeral,
+                pod_status.and_then(|s| s.ephemeral_container_statuses.as_ref()),
+            ),
+        ]
+        .into_iter()
+        .flat_map(|(kind, statuses)| {
+            statuses
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |status| ((kind, ContainerId::from(status.name.clone())), status))
+        })
+        .collect::<HashMap<_, _>>();
+
+        let containers = |containers: Vec<K8sContainer>, kind| {
+            containers
+                .into_iter()
+                .map(|container| Container::new(container, kind, &statuses))
+                .collect::<Vec<_>>()
+        };
+
+        let init_containers = containers(
+            spec.and_then(|s| s.init_containers.clone())
+                .unwrap_or_default(),
+            ContainerKind::Init,
+        );
+
+        let ephemeral_containers = spec
+            .and_then(|s| s.ephemeral_containers.clone())
             .unwrap_or_default()
             .into_iter()
-            .map(|status| (ContainerId::from(status.name.clone()), status))
-            .collect::<HashMap<ContainerId, _>>();
+            .map(|container| Container::ephemeral(container, &statuses))
+            .collect();
+
+        let containers = containers(
+            spec.map(|s| s.containers.clone()).unwrap_or_default(),
+            ContainerKind::Regular,
+        );
+
+        let overhead = Resources::from_quantities(spec.and_then(|s| s.overhead.as_ref()));
+        let requests = Resources::effective(
+            containers.iter().map(|container| &container.requests),
+            init_containers.iter().map(|container| &container.requests),
+            &overhead,
+        );
+        let limits = Resources::sum_limits(containers.iter().map(|container| &container.limits));
 
         Self {
             id: pod
@@ -207,9 +335,14 @@ impl From<K8sPod> for Pod {
                 .unwrap_or_default()
                 .into_iter()
                 .collect(),
-            containers: containers
+            containers,
+            init_containers,
+            ephemeral_containers,
+            volumes: spec
+                .and_then(|s| s.volumes.clone())
+                .unwrap_or_default()
                 .into_iter()
-                .map(|container| Container::new(container, &container_statuses))
+                .map(Into::into)
                 .collect(),
             pod_ip: pod_status.and_then(|s| s.pod_ip.clone()),
             host_ip: pod_status.and_then(|s| s.host_ip.clone()),
@@ -224,6 +357,13 @@ impl From<K8sPod> for Pod {
             phase: pod_status
                 .map(|s| Phase::from(s.phase.clone()))
                 .unwrap_or_default(),
+            node_name: pod.spec.as_ref().and_then(|s| s.node_name.clone()),
+            scheduling: Scheduling::new(&pod),
+            priority: pod.spec.as_ref().and_then(|s| s.priority),
+            priority_class_name: pod
+                .spec
+                .as_ref()
+                .and_then(|s| s.priority_class_name.clone()),
             controlled_by: pod
                 .metadata
                 .owner_references
@@ -250,31 +390,161 @@ impl From<K8sPod> for Pod {
                 .into_iter()
                 .map(Into::into)
                 .collect(),
+            node_selector: spec
+                .and_then(|s| s.node_selector.clone())
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            affinity: spec.and_then(|s| s.affinity.clone()).map(Into::into),
+            security_context: spec
+                .and_then(|s| s.security_context.clone())
+                .map(Into::into),
+            requests,
+            limits,
+            overhead,
+            usage: None,
+        }
+    }
+}
+
+impl Scheduling {
+    fn new(pod: &K8sPod) -> Self {
+        let status = pod.status.as_ref();
+        let condition = status
+            .and_then(|status| status.conditions.as_ref())
+            .and_then(|conditions| {
+                conditions
+                    .iter()
+                    .find(|condition| condition.type_ == "PodScheduled")
+            });
+
+        let scheduled = condition.map_or(false, |condition| condition.status == "True");
+
+        Self {
+            scheduler_name: pod.spec.as_ref().and_then(|s| s.scheduler_name.clone()),
+            nominated_node_name: status.and_then(|s| s.nominated_node_name.clone()),
+            scheduled,
+            scheduled_at: condition
+                .filter(|_| scheduled)
+                .and_then(|condition| condition.last_transition_time.as_ref())
+                .map(|time| time.0.timestamp()),
+            reason: condition
+                .filter(|_| !scheduled)
+                .and_then(|condition| condition.reason.clone()),
+            message: condition
+                .filter(|_| !scheduled)
+                .and_then(|condition| condition.message.clone()),
         }
     }
 }
 
+type ContainerStatuses = HashMap<(ContainerKind, ContainerId), K8sContainerStatus>;
+
 impl Container {
-    fn new(container: K8sContainer, statuses: &HashMap<ContainerId, K8sContainerStatus>) -> Self {
+    fn new(container: K8sContainer, kind: ContainerKind, statuses: &ContainerStatuses) -> Self {
         let container_id = ContainerId::from(container.name.clone());
-        let status = statuses.get(&container_id);
+        let status = statuses.get(&(kind, container_id.clone()));
+
+        let ready = status.map(|s| s.ready).unwrap_or_default();
+        let restart_count = status.map(|s| s.restart_count).unwrap_or_default();
+        let started = status.and_then(|s| s.started).unwrap_or_default();
+        let state = status
+            .and_then(|s| s.state.clone())
+            .map(ContainerState::from);
+        let last_state = status
+            .and_then(|s| s.last_state.clone())
+            .map(ContainerState::from);
 
         Self {
             id: container_id,
             name: container.name,
+            kind,
             image: container.image.unwrap_or_default(),
             image_id: status.map(|s| s.image_id.clone()),
-            last_state: status.and_then(|s| s.last_state.clone()).map(Into::into),
-            ready: status.map(|s| s.ready).unwrap_or_default(),
-            restart_count: status.map(|s| s.restart_count).unwrap_or_default(),
-            started: status.map(|s| s.started).is_some(),
-            state: status.and_then(|s| s.state.clone()).map(Into::into),
+            image_pull_policy: container.image_pull_policy,
+            command: container.command.unwrap_or_default(),
+            args: container.args.unwrap_or_default(),
+            health: ContainerHealth::new(
+                kind,
+                state.as_ref(),
+                last_state.as_ref(),
+                ready,
+                started,
+                restart_count,
+            ),
+            last_state,
+            ready,
+            restart_count,
+            started,
+            state,
             ports: container
                 .ports
                 .unwrap_or_default()
                 .into_iter()
                 .map(|port| port.container_port as u32)
                 .collect(),
+            env: container
+                .env
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            env_from: container
+                .env_from
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(EnvFromSource::new)
+                .collect(),
+            volume_mounts: container
+                .volume_mounts
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            liveness_probe: container.liveness_probe.map(Into::into),
+            readiness_probe: container.readiness_probe.map(Into::into),
+            startup_probe: container.startup_probe.map(Into::into),
+            security_context: container.security_context.map(Into::into),
+            target_container_name: None,
+            requests: Resources::from_quantities(
+                container
+                    .resources
+                    .as_ref()
+                    .and_then(|resources| resources.requests.as_ref()),
+            ),
+            limits: Resources::from_quantities(
+                container
+                    .resources
+                    .as_ref()
+                    .and_then(|resources| resources.limits.as_ref()),
+            ),
+            usage: None,
+        }
+    }
+
+    /// Ephemeral containers have the fields of a container, minus the ones that aren't allowed on
+    /// them like ports and probes, plus the container they target
+    fn ephemeral(container: K8sEphemeralContainer, statuses: &ContainerStatuses) -> Self {
+        let target_container_name = container.target_container_name.clone();
+        let container = K8sContainer {
+            name: container.name,
+            image: container.image,
+            image_pull_policy: container.image_pull_policy,
+            command: container.command,
+            args: container.args,
+            env: container.env,
+            env_from: container.env_from,
+            volume_mounts: container.volume_mounts,
+            security_context: container.security_context,
+            working_dir: container.working_dir,
+            stdin: container.stdin,
+            tty: container.tty,
+            ..K8sContainer::default()
+        };
+
+        Self {
+            target_container_name,
+            ..Self::new(container, ContainerKind::Ephemeral, statuses)
         }
     }
 }
@@ -334,6 +604,18 @@ impl From<K8sContainerState> for ContainerState {
     }
 }
 
+impl Phase {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Running => "Running",
+            Self::Failed => "Failed",
+            Self::Succeeded => "Succeeded",
+            Self::Unknown { raw_value } => raw_value,
+        }
+    }
+}
+
 impl From<Option<String>> for Phase {
     fn from(phase: Option<String>) -> Self {
         if phase.is_none() {
@@ -378,22 +660,262 @@ impl Pod {
         Faker.fake()
     }
 
+    /// Init containers, then the containers, then the ephemeral containers
+    pub fn all_containers(&self) -> impl Iterator<Item = &Container> {
+        self.init_containers
+            .iter()
+            .chain(&self.containers)
+            .chain(&self.ephemeral_containers)
+    }
+
+    /// Value of a downward api field path, ex: `metadata.labels['app']`, `None` for the ones only
+    /// known inside the container
+    pub fn field_path(&self, path: &str) -> Option<String> {
+        let map_key = |prefix: &str| path.strip_prefix(prefix)?.strip_suffix("']");
+
+        if let Some(key) = map_key("metadata.labels['") {
+            return self.labels.get(key).cloned();
+        }
+
+        if let Some(key) = map_key("metadata.annotations['") {
+            return self.annotations.get(key).cloned();
+        }
+
+        match path {
+            "metadata.name" => Some(self.name.clone()),
+            "metadata.namespace" => Some(self.namespace.clone()),
+            "spec.nodeName" => self.node_name.clone(),
+            "spec.serviceAccountName" => self.service_account.clone(),
+            "status.podIP" => self.pod_ip.clone(),
+            "status.podIPs" => Some(self.pod_ips.join(",")),
+            "status.hostIP" => self.host_ip.clone(),
+            _ => None,
+        }
+    }
+
     pub fn total_restart_count(&self) -> i32 {
         self.containers.iter().map(|c| c.restart_count).sum()
     }
+
+    pub fn is_daemon_set_pod(&self) -> bool {
+        self.controlled_by
+            .iter()
+            .any(|owner| owner.controller && owner.kind == "DaemonSet")
+    }
+
+    /// Static pods created by the kubelet, they can't be evicted through the api server
+    pub fn is_mirror_pod(&self) -> bool {
+        self.annotations.contains_key("kubernetes.io/config.mirror")
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        matches!(self.phase, Phase::Succeeded | Phase::Failed)
+    }
+
+    /// The limits the containers set, counted like the requests, a container without a limit adds
+    /// nothing, like the limits in the allocated resources of `kubectl describe node`
+    pub fn container_limits(&self) -> Resources {
+        Resources::effective(
+            self.containers.iter().map(|container| &container.limits),
+            self.init_containers
+                .iter()
+                .map(|container| &container.limits),
+            &self.overhead,
+        )
+    }
+
+    fn usage_percent(&self, percent: impl Fn(&Usage) -> Option<u32>) -> FieldValue<'_> {
+        self.usage
+            .as_ref()
+            .and_then(percent)
+            .map_or(FieldValue::Missing, |percent| {
+                FieldValue::Number(percent.into())
+            })
+    }
+
+    fn usage_value(&self, value: impl Fn(&Resources) -> Option<i64>) -> SortValue<'_> {
+        self.usage
+            .as_ref()
+            .and_then(|usage| value(&usage.used))
+            .map_or(SortValue::Missing, SortValue::Number)
+    }
+}
+
+impl ResourceRecord for Pod {
+    type Id = PodId;
+    type Usage = NamespacedPodUsage;
+    type History = ();
+
+    fn id(&self) -> &PodId {
+        &self.id
+    }
+
+    fn set_usage(&mut self, usage: Option<&NamespacedPodUsage>) {
+        let usage = usage.and_then(|usage| usage.get(&self.namespace));
+
+        for container in &mut self.containers {
+            container.usage = usage
+                .and_then(|usage| usage.containers.get(&container.name))
+                .map(|used| Usage::new(*used, &container.requests, &container.limits));
+        }
+
+        self.usage = usage.map(|usage| {
+            Usage::new(
+                Resources::sum(usage.containers.values()),
+                &self.requests,
+                &self.limits,
+            )
+        });
+    }
+}
+
+impl Sortable for Pod {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "namespace",
+        "containers",
+        "restarts",
+        "qos",
+        "age",
+        "phase",
+        "node",
+        "ip",
+        "cpu",
+        "memory",
+        "priority",
+    ];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "containers" => SortValue::Number(self.containers.len() as i64),
+            "restarts" => SortValue::Number(self.total_restart_count().into()),
+            "qos" => SortValue::optional(&self.qos_class),
+            // youngest first, like the age column counting up
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            "phase" => SortValue::Text(self.phase.as_str()),
+            "node" => SortValue::optional(&self.node_name),
+            "ip" => SortValue::optional(&self.pod_ip),
+            "cpu" => self.usage_value(|used| used.cpu_millis),
+            "memory" => self.usage_value(|used| used.memory_bytes),
+            "priority" => self.priority.map_or(SortValue::Missing, |priority| {
+                SortValue::Number(priority.into())
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for Pod {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("ns"),
+        QueryField::text("namespace"),
+        QueryField::labels("label"),
+        QueryField::text("phase"),
+        QueryField::number("restarts"),
+        QueryField::text("node"),
+        QueryField::text("ip"),
+        QueryField::text("qos"),
+        QueryField::text("image"),
+        QueryField::text("sa"),
+        QueryField::number("cpu_limit"),
+        QueryField::number("mem_limit"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "label" => FieldValue::Labels(&self.labels),
+            "phase" => FieldValue::Text(self.phase.as_str()),
+            "restarts" => FieldValue::Number(self.total_restart_count().into()),
+            "node" => FieldValue::optional(&self.node_name),
+            "ip" => FieldValue::Texts(self.pod_ips.iter().map(String::as_str).collect()),
+            "qos" => FieldValue::optional(&self.qos_class),
+            "image" => {
+                FieldValue::Texts(self.containers.iter().map(|c| c.image.as_str()).collect())
+            }
+            "sa" => FieldValue::optional(&self.service_account),
+            // percent of the limit used, `mem_limit:>90` finds pods about to be OOM killed
+            "cpu_limit" => self.usage_percent(|usage| usage.cpu_of_limit),
+            "mem_limit" => self.usage_percent(|usage| usage.memory_of_limit),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            "node" => Some("spec.nodeName"),
+            "sa" => Some("spec.serviceAccountName"),
+            _ => None,
+        }
+    }
 }
 
-pub async fn get_all(client: Client) -> Result<HashMap<PodId, Pod>> {
-    let pods_api: Api<K8sPod> = Api::all(client);
-    let pods = pods_api.list(&Default::default()).await?;
+impl KubeResource for K8sPod {
+    type Record = Pod;
+    const NAME: &'static str = "pod";
 
-    let pods_hash_map = pods
+    fn usage_fetcher(_cluster_id: &ClusterId, client: Client) -> Option<UsageFetcher<Self>> {
+        Some(Box::new(move || metrics::pod_usage(client.clone()).boxed()))
+    }
+}
+
+/// Pods on the node from the shared pod cache, or listed by node when no one is watching every pod,
+/// sorted by namespace and name, with their usage when the metrics api is served
+pub async fn on_node(cluster_id: &ClusterId, client: Client, node_id: &NodeId) -> Result<Vec<Pod>> {
+    let pods = match ResourceCache::<K8sPod>::get(cluster_id, &Selectors::default()) {
+        Some(cache) => {
+            cache.ready().await?;
+            cache
+                .records()
+                .into_values()
+                .filter(|pod| pod.node_name.as_deref() == Some(node_id.raw_value.as_str()))
+                .collect::<Vec<_>>()
+        }
+
+        None => {
+            let api: Api<K8sPod> = Api::all(client.clone());
+            let params =
+                ListParams::default().fields(&format!("spec.nodeName={}", node_id.raw_value));
+
+            api.list(&params)
+                .await?
+                .items
+                .into_iter()
+                .map(Pod::from)
+                .collect()
+        }
+    };
+
+    let usage = match metrics::pod_usage(client).await {
+        Ok(usage) => usage.unwrap_or_default(),
+        Err(error) => {
+            warn!("failed to get pod metrics: {error}");
+            HashMap::new()
+        }
+    };
+
+    let mut pods = pods
         .into_iter()
-        .map(Into::<Pod>::into)
-        .map(|pod| (pod.id.clone(), pod))
-        .collect();
+        .map(|mut pod| {
+            pod.set_usage(usage.get(&pod.id));
+            pod
+        })
+        .collect::<Vec<_>>();
 
-    Ok(pods_hash_map)
+    pods.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+    Ok(pods)
 }
 
 pub async fn delete(client: Client, pod: &Pod) -> Result<Either<K8sPod, Status>, Error> {
@@ -430,35 +952,3 @@ pub async fn delete_list_in_namespace(
         .filter_map(Result::ok)
         .collect()
 }
-
-pub async fn watch(
-    addr: Addr<PodViewModel>,
-    selected_cluster: ClusterId,
-    client: Client,
-) -> Result<()> {
-    use kube::runtime::watcher;
-    debug!("starting pod watcher for {:?}", selected_cluster);
-
-    let nodes_api: Api<K8sPod> = Api::all(client);
-
-    let mut stream = watcher(nodes_api, watcher::Config::default()).boxed();
-
-    while let Some(status) = stream.try_next().await? {
-        match status {
-            watcher::Event::Applied(pod) => {
-                debug!("applied event received on cluster {:?}", selected_cluster);
-                call!(addr.applied(pod.into())).await?;
-            }
-            watcher::Event::Deleted(pod) => {
-                debug!("deleted event received on cluster {:?}", selected_cluster);
-                call!(addr.deleted(pod.into())).await?;
-            }
-            watcher::Event::Restarted(_) => {
-                debug!("restarted event received on cluster {:?}", selected_cluster);
-                let _ = call!(addr.load_pods(selected_cluster.clone())).await;
-            }
-        }
-    }
-
-    Ok(())
-}
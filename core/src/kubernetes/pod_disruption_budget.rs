@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::{
+    api::policy::v1::PodDisruptionBudget as K8sPodDisruptionBudget,
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    core::LabelSelector,
+    node::NodeId,
+    pod::{Pod, PodId},
+    resource::{KubeResource, ResourceRecord},
+};
+
+uniffi::custom_newtype!(PodDisruptionBudgetId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct PodDisruptionBudgetId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct PodDisruptionBudget {
+    pub id: PodDisruptionBudgetId,
+    pub name: String,
+    pub namespace: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    /// absolute number or percentage, ex: `2` or `50%`
+    pub min_available: Option<String>,
+    pub max_unavailable: Option<String>,
+    /// a missing selector matches no pods, an empty one matches every pod in the namespace
+    pub selector: Option<LabelSelector>,
+    pub unhealthy_pod_eviction_policy: Option<String>,
+    pub current_healthy: i32,
+    pub desired_healthy: i32,
+    pub disruptions_allowed: i32,
+    pub expected_pods: i32,
+}
+
+/// A pod disruption budget that would refuse evictions while draining a node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct DrainBlocker {
+    pub pdb_id: PodDisruptionBudgetId,
+    pub pdb_name: String,
+    pub namespace: String,
+    pub disruptions_allowed: i32,
+    /// pods on the node covered by the budget
+    pub pods_on_node: Vec<PodId>,
+    /// pods on the node that can't be evicted until others become healthy again
+    pub blocked_pods: Vec<PodId>,
+    pub reason: String,
+}
+
+impl From<K8sPodDisruptionBudget> for PodDisruptionBudget {
+    fn from(pdb: K8sPodDisruptionBudget) -> Self {
+        let name = pdb
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown pod disruption budget name".to_string());
+
+        let namespace = pdb
+            .metadata
+            .namespace
+            .unwrap_or_else(|| "default".to_string());
+
+        let spec = pdb.spec.unwrap_or_default();
+        let status = pdb.status.unwrap_or_default();
+
+        Self {
+            id: format!("{namespace}/{name}").into(),
+            name,
+            namespace,
+            created_at: pdb.metadata.creation_timestamp.map(|t| t.0.timestamp()),
+            labels: pdb
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: pdb
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            min_available: spec.min_available.map(int_or_string),
+            max_unavailable: spec.max_unavailable.map(int_or_string),
+            selector: spec.selector.map(Into::into),
+            unhealthy_pod_eviction_policy: spec.unhealthy_pod_eviction_policy,
+            current_healthy: status.current_healthy,
+            desired_healthy: status.desired_healthy,
+            disruptions_allowed: status.disruptions_allowed,
+            expected_pods: status.expected_pods,
+        }
+    }
+}
+
+fn int_or_string(value: IntOrString) -> String {
+    match value {
+        IntOrString::Int(int) => int.to_string(),
+        IntOrString::String(string) => string,
+    }
+}
+
+impl PodDisruptionBudget {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    pub fn matches(&self, pod: &Pod) -> bool {
+        if pod.namespace != self.namespace {
+            return false;
+        }
+
+        self.selector
+            .as_ref()
+            .is_some_and(|selector| selector.matches(&pod.labels))
+    }
+
+    pub fn matching_pods<'a>(&self, pods: impl IntoIterator<Item = &'a Pod>) -> Vec<&'a Pod> {
+        pods.into_iter().filter(|pod| self.matches(pod)).collect()
+    }
+}
+
+/// Lists the pod disruption budgets that would refuse evicting pods when draining the node
+///
+/// Only pods a drain would evict are considered, so DaemonSet, mirror and finished pods are
+/// skipped. A budget blocks when it covers more pods on the node than it currently allows to be
+/// disrupted, the extra pods have to wait for replacements to become healthy elsewhere.
+pub fn drain_blockers<'a>(
+    node_id: &NodeId,
+    pdbs: impl IntoIterator<Item = &'a PodDisruptionBudget>,
+    pods: impl IntoIterator<Item = &'a Pod>,
+) -> Vec<DrainBlocker> {
+    let evictable_pods = pods
+        .into_iter()
+        .filter(|pod| pod.node_name.as_ref() == Some(&node_id.raw_value))
+        .filter(|pod| !pod.is_daemon_set_pod() && !pod.is_mirror_pod() && !pod.is_terminated())
+        .collect::<Vec<&Pod>>();
+
+    let mut blockers = pdbs
+        .into_iter()
+        .filter_map(|pdb| {
+            let mut pods_on_node = pdb
+                .matching_pods(evictable_pods.iter().copied())
+                .into_iter()
+                .map(|pod| pod.id.clone())
+                .collect::<Vec<_>>();
+
+            let allowed = pdb.disruptions_allowed.max(0) as usize;
+            if pods_on_node.len() <= allowed {
+                return None;
+            }
+
+            pods_on_node.sort();
+            let blocked_pods = pods_on_node[allowed..].to_vec();
+
+            let reason = if allowed == 0 {
+                format!(
+                    "{} allows no disruptions, {} of {} expected pods are healthy and {} are required",
+                    pdb.name, pdb.current_healthy, pdb.expected_pods, pdb.desired_healthy
+                )
+            } else {
+                format!(
+                    "{} allows {allowed} disruptions but covers {} pods on this node",
+                    pdb.name,
+                    pods_on_node.len()
+                )
+            };
+
+            Some(DrainBlocker {
+                pdb_id: pdb.id.clone(),
+                pdb_name: pdb.name.clone(),
+                namespace: pdb.namespace.clone(),
+                disruptions_allowed: pdb.disruptions_allowed,
+                pods_on_node,
+                blocked_pods,
+                reason,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // most blocked pods first
+    blockers.sort_by(|a, b| b.blocked_pods.len().cmp(&a.blocked_pods.len()));
+
+    blockers
+}
+
+#[uniffi::export]
+pub fn pod_disruption_budget_preview() -> PodDisruptionBudget {
+    PodDisruptionBudget::preview()
+}
+
+impl ResourceRecord for PodDisruptionBudget {
+    type Id = PodDisruptionBudgetId;
+    type Usage = ();
+    type History = ();
+
+    fn id(&self) -> &PodDisruptionBudgetId {
+        &self.id
+    }
+}
+
+impl Sortable for PodDisruptionBudget {
+    const COLUMNS: &'static [&'static str] = &[
+        "name",
+        "namespace",
+        "min",
+        "max",
+        "allowed",
+        "healthy",
+        "age",
+    ];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "min" => SortValue::optional(&self.min_available),
+            "max" => SortValue::optional(&self.max_unavailable),
+            "allowed" => SortValue::Number(self.disruptions_allowed.into()),
+            "healthy" => SortValue::Number(self.current_healthy.into()),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for PodDisruptionBudget {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("namespace"),
+        QueryField::text("ns"),
+        QueryField::number("allowed"),
+        QueryField::number("healthy"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "allowed" => FieldValue::Number(self.disruptions_allowed.into()),
+            "healthy" => FieldValue::Number(self.current_healthy.into()),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sPodDisruptionBudget {
+    type Record = PodDisruptionBudget;
+    const NAME: &'static str = "pod disruption budget";
+}
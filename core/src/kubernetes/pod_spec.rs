@@ -0,0 +1,909 @@
+//! Parts of the pod spec shown in the pod details, as complete as `kubectl describe pod`
+//!
+//! Env vars keep their references to ConfigMaps and Secrets, [`resolve_env`] looks up the values
+//! when the details are opened instead of for every pod in the list
+
+use std::collections::{BTreeMap, HashMap};
+
+use eyre::Result;
+use fake::Dummy;
+use k8s_openapi::{
+    api::core::v1::{
+        Affinity as K8sAffinity, ConfigMap, EnvFromSource as K8sEnvFromSource, EnvVar as K8sEnvVar,
+        NodeSelectorTerm as K8sNodeSelectorTerm, PodAffinityTerm as K8sPodAffinityTerm,
+        PodSecurityContext as K8sPodSecurityContext, Probe as K8sProbe, Secret,
+        SecurityContext as K8sSecurityContext, Volume as K8sVolume, VolumeMount as K8sVolumeMount,
+        WeightedPodAffinityTerm as K8sWeightedPodAffinityTerm,
+    },
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use kube::{Api, Client};
+use log::warn;
+use serde::de::DeserializeOwned;
+use uniffi::{Enum, Record};
+
+use super::{
+    core::{LabelSelector, LabelSelectorRequirement},
+    pod::{ContainerKind, Pod},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: EnvValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Enum, Dummy)]
+pub enum EnvValue {
+    Value {
+        value: String,
+    },
+    ConfigMapKey {
+        name: String,
+        key: String,
+        optional: bool,
+    },
+    SecretKey {
+        name: String,
+        key: String,
+        optional: bool,
+    },
+    /// Downward api, ex: `metadata.name`
+    Field {
+        field_path: String,
+    },
+    /// Downward api, ex: `limits.memory`
+    ResourceField {
+        container_name: Option<String>,
+        resource: String,
+    },
+}
+
+/// Every key of a ConfigMap or Secret as env vars
+#[derive(Debug, Clone, PartialEq, Eq, Enum, Dummy)]
+pub enum EnvFromSource {
+    ConfigMap {
+        name: String,
+        prefix: Option<String>,
+        optional: bool,
+    },
+    Secret {
+        name: String,
+        prefix: Option<String>,
+        optional: bool,
+    },
+}
+
+/// An env var with its value looked up, like the environment the container starts with
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct ResolvedEnvVar {
+    pub name: String,
+    /// `None` if it couldn't be resolved, or is only known inside the container
+    pub value: Option<String>,
+    /// ex: `configmap web-config (port)`, `None` for literal values
+    pub source: Option<String>,
+    /// From a Secret, should be hidden until revealed
+    pub secret: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record)]
+pub struct ContainerEnv {
+    pub container_name: String,
+    /// Init and regular containers can share a name
+    pub container_kind: ContainerKind,
+    /// `envFrom` first then `env`, a later var overrides an earlier one with the same name
+    pub env: Vec<ResolvedEnvVar>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct VolumeMount {
+    pub name: String,
+    pub mount_path: String,
+    pub sub_path: Option<String>,
+    pub read_only: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct Volume {
+    pub name: String,
+    pub source: VolumeSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Enum, Dummy)]
+pub enum VolumeSource {
+    EmptyDir {
+        medium: Option<String>,
+        size_limit: Option<String>,
+    },
+    ConfigMap {
+        name: String,
+    },
+    Secret {
+        secret_name: String,
+    },
+    PersistentVolumeClaim {
+        claim_name: String,
+        read_only: bool,
+    },
+    HostPath {
+        path: String,
+        type_: Option<String>,
+    },
+    /// Service account tokens, ConfigMaps, Secrets and the downward api in one directory
+    Projected {
+        sources: Vec<String>,
+    },
+    Csi {
+        driver: String,
+    },
+    /// Any other volume type, ex: `nfs`
+    Other {
+        kind: String,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct Probe {
+    pub handler: ProbeHandler,
+    pub initial_delay_seconds: i32,
+    pub timeout_seconds: i32,
+    pub period_seconds: i32,
+    pub success_threshold: i32,
+    pub failure_threshold: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Enum, Dummy)]
+pub enum ProbeHandler {
+    Exec {
+        command: Vec<String>,
+    },
+    HttpGet {
+        scheme: Option<String>,
+        host: Option<String>,
+        /// A number or the name of a container port
+        port: String,
+        path: Option<String>,
+    },
+    TcpSocket {
+        host: Option<String>,
+        port: String,
+    },
+    Grpc {
+        port: i32,
+        service: Option<String>,
+    },
+    #[default]
+    Unknown,
+}
+
+/// Security context of a pod or a container, each only sets the fields it has
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct SecurityContext {
+    pub run_as_user: Option<i64>,
+    pub run_as_group: Option<i64>,
+    pub run_as_non_root: Option<bool>,
+    pub seccomp_profile: Option<String>,
+
+    // pod only
+    pub fs_group: Option<i64>,
+    pub supplemental_groups: Vec<i64>,
+
+    // container only
+    pub privileged: Option<bool>,
+    pub allow_privilege_escalation: Option<bool>,
+    pub read_only_root_filesystem: Option<bool>,
+    pub capabilities_add: Vec<String>,
+    pub capabilities_drop: Vec<String>,
+}
+
+/// Scheduling constraints on the labels of the nodes and of the pods already on them, the
+/// required terms must match and the preferred ones add their weight to a node's score
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct Affinity {
+    /// Any of the terms has to match
+    pub node_required: Vec<NodeSelectorTerm>,
+    pub node_preferred: Vec<WeightedNodeSelectorTerm>,
+    pub pod_required: Vec<PodAffinityTerm>,
+    pub pod_preferred: Vec<WeightedPodAffinityTerm>,
+    pub pod_anti_required: Vec<PodAffinityTerm>,
+    pub pod_anti_preferred: Vec<WeightedPodAffinityTerm>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct NodeSelectorTerm {
+    pub match_expressions: Vec<LabelSelectorRequirement>,
+    pub match_fields: Vec<LabelSelectorRequirement>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct WeightedNodeSelectorTerm {
+    pub weight: i32,
+    pub term: NodeSelectorTerm,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct PodAffinityTerm {
+    pub label_selector: Option<LabelSelector>,
+    /// Empty for the namespace of the pod
+    pub namespaces: Vec<String>,
+    /// ex: `kubernetes.io/hostname` to spread or pack pods per node
+    pub topology_key: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct WeightedPodAffinityTerm {
+    pub weight: i32,
+    pub term: PodAffinityTerm,
+}
+
+impl Default for EnvValue {
+    fn default() -> Self {
+        Self::Value {
+            value: String::new(),
+        }
+    }
+}
+
+impl Default for VolumeSource {
+    fn default() -> Self {
+        Self::EmptyDir {
+            medium: None,
+            size_limit: None,
+        }
+    }
+}
+
+impl From<K8sEnvVar> for EnvVar {
+    fn from(env: K8sEnvVar) -> Self {
+        let source = env.value_from.unwrap_or_default();
+
+        let value = if let Some(selector) = source.config_map_key_ref {
+            EnvValue::ConfigMapKey {
+                name: selector.name.unwrap_or_default(),
+                key: selector.key,
+                optional: selector.optional.unwrap_or_default(),
+            }
+        } else if let Some(selector) = source.secret_key_ref {
+            EnvValue::SecretKey {
+                name: selector.name.unwrap_or_default(),
+                key: selector.key,
+                optional: selector.optional.unwrap_or_default(),
+            }
+        } else if let Some(selector) = source.field_ref {
+            EnvValue::Field {
+                field_path: selector.field_path,
+            }
+        } else if let Some(selector) = source.resource_field_ref {
+            EnvValue::ResourceField {
+                container_name: selector.container_name,
+                resource: selector.resource,
+            }
+        } else {
+            EnvValue::Value {
+                value: env.value.unwrap_or_default(),
+            }
+        };
+
+        Self {
+            name: env.name,
+            value,
+        }
+    }
+}
+
+impl EnvFromSource {
+    pub fn new(source: K8sEnvFromSource) -> Option<Self> {
+        if let Some(config_map) = source.config_map_ref {
+            return Some(Self::ConfigMap {
+                name: config_map.name.unwrap_or_default(),
+                prefix: source.prefix,
+                optional: config_map.optional.unwrap_or_default(),
+            });
+        }
+
+        source.secret_ref.map(|secret| Self::Secret {
+            name: secret.name.unwrap_or_default(),
+            prefix: source.prefix,
+            optional: secret.optional.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<K8sVolumeMount> for VolumeMount {
+    fn from(mount: K8sVolumeMount) -> Self {
+        Self {
+            name: mount.name,
+            mount_path: mount.mount_path,
+            sub_path: mount.sub_path.or(mount.sub_path_expr),
+            read_only: mount.read_only.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<K8sVolume> for Volume {
+    fn from(volume: K8sVolume) -> Self {
+        let source = if let Some(empty_dir) = &volume.empty_dir {
+            VolumeSource::EmptyDir {
+                medium: empty_dir.medium.clone().filter(|medium| !medium.is_empty()),
+                size_limit: empty_dir.size_limit.as_ref().map(|limit| limit.0.clone()),
+            }
+        } else if let Some(config_map) = &volume.config_map {
+            VolumeSource::ConfigMap {
+                name: config_map.name.clone().unwrap_or_default(),
+            }
+        } else if let Some(secret) = &volume.secret {
+            VolumeSource::Secret {
+                secret_name: secret.secret_name.clone().unwrap_or_default(),
+            }
+        } else if let Some(claim) = &volume.persistent_volume_claim {
+            VolumeSource::PersistentVolumeClaim {
+                claim_name: claim.claim_name.clone(),
+                read_only: claim.read_only.unwrap_or_default(),
+            }
+        } else if let Some(host_path) = &volume.host_path {
+            VolumeSource::HostPath {
+                path: host_path.path.clone(),
+                type_: host_path.type_.clone().filter(|type_| !type_.is_empty()),
+            }
+        } else if let Some(projected) = &volume.projected {
+            VolumeSource::Projected {
+                sources: projected
+                    .sources
+                    .iter()
+                    .flatten()
+                    .filter_map(|source| source_kind(source).into_iter().next())
+                    .collect(),
+            }
+        } else if let Some(csi) = &volume.csi {
+            VolumeSource::Csi {
+                driver: csi.driver.clone(),
+            }
+        } else {
+            VolumeSource::Other {
+                kind: source_kind(&volume)
+                    .into_iter()
+                    .find(|key| key != "name")
+                    .unwrap_or_else(|| "unknown".to_string()),
+            }
+        };
+
+        Self {
+            name: volume.name,
+            source,
+        }
+    }
+}
+
+/// Keys set on a volume source, the name of the source type for the ones without their own variant
+fn source_kind(source: &impl serde::Serialize) -> Vec<String> {
+    match serde_json::to_value(source) {
+        Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+impl From<K8sProbe> for Probe {
+    fn from(probe: K8sProbe) -> Self {
+        let port = |port: IntOrString| match port {
+            IntOrString::Int(port) => port.to_string(),
+            IntOrString::String(port) => port,
+        };
+
+        let handler = if let Some(exec) = probe.exec {
+            ProbeHandler::Exec {
+                command: exec.command.unwrap_or_default(),
+            }
+        } else if let Some(http_get) = probe.http_get {
+            ProbeHandler::HttpGet {
+                scheme: http_get.scheme,
+                host: http_get.host,
+                port: port(http_get.port),
+                path: http_get.path,
+            }
+        } else if let Some(tcp_socket) = probe.tcp_socket {
+            ProbeHandler::TcpSocket {
+                host: tcp_socket.host,
+                port: port(tcp_socket.port),
+            }
+        } else if let Some(grpc) = probe.grpc {
+            ProbeHandler::Grpc {
+                port: grpc.port,
+                service: grpc.service,
+            }
+        } else {
+            ProbeHandler::Unknown
+        };
+
+        // the defaults the api server fills in
+        Self {
+            handler,
+            initial_delay_seconds: probe.initial_delay_seconds.unwrap_or(0),
+            timeout_seconds: probe.timeout_seconds.unwrap_or(1),
+            period_seconds: probe.period_seconds.unwrap_or(10),
+            success_threshold: probe.success_threshold.unwrap_or(1),
+            failure_threshold: probe.failure_threshold.unwrap_or(3),
+        }
+    }
+}
+
+impl From<K8sSecurityContext> for SecurityContext {
+    fn from(context: K8sSecurityContext) -> Self {
+        let capabilities = context.capabilities.unwrap_or_default();
+
+        Self {
+            run_as_user: context.run_as_user,
+            run_as_group: context.run_as_group,
+            run_as_non_root: context.run_as_non_root,
+            seccomp_profile: context.seccomp_profile.map(|profile| profile.type_),
+            privileged: context.privileged,
+            allow_privilege_escalation: context.allow_privilege_escalation,
+            read_only_root_filesystem: context.read_only_root_filesystem,
+            capabilities_add: capabilities.add.unwrap_or_default(),
+            capabilities_drop: capabilities.drop.unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<K8sPodSecurityContext> for SecurityContext {
+    fn from(context: K8sPodSecurityContext) -> Self {
+        Self {
+            run_as_user: context.run_as_user,
+            run_as_group: context.run_as_group,
+            run_as_non_root: context.run_as_non_root,
+            seccomp_profile: context.seccomp_profile.map(|profile| profile.type_),
+            fs_group: context.fs_group,
+            supplemental_groups: context.supplemental_groups.unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+}
+
+impl From<K8sAffinity> for Affinity {
+    fn from(affinity: K8sAffinity) -> Self {
+        let node = affinity.node_affinity.unwrap_or_default();
+        let pod = affinity.pod_affinity.unwrap_or_default();
+        let pod_anti = affinity.pod_anti_affinity.unwrap_or_default();
+
+        let terms = |terms: Option<Vec<K8sPodAffinityTerm>>| {
+            terms
+                .unwrap_or_default()
+                .into_iter()
+                .map(PodAffinityTerm::from)
+                .collect()
+        };
+
+        let weighted = |terms: Option<Vec<K8sWeightedPodAffinityTerm>>| {
+            terms
+                .unwrap_or_default()
+                .into_iter()
+                .map(|term| WeightedPodAffinityTerm {
+                    weight: term.weight,
+                    term: term.pod_affinity_term.into(),
+                })
+                .collect()
+        };
+
+        Self {
+            node_required: node
+                .required_during_scheduling_ignored_during_execution
+                .map(|selector| selector.node_selector_terms)
+                .unwrap_or_default()
+                .into_iter()
+                .map(NodeSelectorTerm::from)
+                .collect(),
+            node_preferred: node
+                .preferred_during_scheduling_ignored_during_execution
+                .unwrap_or_default()
+                .into_iter()
+                .map(|term| WeightedNodeSelectorTerm {
+                    weight: term.weight,
+                    term: term.preference.into(),
+                })
+                .collect(),
+            pod_required: terms(pod.required_during_scheduling_ignored_during_execution),
+            pod_preferred: weighted(pod.preferred_during_scheduling_ignored_during_execution),
+            pod_anti_required: terms(pod_anti.required_during_scheduling_ignored_during_execution),
+            pod_anti_preferred: weighted(
+                pod_anti.preferred_during_scheduling_ignored_during_execution,
+            ),
+        }
+    }
+}
+
+impl From<K8sNodeSelectorTerm> for NodeSelectorTerm {
+    fn from(term: K8sNodeSelectorTerm) -> Self {
+        Self {
+            match_expressions: term
+                .match_expressions
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            match_fields: term
+                .match_fields
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl From<K8sPodAffinityTerm> for PodAffinityTerm {
+    fn from(term: K8sPodAffinityTerm) -> Self {
+        Self {
+            label_selector: term.label_selector.map(Into::into),
+            namespaces: term.namespaces.unwrap_or_default(),
+            topology_key: term.topology_key,
+        }
+    }
+}
+
+/// A ConfigMap or Secret referenced by the env
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Data {
+    Found(BTreeMap<String, String>),
+    Missing,
+
+    /// Not allowed to read it, it may well exist so its values are unknown rather than missing
+    Forbidden,
+}
+
+/// Env of every container of the pod, with the ConfigMaps and Secrets they reference looked up in
+/// the namespace of the pod
+pub async fn resolve_env(client: Client, pod: &Pod) -> Result<Vec<ContainerEnv>> {
+    let mut config_maps: HashMap<String, Data> = HashMap::new();
+    let mut secrets: HashMap<String, Data> = HashMap::new();
+
+    for container in pod.all_containers() {
+        for source in &container.env_from {
+            match source {
+                EnvFromSource::ConfigMap { name, .. } => {
+                    config_maps.insert(name.clone(), Data::Missing)
+                }
+                EnvFromSource::Secret { name, .. } => secrets.insert(name.clone(), Data::Missing),
+            };
+        }
+
+        for env in &container.env {
+            match &env.value {
+                EnvValue::ConfigMapKey { name, .. } => {
+                    config_maps.insert(name.clone(), Data::Missing)
+                }
+                EnvValue::SecretKey { name, .. } => secrets.insert(name.clone(), Data::Missing),
+                _ => None,
+            };
+        }
+    }
+
+    let config_map_api: Api<ConfigMap> = Api::namespaced(client.clone(), &pod.namespace);
+    for (name, data) in config_maps.iter_mut() {
+        *data = lookup(&config_map_api, "configmap", name, |config_map| {
+            let binary_data = config_map.binary_data.unwrap_or_default();
+
+            config_map
+                .data
+                .unwrap_or_default()
+                .into_iter()
+                .chain(
+                    binary_data
+                        .into_iter()
+                        .map(|(key, value)| (key, String::from_utf8_lossy(&value.0).into_owned())),
+                )
+                .collect()
+        })
+        .await?;
+    }
+
+    let secret_api: Api<Secret> = Api::namespaced(client, &pod.namespace);
+    for (name, data) in secrets.iter_mut() {
+        *data = lookup(&secret_api, "secret", name, |secret| {
+            secret
+                .data
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| (key, String::from_utf8_lossy(&value.0).into_owned()))
+                .collect()
+        })
+        .await?;
+    }
+
+    let env = pod
+        .all_containers()
+        .map(|container| ContainerEnv {
+            container_name: container.name.clone(),
+            container_kind: container.kind,
+            env: container
+                .env_from
+                .iter()
+                .flat_map(|source| resolve_env_from(source, &config_maps, &secrets))
+                .chain(
+                    container
+                        .env
+                        .iter()
+                        .map(|env| resolve_env_var(env, pod, &config_maps, &secrets)),
+                )
+                .collect(),
+        })
+        .collect();
+
+    Ok(env)
+}
+
+/// Not being allowed to read ConfigMaps or Secrets is common, the rest of the env is still resolved
+async fn lookup<K>(
+    api: &Api<K>,
+    kind: &str,
+    name: &str,
+    values: impl FnOnce(K) -> BTreeMap<String, String>,
+) -> Result<Data>
+where
+    K: Clone + DeserializeOwned + std::fmt::Debug,
+{
+    match api.get_opt(name).await {
+        Ok(Some(object)) => Ok(Data::Found(values(object))),
+        Ok(None) => Ok(Data::Missing),
+        Err(kube::Error::Api(response)) if response.code == 403 => {
+            warn!("not allowed to read {kind} {name}: {}", response.message);
+            Ok(Data::Forbidden)
+        }
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn resolve_env_from(
+    source: &EnvFromSource,
+    config_maps: &HashMap<String, Data>,
+    secrets: &HashMap<String, Data>,
+) -> Vec<ResolvedEnvVar> {
+    let (kind, name, prefix, optional, data, secret) = match source {
+        EnvFromSource::ConfigMap {
+            name,
+            prefix,
+            optional,
+        } => (
+            "configmap",
+            name,
+            prefix,
+            optional,
+            config_maps.get(name),
+            false,
+        ),
+        EnvFromSource::Secret {
+            name,
+            prefix,
+            optional,
+        } => ("secret", name, prefix, optional, secrets.get(name), true),
+    };
+
+    let source = Some(format!("{kind} {name}"));
+    let unresolved = |error: Option<String>| {
+        vec![ResolvedEnvVar {
+            name: format!("{}*", prefix.as_deref().unwrap_or_default()),
+            value: None,
+            source: source.clone(),
+            secret,
+            error,
+        }]
+    };
+
+    match data.unwrap_or(&Data::Missing) {
+        Data::Found(data) => data
+            .iter()
+            .map(|(key, value)| ResolvedEnvVar {
+                name: format!("{}{key}", prefix.as_deref().unwrap_or_default()),
+                value: Some(value.clone()),
+                source: source.clone(),
+                secret,
+                error: None,
+            })
+            .collect(),
+
+        Data::Forbidden => unresolved(None),
+        Data::Missing if *optional => vec![],
+        Data::Missing => unresolved(Some(format!("{kind} {name} not found"))),
+    }
+}
+
+fn resolve_env_var(
+    env: &EnvVar,
+    pod: &Pod,
+    config_maps: &HashMap<String, Data>,
+    secrets: &HashMap<String, Data>,
+) -> ResolvedEnvVar {
+    let resolved = |value: Option<String>, source: Option<String>| ResolvedEnvVar {
+        name: env.name.clone(),
+        value,
+        source,
+        secret: false,
+        error: None,
+    };
+
+    let lookup = |kind: &str, data: &HashMap<String, Data>, name: &str, key: &str, optional| {
+        let source = Some(format!("{kind} {name} ({key})"));
+
+        let (value, error) = match data.get(name).unwrap_or(&Data::Missing) {
+            Data::Found(data) => match data.get(key) {
+                Some(value) => (Some(value.clone()), None),
+                None if optional => (None, None),
+                None => (None, Some(format!("key {key} not found in {kind} {name}"))),
+            },
+            Data::Forbidden => (None, None),
+            Data::Missing if optional => (None, None),
+            Data::Missing => (None, Some(format!("{kind} {name} not found"))),
+        };
+
+        ResolvedEnvVar {
+            name: env.name.clone(),
+            value,
+            source,
+            secret: kind == "secret",
+            error,
+        }
+    };
+
+    match &env.value {
+        EnvValue::Value { value } => resolved(Some(value.clone()), None),
+        EnvValue::ConfigMapKey {
+            name,
+            key,
+            optional,
+        } => lookup("configmap", config_maps, name, key, *optional),
+        EnvValue::SecretKey {
+            name,
+            key,
+            optional,
+        } => lookup("secret", secrets, name, key, *optional),
+        EnvValue::Field { field_path } => resolved(
+            pod.field_path(field_path),
+            Some(format!("field {field_path}")),
+        ),
+        EnvValue::ResourceField {
+            container_name,
+            resource,
+        } => {
+            let source = match container_name {
+                Some(container_name) => format!("resource {resource} of {container_name}"),
+                None => format!("resource {resource}"),
+            };
+
+            resolved(None, Some(source))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(values: &[(&str, &str)]) -> Data {
+        Data::Found(
+            values
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    fn env(name: &str, value: EnvValue) -> EnvVar {
+        EnvVar {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn resolves_config_map_and_secret_keys() {
+        let config_maps = HashMap::from([("web".to_string(), data(&[("port", "8080")]))]);
+        let secrets = HashMap::from([
+            ("db".to_string(), Data::Missing),
+            ("api".to_string(), Data::Forbidden),
+        ]);
+        let pod = Pod::default();
+
+        let port = resolve_env_var(
+            &env(
+                "PORT",
+                EnvValue::ConfigMapKey {
+                    name: "web".to_string(),
+                    key: "port".to_string(),
+                    optional: false,
+                },
+            ),
+            &pod,
+            &config_maps,
+            &secrets,
+        );
+
+        assert_eq!(port.value.as_deref(), Some("8080"));
+        assert_eq!(port.source.as_deref(), Some("configmap web (port)"));
+        assert_eq!(port.error, None);
+
+        let password = resolve_env_var(
+            &env(
+                "PASSWORD",
+                EnvValue::SecretKey {
+                    name: "db".to_string(),
+                    key: "password".to_string(),
+                    optional: false,
+                },
+            ),
+            &pod,
+            &config_maps,
+            &secrets,
+        );
+
+        assert!(password.secret);
+        assert_eq!(password.value, None);
+        assert!(password.error.is_some());
+
+        // a secret that can't be read isn't known to be missing
+        let token = resolve_env_var(
+            &env(
+                "TOKEN",
+                EnvValue::SecretKey {
+                    name: "api".to_string(),
+                    key: "token".to_string(),
+                    optional: false,
+                },
+            ),
+            &pod,
+            &config_maps,
+            &secrets,
+        );
+
+        assert_eq!(token.value, None);
+        assert_eq!(token.error, None);
+    }
+
+    #[test]
+    fn resolves_downward_api_fields() {
+        let pod = Pod {
+            name: "web-0".to_string(),
+            labels: HashMap::from([("app".to_string(), "web".to_string())]),
+            ..Pod::default()
+        };
+
+        let resolve = |field_path: &str| {
+            let field_path = field_path.to_string();
+            resolve_env_var(
+                &env("FIELD", EnvValue::Field { field_path }),
+                &pod,
+                &HashMap::new(),
+                &HashMap::new(),
+            )
+            .value
+        };
+
+        assert_eq!(resolve("metadata.name").as_deref(), Some("web-0"));
+        assert_eq!(resolve("metadata.labels['app']").as_deref(), Some("web"));
+        assert_eq!(resolve("metadata.uid"), None);
+    }
+
+    #[test]
+    fn expands_env_from_with_a_prefix() {
+        let config_maps = HashMap::from([("web".to_string(), data(&[("a", "1"), ("b", "2")]))]);
+        let source = |name: &str, optional| EnvFromSource::ConfigMap {
+            name: name.to_string(),
+            prefix: Some("WEB_".to_string()),
+            optional,
+        };
+
+        let names = resolve_env_from(&source("web", false), &config_maps, &HashMap::new())
+            .into_iter()
+            .map(|env| env.name)
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec!["WEB_a", "WEB_b"]);
+        assert!(
+            resolve_env_from(&source("missing", true), &config_maps, &HashMap::new()).is_empty()
+        );
+        assert!(
+            resolve_env_from(&source("missing", false), &config_maps, &HashMap::new())[0]
+                .error
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::scheduling::v1::PriorityClass as K8sPriorityClass;
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    pod::{Pod, PodId},
+    resource::{KubeResource, ResourceRecord},
+};
+
+uniffi::custom_newtype!(PriorityClassId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct PriorityClassId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct PriorityClass {
+    pub id: PriorityClassId,
+    pub name: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub value: i32,
+    pub global_default: bool,
+    pub description: Option<String>,
+    /// `PreemptLowerPriority` or `Never`
+    pub preemption_policy: String,
+}
+
+/// Pods linked to a priority class
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct PriorityClassUsage {
+    pub priority_class_id: PriorityClassId,
+    /// pods created with this priority class
+    pub pods: Vec<PodId>,
+    /// running or pending pods with a lower priority, that pods of this class can preempt
+    pub preemptible_pod_count: u32,
+}
+
+impl From<K8sPriorityClass> for PriorityClass {
+    fn from(priority_class: K8sPriorityClass) -> Self {
+        let name = priority_class
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown priority class name".to_string());
+
+        Self {
+            id: name.clone().into(),
+            name,
+            created_at: priority_class
+                .metadata
+                .creation_timestamp
+                .map(|t| t.0.timestamp()),
+            labels: priority_class
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: priority_class
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            value: priority_class.value,
+            global_default: priority_class.global_default.unwrap_or_default(),
+            description: priority_class.description,
+            preemption_policy: priority_class
+                .preemption_policy
+                .unwrap_or_else(|| "PreemptLowerPriority".to_string()),
+        }
+    }
+}
+
+impl PriorityClass {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    pub fn can_preempt(&self) -> bool {
+        self.preemption_policy != "Never"
+    }
+
+    pub fn usage<'a>(&self, pods: impl IntoIterator<Item = &'a Pod>) -> PriorityClassUsage {
+        let mut usage = PriorityClassUsage {
+            priority_class_id: self.id.clone(),
+            ..Default::default()
+        };
+
+        for pod in pods {
+            if pod.priority_class_name.as_deref() == Some(self.name.as_str()) {
+                usage.pods.push(pod.id.clone());
+            }
+
+            // pods without a priority get the global default, which is 0 unless one is set
+            let pod_priority = pod.priority.unwrap_or_default();
+            if self.can_preempt() && !pod.is_terminated() && pod_priority < self.value {
+                usage.preemptible_pod_count += 1;
+            }
+        }
+
+        usage.pods.sort();
+        usage
+    }
+}
+
+#[uniffi::export]
+pub fn priority_class_preview() -> PriorityClass {
+    PriorityClass::preview()
+}
+
+impl ResourceRecord for PriorityClass {
+    type Id = PriorityClassId;
+    type Usage = ();
+    type History = ();
+
+    fn id(&self) -> &PriorityClassId {
+        &self.id
+    }
+}
+
+impl Sortable for PriorityClass {
+    const COLUMNS: &'static [&'static str] = &["name", "value", "preemption", "age"];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            // highest priority first
+            "value" => SortValue::Number(-i64::from(self.value)),
+            "preemption" => SortValue::Text(&self.preemption_policy),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for PriorityClass {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::number("value"),
+        QueryField::text("preemption"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "value" => FieldValue::Number(self.value.into()),
+            "preemption" => FieldValue::Text(&self.preemption_policy),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sPriorityClass {
+    type Record = PriorityClass;
+    const NAME: &'static str = "priority class";
+}
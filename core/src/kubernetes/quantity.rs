@@ -0,0 +1,90 @@
+use std::collections::{BTreeMap, HashMap};
+
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+/// Parses a kubernetes quantity string (ex: `500m`, `2Gi`, `1e3`) into its base unit value
+///
+/// CPU values are returned in cores and memory values in bytes
+pub fn parse(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    if quantity.is_empty() {
+        return None;
+    }
+
+    let split_at = quantity
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '+' || c == '-'))
+        .unwrap_or(quantity.len());
+
+    let (number, suffix) = quantity.split_at(split_at);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier = match suffix {
+        "" => 1.0,
+        "n" => 1e-9,
+        "u" => 1e-6,
+        "m" => 1e-3,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        "P" => 1e15,
+        "E" => 1e18,
+        "Ki" => 1024_f64,
+        "Mi" => 1024_f64.powi(2),
+        "Gi" => 1024_f64.powi(3),
+        "Ti" => 1024_f64.powi(4),
+        "Pi" => 1024_f64.powi(5),
+        "Ei" => 1024_f64.powi(6),
+        exponent if exponent.starts_with(['e', 'E']) => {
+            let exponent: i32 = exponent[1..].parse().ok()?;
+            10_f64.powi(exponent)
+        }
+        _ => return None,
+    };
+
+    Some(number * multiplier)
+}
+
+/// Percentage of `used` compared to `total`, a `total` of zero is fully used once anything is
+/// used
+pub fn percentage(used: f64, total: f64) -> f64 {
+    if total == 0.0 {
+        return if used == 0.0 { 0.0 } else { 100.0 };
+    }
+
+    used / total * 100.0
+}
+
+pub fn to_string_map(quantities: Option<BTreeMap<String, Quantity>>) -> HashMap<String, String> {
+    quantities
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, quantity)| (name, quantity.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse("2"), Some(2.0));
+        assert_eq!(parse("500m"), Some(0.5));
+        assert_eq!(parse("1.5"), Some(1.5));
+        assert_eq!(parse("1Ki"), Some(1024.0));
+        assert_eq!(parse("2Gi"), Some(2.0 * 1024.0 * 1024.0 * 1024.0));
+        assert_eq!(parse("3M"), Some(3_000_000.0));
+        assert_eq!(parse("1e3"), Some(1000.0));
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("ten"), None);
+        assert_eq!(parse("10Zi"), None);
+    }
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!(percentage(5.0, 10.0), 50.0);
+        assert_eq!(percentage(0.0, 0.0), 0.0);
+        assert_eq!(percentage(1.0, 0.0), 100.0);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc, time::Duration};
+
+use act_zero::{call, WeakAddr};
+use eyre::Result;
+use futures::future::BoxFuture;
+use kube::{
+    api::{ListParams, WatchParams},
+    Client,
+};
+use log::{debug, warn};
+use serde::de::DeserializeOwned;
+use uniffi::Record;
+
+use crate::{
+    cluster::ClusterId,
+    query::{Queryable, Selectors},
+    table::Sortable,
+    view_models::resource::ResourceViewModel,
+};
+
+use super::cache::{Change, ResourceCache};
+
+/// A uniffi record shown in a resource tab, filtered by the [`Query`](crate::query::Query) typed
+/// in the search box and sorted by the columns of its table
+pub trait ResourceRecord:
+    Queryable + Sortable + Debug + Clone + PartialEq + Send + Sync + 'static
+{
+    type Id: Debug + Clone + Eq + Hash + Ord + Send + Sync + 'static;
+
+    /// Live usage from the metrics api, `()` for kinds without metrics
+    type Usage: Debug + Clone + PartialEq + Send + Sync + 'static;
+
+    /// Changes the watcher observed on one object, `()` for kinds without history
+    type History: Debug + Clone + Default + Send + Sync + 'static;
+
+    /// How often the fields derived from the history are recomputed while the object doesn't
+    /// change, ex: a node stops flapping once its flaps are out of the window, `None` when they
+    /// only change with the object
+    const REOBSERVE_INTERVAL: Option<Duration> = None;
+
+    fn id(&self) -> &Self::Id;
+
+    /// Merges the usage into the record, `None` when the metrics api has none for it
+    fn set_usage(&mut self, _usage: Option<&Self::Usage>) {}
+
+    /// Records what changed since the object was last observed, and sets the fields derived from
+    /// the history on the record. The same object is observed again each time it is read, only
+    /// changes may be recorded
+    fn observe(&mut self, _history: &mut Self::History, _observed_at: i64) {}
+}
+
+/// Objects requested per page when listing
+pub const PAGE_SIZE: u32 = 500;
+
+/// How long the api server keeps a watch open, it is resumed from the last resource version after,
+/// the api server caps it below 295s
+const WATCH_TIMEOUT_SECONDS: u32 = 290;
+
+/// How often the usage is fetched from the metrics api, metrics-server scrapes every 15s by
+/// default
+pub const USAGE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A kubernetes object and the record it is converted into for the UI
+pub trait KubeResource:
+    kube::Resource<DynamicType = ()>
+    + Clone
+    + Default
+    + DeserializeOwned
+    + Debug
+    + Send
+    + Sync
+    + 'static
+{
+    type Record: ResourceRecord + From<Self>;
+
+    /// Name of the kind in logs and error messages, ex: `pod`, `runtime class`
+    const NAME: &'static str;
+
+    fn into_record(self) -> Self::Record {
+        self.into()
+    }
+
+    /// Fetches the usage of every object each time it is called, `None` if the kind has no usage
+    fn usage_fetcher(_cluster_id: &ClusterId, _client: Client) -> Option<UsageFetcher<Self>> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Record)]
+pub struct ListProgress {
+    pub loaded: u64,
+
+    /// Estimate from the api server, not sent for every list
+    pub remaining: Option<u64>,
+}
+
+pub type RecordId<K> = <<K as KubeResource>::Record as ResourceRecord>::Id;
+pub type RecordUsage<K> = <<K as KubeResource>::Record as ResourceRecord>::Usage;
+pub type UsageById<K> = HashMap<RecordId<K>, RecordUsage<K>>;
+pub type RecordHistory<K> = <<K as KubeResource>::Record as ResourceRecord>::History;
+
+/// Returns `None` once there is no usage to fetch, ex: the metrics api isn't served
+pub type UsageFetcher<K> =
+    Box<dyn FnMut() -> BoxFuture<'static, Result<Option<UsageById<K>>>> + Send>;
+
+/// List parameters filtering by the selectors on the api server
+pub fn list_params(selectors: &Selectors) -> ListParams {
+    let mut params = ListParams::default();
+
+    if let Some(labels) = &selectors.labels {
+        params = params.labels(labels);
+    }
+
+    if let Some(fields) = &selectors.fields {
+        params = params.fields(fields);
+    }
+
+    params
+}
+
+/// Watch parameters filtering by the selectors on the api server, with bookmarks so a watch that
+/// saw no changes can still resume from a recent resource version
+pub fn watch_params(selectors: &Selectors) -> WatchParams {
+    let mut params = WatchParams::default().timeout(WATCH_TIMEOUT_SECONDS);
+
+    if let Some(labels) = &selectors.labels {
+        params = params.labels(labels);
+    }
+
+    if let Some(fields) = &selectors.fields {
+        params = params.fields(fields);
+    }
+
+    params
+}
+
+/// Forwards the changes of the shared cache to the view model until the task is aborted
+pub async fn watch<K: KubeResource>(
+    addr: WeakAddr<ResourceViewModel<K>>,
+    cache: Arc<ResourceCache<K>>,
+) -> Result<()> {
+    use tokio::sync::broadcast::error::RecvError;
+    debug!("watching {} cache for {:?}", K::NAME, cache.cluster_id());
+
+    // subscribe before reading the store so no change is missed in between
+    let mut changes = cache.subscribe();
+    call!(addr.connection_changed(cache.connection())).await?;
+
+    if cache.is_ready() {
+        call!(addr.replaced(cache.records())).await?;
+    } else if let Some(progress) = cache.progress() {
+        call!(addr.listed(cache.records(), progress)).await?;
+    }
+
+    // recomputes the fields derived from the history of records that didn't change
+    let mut reobserve = K::Record::REOBSERVE_INTERVAL
+        .map(|period| tokio::time::interval_at(tokio::time::Instant::now() + period, period));
+
+    loop {
+        let change = tokio::select! {
+            change = changes.recv() => change,
+            () = tick(&mut reobserve) => {
+                if cache.is_ready() {
+                    call!(addr.replaced(cache.records())).await?;
+                }
+                continue;
+            }
+        };
+
+        match change {
+            // pages are only served until the store is ready, its `Restarted` follows
+            Ok(Change::Listed(progress)) if !cache.is_ready() => {
+                call!(addr.listed(cache.records(), progress)).await?;
+            }
+            Ok(Change::Listed(_)) => {}
+            Ok(Change::Applied(resource)) => {
+                call!(addr.applied(cache.record(&resource))).await?;
+            }
+            Ok(Change::Deleted(resource)) => {
+                call!(addr.deleted(K::clone(&resource).into_record())).await?;
+            }
+            Ok(Change::Restarted) => {
+                debug!("{} cache restarted on {:?}", K::NAME, cache.cluster_id());
+                call!(addr.replaced(cache.records())).await?;
+            }
+            Ok(Change::Connection(state)) => {
+                call!(addr.connection_changed(state)).await?;
+            }
+            Err(RecvError::Lagged(skipped)) if cache.is_ready() => {
+                debug!("{} watcher lagged by {skipped} changes, reloading", K::NAME);
+                call!(addr.replaced(cache.records())).await?;
+            }
+            Err(RecvError::Lagged(_)) => {}
+            Err(RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+/// Waits for the next tick, forever without an interval
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Sends the usage to the view model every [`USAGE_INTERVAL`] until the task is aborted, stops
+/// right away if the kind has no usage
+pub async fn poll_usage<K: KubeResource>(
+    addr: WeakAddr<ResourceViewModel<K>>,
+    cluster_id: ClusterId,
+    client: Client,
+) -> Result<()> {
+    let Some(mut fetch) = K::usage_fetcher(&cluster_id, client) else {
+        return Ok(());
+    };
+
+    loop {
+        match fetch().await {
+            Ok(Some(usage)) => call!(addr.usage_loaded(usage)).await?,
+            Ok(None) => {
+                debug!("no {} metrics on the cluster", K::NAME);
+                return Ok(());
+            }
+            Err(error) => warn!("failed to get {} metrics: {error}", K::NAME),
+        }
+
+        tokio::time::sleep(USAGE_INTERVAL).await;
+    }
+}
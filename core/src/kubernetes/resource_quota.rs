@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::core::v1::ResourceQuota as K8sResourceQuota;
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    quantity,
+    resource::{KubeResource, ResourceRecord},
+};
+
+uniffi::custom_newtype!(ResourceQuotaId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct ResourceQuotaId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Record, Dummy)]
+pub struct ResourceQuota {
+    pub id: ResourceQuotaId,
+    pub name: String,
+    pub namespace: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    pub scopes: Vec<String>,
+    pub usages: Vec<QuotaUsage>,
+}
+
+/// Usage of a single resource tracked by a quota (ex: `requests.cpu`, `pods`)
+#[derive(Debug, Clone, Default, PartialEq, Record, Dummy)]
+pub struct QuotaUsage {
+    pub resource: String,
+    pub used: Option<String>,
+    pub hard: String,
+    pub used_percent: Option<f64>,
+}
+
+/// A quota resource whose usage is above the configured warning threshold
+#[derive(Debug, Clone, Default, PartialEq, Record, Dummy)]
+pub struct QuotaWarning {
+    pub quota_id: ResourceQuotaId,
+    pub quota_name: String,
+    pub namespace: String,
+    pub usage: QuotaUsage,
+}
+
+impl From<K8sResourceQuota> for ResourceQuota {
+    fn from(quota: K8sResourceQuota) -> Self {
+        let name = quota
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown resource quota name".to_string());
+
+        let namespace = quota
+            .metadata
+            .namespace
+            .unwrap_or_else(|| "default".to_string());
+
+        let status = quota.status.unwrap_or_default();
+        let used = quantity::to_string_map(status.used);
+
+        // status.hard is the enforced value, fallback to spec.hard when the status is not set yet
+        let hard = status
+            .hard
+            .or_else(|| quota.spec.as_ref().and_then(|spec| spec.hard.clone()));
+
+        let mut usages = quantity::to_string_map(hard)
+            .into_iter()
+            .map(|(resource, hard)| QuotaUsage::new(used.get(&resource).cloned(), resource, hard))
+            .collect::<Vec<_>>();
+
+        usages.sort_by(|a, b| a.resource.cmp(&b.resource));
+
+        Self {
+            id: format!("{namespace}/{name}").into(),
+            name,
+            namespace,
+            created_at: quota.metadata.creation_timestamp.map(|t| t.0.timestamp()),
+            labels: quota
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: quota
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            scopes: quota.spec.and_then(|spec| spec.scopes).unwrap_or_default(),
+            usages,
+        }
+    }
+}
+
+impl QuotaUsage {
+    fn new(used: Option<String>, resource: String, hard: String) -> Self {
+        let used_percent = used
+            .as_deref()
+            .and_then(quantity::parse)
+            .zip(quantity::parse(&hard))
+            .map(|(used, hard)| quantity::percentage(used, hard));
+
+        Self {
+            resource,
+            used,
+            hard,
+            used_percent,
+        }
+    }
+}
+
+impl ResourceQuota {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+
+    /// The highest usage percentage out of all the resources in the quota
+    pub fn max_used_percent(&self) -> Option<f64> {
+        self.usages
+            .iter()
+            .filter_map(|usage| usage.used_percent)
+            .max_by(|a, b| a.total_cmp(b))
+    }
+
+    pub fn warnings(&self, threshold: f64) -> Vec<QuotaWarning> {
+        self.usages
+            .iter()
+            .filter(|usage| {
+                usage
+                    .used_percent
+                    .is_some_and(|percent| percent >= threshold)
+            })
+            .map(|usage| QuotaWarning {
+                quota_id: self.id.clone(),
+                quota_name: self.name.clone(),
+                namespace: self.namespace.clone(),
+                usage: usage.clone(),
+            })
+            .collect()
+    }
+}
+
+#[uniffi::export]
+pub fn resource_quota_preview() -> ResourceQuota {
+    ResourceQuota::preview()
+}
+
+impl ResourceRecord for ResourceQuota {
+    type Id = ResourceQuotaId;
+    type Usage = ();
+    type History = ();
+
+    fn id(&self) -> &ResourceQuotaId {
+        &self.id
+    }
+}
+
+impl Sortable for ResourceQuota {
+    const COLUMNS: &'static [&'static str] = &["name", "namespace", "used", "age"];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "namespace" => SortValue::Text(&self.namespace),
+            "used" => self
+                .max_used_percent()
+                .map_or(SortValue::Missing, |percent| {
+                    SortValue::Number(percent as i64)
+                }),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for ResourceQuota {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("namespace"),
+        QueryField::text("ns"),
+        QueryField::text("resource"),
+        QueryField::number("used"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "ns" | "namespace" => FieldValue::Text(&self.namespace),
+            "resource" => FieldValue::Texts(
+                self.usages
+                    .iter()
+                    .map(|usage| usage.resource.as_str())
+                    .collect(),
+            ),
+            "used" => self
+                .max_used_percent()
+                .map_or(FieldValue::Missing, |percent| {
+                    FieldValue::Number(percent as i64)
+                }),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            "ns" | "namespace" => Some("metadata.namespace"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sResourceQuota {
+    type Record = ResourceQuota;
+    const NAME: &'static str = "resource quota";
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use derive_more::{AsRef, Display, From};
+use fake::{Dummy, Fake, Faker};
+use k8s_openapi::api::node::v1::RuntimeClass as K8sRuntimeClass;
+use uniffi::Record;
+
+use crate::{
+    query::{FieldValue, QueryField, Queryable},
+    table::{SortValue, Sortable},
+};
+
+use super::{
+    core::Toleration,
+    quantity,
+    resource::{KubeResource, ResourceRecord},
+};
+
+uniffi::custom_newtype!(RuntimeClassId, String);
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, From, Hash, Dummy, Display, AsRef,
+)]
+pub struct RuntimeClassId(String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Dummy)]
+pub struct RuntimeClass {
+    pub id: RuntimeClassId,
+    pub name: String,
+    pub created_at: Option<i64>,
+    pub labels: HashMap<String, String>,
+    pub annotations: HashMap<String, String>,
+    /// the CRI handler the runtime class maps to, ex: `runc`, `runsc`, `kata`
+    pub handler: String,
+    /// resources added to the pod's requests for the runtime itself
+    pub overhead: HashMap<String, String>,
+    /// pods using the runtime class are only scheduled on nodes with these labels
+    pub node_selector: HashMap<String, String>,
+    /// added to the tolerations of pods using the runtime class
+    pub tolerations: Vec<Toleration>,
+}
+
+impl From<K8sRuntimeClass> for RuntimeClass {
+    fn from(runtime_class: K8sRuntimeClass) -> Self {
+        let name = runtime_class
+            .metadata
+            .name
+            .unwrap_or_else(|| "Unknown runtime class name".to_string());
+
+        let scheduling = runtime_class.scheduling.unwrap_or_default();
+
+        Self {
+            id: name.clone().into(),
+            name,
+            created_at: runtime_class
+                .metadata
+                .creation_timestamp
+                .map(|t| t.0.timestamp()),
+            labels: runtime_class
+                .metadata
+                .labels
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            annotations: runtime_class
+                .metadata
+                .annotations
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            handler: runtime_class.handler,
+            overhead: quantity::to_string_map(
+                runtime_class
+                    .overhead
+                    .and_then(|overhead| overhead.pod_fixed),
+            ),
+            node_selector: scheduling
+                .node_selector
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+            tolerations: scheduling
+                .tolerations
+                .unwrap_or_default()
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl RuntimeClass {
+    pub fn preview() -> Self {
+        Faker.fake()
+    }
+}
+
+#[uniffi::export]
+pub fn runtime_class_preview() -> RuntimeClass {
+    RuntimeClass::preview()
+}
+
+impl ResourceRecord for RuntimeClass {
+    type Id = RuntimeClassId;
+    type Usage = ();
+    type History = ();
+
+    fn id(&self) -> &RuntimeClassId {
+        &self.id
+    }
+}
+
+impl Sortable for RuntimeClass {
+    const COLUMNS: &'static [&'static str] = &["name", "handler", "age"];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_> {
+        match field {
+            "name" => SortValue::Text(&self.name),
+            "handler" => SortValue::Text(&self.handler),
+            "age" => self.created_at.map_or(SortValue::Missing, |created_at| {
+                SortValue::Number(-created_at)
+            }),
+            _ => SortValue::Missing,
+        }
+    }
+}
+
+impl Queryable for RuntimeClass {
+    const FIELDS: &'static [QueryField] = &[
+        QueryField::text("name"),
+        QueryField::text("handler"),
+        QueryField::labels("label"),
+    ];
+
+    fn field(&self, name: &str) -> FieldValue<'_> {
+        match name {
+            "name" => FieldValue::Text(&self.name),
+            "handler" => FieldValue::Text(&self.handler),
+            "label" => FieldValue::Labels(&self.labels),
+            _ => FieldValue::Missing,
+        }
+    }
+
+    fn search_text(&self) -> &str {
+        &self.name
+    }
+
+    fn field_selector(name: &str) -> Option<&'static str> {
+        match name {
+            "name" => Some("metadata.name"),
+            _ => None,
+        }
+    }
+}
+
+impl KubeResource for K8sRuntimeClass {
+    type Record = RuntimeClass;
+    const NAME: &'static str = "runtime class";
+}
@@ -6,7 +6,10 @@ mod tab;
 mod tab_group;
 
 pub mod env;
+pub mod fuzzy;
 pub mod kubernetes;
+pub mod query;
+pub mod table;
 pub mod task;
 pub mod timestamps;
 pub mod user_config;
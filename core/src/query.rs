@@ -0,0 +1,758 @@
+//! Search box query language shared by the resource tabs
+//!
+//! A query is a list of terms separated by whitespace, a record is shown if it matches every term:
+//! - `web` free text, fuzzy matched on the name of the record, the best matches sort first
+//! - `ns:kube-system` field equals the value, case insensitive
+//! - `node:ip-10-*` glob, `*` matches any run of characters and `?` a single one
+//! - `phase:!Running` negated, on free text `!web` hides names containing `web`
+//! - `restarts:>3` numeric fields take `>`, `>=`, `<`, `<=` or `=`
+//! - `label:app=web` labels match the key and the value, `label:app` only needs the key
+//! - `name:"my pod"` quotes keep the whitespace in a value
+//!
+//! A query made only of labels and fields the api server can select on, without globs, is also
+//! sent to the api server as [`Selectors`] so only the matching objects are listed and watched
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use thiserror::Error;
+
+use crate::fuzzy::{self, FuzzyMatch};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum QueryError {
+    #[error("Unknown field `{field}`, expected one of: {expected}")]
+    UnknownField { field: String, expected: String },
+
+    #[error("Missing value for `{0}`")]
+    MissingValue(String),
+
+    #[error("`{field}` expects a number like `3` or `>3`, got `{value}`")]
+    InvalidNumber { field: String, value: String },
+
+    #[error("Missing closing quote")]
+    UnclosedQuote,
+
+    #[error("`{0}` isn't a valid label selector, it is only matched on the records listed")]
+    InvalidLabel(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    Labels,
+}
+
+/// A field a record can be filtered on, ex: `ns`, `restarts`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryField {
+    pub name: &'static str,
+    pub kind: FieldKind,
+}
+
+impl QueryField {
+    pub const fn text(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: FieldKind::Text,
+        }
+    }
+
+    pub const fn number(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: FieldKind::Number,
+        }
+    }
+
+    pub const fn labels(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: FieldKind::Labels,
+        }
+    }
+}
+
+/// Value of a field on a record, a field the record doesn't have only matches negated terms
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue<'a> {
+    Text(&'a str),
+    /// matches if any of the values does, ex: the images of every container
+    Texts(Vec<&'a str>),
+    Number(i64),
+    Labels(&'a HashMap<String, String>),
+    Missing,
+}
+
+impl<'a> FieldValue<'a> {
+    pub fn optional(value: &'a Option<String>) -> Self {
+        value.as_deref().map_or(Self::Missing, Self::Text)
+    }
+}
+
+/// A record that can be filtered with a [`Query`]
+pub trait Queryable {
+    /// Every field name accepted in a query, including aliases
+    const FIELDS: &'static [QueryField];
+
+    /// Value of the field, only called with names from [`Queryable::FIELDS`]
+    fn field(&self, name: &str) -> FieldValue<'_>;
+
+    /// Text free text terms are fuzzy matched on, and highlighted in, usually the name
+    fn search_text(&self) -> &str;
+
+    /// Api field selector path of a text field, ex: `spec.nodeName`, only fields with one are
+    /// sent to the api server
+    fn field_selector(_name: &str) -> Option<&'static str> {
+        None
+    }
+}
+
+/// Label and field selectors for listing and watching
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Selectors {
+    pub labels: Option<String>,
+    pub fields: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Matcher {
+    Text(String),
+    Field {
+        name: &'static str,
+        pattern: Pattern,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Pattern {
+    Glob(String),
+    Compare(Comparison, i64),
+    Label { key: String, value: Option<String> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+impl Query {
+    pub fn parse<R: Queryable>(input: &str) -> Result<Self, QueryError> {
+        let terms = tokenize(input)?
+            .into_iter()
+            .map(|token| parse_term::<R>(&token))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Whether the query has free text, the matches are then sorted by score
+    pub fn has_text(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| !term.negated && matches!(term.matcher, Matcher::Text(_)))
+    }
+
+    pub fn matches<R: Queryable>(&self, record: &R) -> bool {
+        self.score(record).is_some()
+    }
+
+    /// The terms that can be sent to the api server as selectors, the terms are ANDed so the
+    /// others are still matched here on what it returns. Values are sent as typed, except field
+    /// values which are lower cased like the object names they select on
+    pub fn selectors<R: Queryable>(&self) -> Selectors {
+        let mut labels = vec![];
+        let mut fields = vec![];
+
+        for term in &self.terms {
+            let Matcher::Field { name, pattern } = &term.matcher else {
+                continue;
+            };
+
+            let operator = if term.negated { "!=" } else { "=" };
+
+            match pattern {
+                Pattern::Label { key, value } if !has_glob(key) && is_label_key(key) => match value
+                {
+                    Some(value) if !has_glob(value) && is_label_value(value) => {
+                        labels.push(format!("{key}{operator}{value}"))
+                    }
+                    Some(_) => {}
+                    None if term.negated => labels.push(format!("!{key}")),
+                    None => labels.push(key.clone()),
+                },
+                Pattern::Glob(value) if !has_glob(value) => {
+                    if let Some(path) = R::field_selector(name) {
+                        fields.push(format!("{path}{operator}{}", value.to_lowercase()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let join = |selectors: Vec<String>| (!selectors.is_empty()).then(|| selectors.join(","));
+
+        Selectors {
+            labels: join(labels),
+            fields: join(fields),
+        }
+    }
+
+    /// The first label term that isn't valid label syntax, it isn't sent as a selector so the api
+    /// server doesn't reject the list, globs are always matched here and aren't checked
+    pub fn invalid_label(&self) -> Option<QueryError> {
+        self.terms.iter().find_map(|term| {
+            let Matcher::Field {
+                pattern: Pattern::Label { key, value },
+                ..
+            } = &term.matcher
+            else {
+                return None;
+            };
+
+            let invalid_key = !has_glob(key) && !is_label_key(key);
+            let invalid_value = value
+                .as_ref()
+                .is_some_and(|value| !has_glob(value) && !is_label_value(value));
+
+            (invalid_key || invalid_value).then(|| {
+                QueryError::InvalidLabel(match value {
+                    Some(value) => format!("{key}={value}"),
+                    None => key.clone(),
+                })
+            })
+        })
+    }
+
+    /// The fuzzy match of the free text terms on [`Queryable::search_text`], `None` if any term
+    /// doesn't match
+    pub fn score<R: Queryable>(&self, record: &R) -> Option<FuzzyMatch> {
+        let mut score = FuzzyMatch::default();
+
+        for term in &self.terms {
+            match (&term.matcher, term.negated) {
+                (Matcher::Text(text), false) => {
+                    score.merge(fuzzy::fuzzy_match(text, record.search_text())?);
+                }
+                (Matcher::Text(text), true) => {
+                    let search_text = record.search_text().to_lowercase();
+                    if search_text.contains(&text.to_lowercase()) {
+                        return None;
+                    }
+                }
+                (Matcher::Field { name, pattern }, negated) => {
+                    if pattern.matches(&record.field(name)) == negated {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(score)
+    }
+}
+
+impl Pattern {
+    fn matches(&self, value: &FieldValue) -> bool {
+        match (self, value) {
+            (Self::Glob(glob), FieldValue::Text(text)) => glob_matches(glob, text),
+            (Self::Glob(glob), FieldValue::Texts(texts)) => {
+                texts.iter().any(|text| glob_matches(glob, text))
+            }
+            (Self::Glob(glob), FieldValue::Number(number)) => {
+                glob_matches(glob, &number.to_string())
+            }
+            (Self::Compare(comparison, expected), FieldValue::Number(number)) => {
+                comparison.matches(number.cmp(expected))
+            }
+            (Self::Label { key, value }, FieldValue::Labels(labels)) => {
+                // labels are case sensitive, like the label selectors they are sent as
+                labels.iter().any(|(label_key, label_value)| {
+                    glob_matches_case_sensitive(key, label_key)
+                        && value.as_ref().map_or(true, |value| {
+                            glob_matches_case_sensitive(value, label_value)
+                        })
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Comparison {
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Equal => ordering.is_eq(),
+            Self::Greater => ordering.is_gt(),
+            Self::GreaterOrEqual => ordering.is_ge(),
+            Self::Less => ordering.is_lt(),
+            Self::LessOrEqual => ordering.is_le(),
+        }
+    }
+}
+
+/// Splits on whitespace outside of double quotes, removing the quotes
+fn tokenize(input: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut in_quotes = false;
+
+    for char in input.chars() {
+        match char {
+            '"' => in_quotes = !in_quotes,
+            char if char.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            char => token.push(char),
+        }
+    }
+
+    if in_quotes {
+        return Err(QueryError::UnclosedQuote);
+    }
+
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term<R: Queryable>(token: &str) -> Result<Term, QueryError> {
+    let Some((name, value)) = token.split_once(':') else {
+        let (negated, text) = negation(token);
+
+        return Ok(Term {
+            negated,
+            matcher: Matcher::Text(text.to_string()),
+        });
+    };
+
+    let field = R::FIELDS
+        .iter()
+        .find(|field| field.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| QueryError::UnknownField {
+            field: name.to_string(),
+            expected: R::FIELDS
+                .iter()
+                .map(|field| field.name)
+                .collect::<Vec<_>>()
+                .join(", "),
+        })?;
+
+    let (negated, value) = negation(value);
+    if value.is_empty() {
+        return Err(QueryError::MissingValue(field.name.to_string()));
+    }
+
+    let pattern = match field.kind {
+        FieldKind::Text => Pattern::Glob(value.to_string()),
+        FieldKind::Number => parse_comparison(value).ok_or_else(|| QueryError::InvalidNumber {
+            field: field.name.to_string(),
+            value: value.to_string(),
+        })?,
+        FieldKind::Labels => match value.split_once('=') {
+            Some((key, value)) => Pattern::Label {
+                key: key.to_string(),
+                value: Some(value.to_string()),
+            },
+            None => Pattern::Label {
+                key: value.to_string(),
+                value: None,
+            },
+        },
+    };
+
+    Ok(Term {
+        negated,
+        matcher: Matcher::Field {
+            name: field.name,
+            pattern,
+        },
+    })
+}
+
+fn negation(value: &str) -> (bool, &str) {
+    match value.strip_prefix('!') {
+        Some(value) => (true, value),
+        None => (false, value),
+    }
+}
+
+fn parse_comparison(value: &str) -> Option<Pattern> {
+    // two character operators first so `>=` isn't read as `>` followed by `=3`
+    let operators = [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ];
+
+    let (comparison, number) = operators
+        .iter()
+        .find_map(|(operator, comparison)| {
+            value
+                .strip_prefix(operator)
+                .map(|number| (*comparison, number))
+        })
+        .unwrap_or((Comparison::Equal, value));
+
+    let number = number.trim().parse().ok()?;
+    Some(Pattern::Compare(comparison, number))
+}
+
+fn has_glob(value: &str) -> bool {
+    value.contains(['*', '?'])
+}
+
+/// An optional dns subdomain prefix and `/`, followed by a name like [`is_label_value`] that
+/// isn't empty
+fn is_label_key(key: &str) -> bool {
+    let (prefix, name) = match key.split_once('/') {
+        Some((prefix, name)) => (Some(prefix), name),
+        None => (None, key),
+    };
+
+    prefix.map_or(true, is_dns_subdomain) && !name.is_empty() && is_label_value(name)
+}
+
+/// Empty, or up to 63 alphanumerics, `-`, `_` and `.`, starting and ending with an alphanumeric
+fn is_label_value(value: &str) -> bool {
+    if value.is_empty() {
+        return true;
+    }
+
+    value.len() <= 63
+        && value.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && value.ends_with(|c: char| c.is_ascii_alphanumeric())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Up to 253 characters of `.` separated lower case alphanumerics and `-`, each part starting and
+/// ending with an alphanumeric
+fn is_dns_subdomain(value: &str) -> bool {
+    value.len() <= 253
+        && value.split('.').all(|part| {
+            part.len() <= 63
+                && part.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && part.ends_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
+}
+
+/// Case insensitive match of the whole text, `*` matches any run of characters, `?` any one
+fn glob_matches(glob: &str, text: &str) -> bool {
+    glob_matches_case_sensitive(&glob.to_lowercase(), &text.to_lowercase())
+}
+
+fn glob_matches_case_sensitive(glob: &str, text: &str) -> bool {
+    let glob = glob.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut glob_index, mut text_index) = (0, 0);
+
+    // position of the last `*` and the text index it was tried at, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+
+    while text_index < text.len() {
+        match glob.get(glob_index) {
+            Some('*') => {
+                star = Some((glob_index, text_index));
+                glob_index += 1;
+            }
+            Some(char) if *char == '?' || *char == text[text_index] => {
+                glob_index += 1;
+                text_index += 1;
+            }
+            _ => match star {
+                Some((star_index, star_text_index)) => {
+                    glob_index = star_index + 1;
+                    text_index = star_text_index + 1;
+                    star = Some((star_index, star_text_index + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[glob_index..].iter().all(|char| *char == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use maplit::hashmap;
+
+    struct Record {
+        name: String,
+        namespace: String,
+        restarts: i64,
+        node: Option<String>,
+        labels: HashMap<String, String>,
+    }
+
+    impl Queryable for Record {
+        const FIELDS: &'static [QueryField] = &[
+            QueryField::text("name"),
+            QueryField::text("ns"),
+            QueryField::number("restarts"),
+            QueryField::text("node"),
+            QueryField::labels("label"),
+        ];
+
+        fn field(&self, name: &str) -> FieldValue<'_> {
+            match name {
+                "name" => FieldValue::Text(&self.name),
+                "ns" => FieldValue::Text(&self.namespace),
+                "restarts" => FieldValue::Number(self.restarts),
+                "node" => FieldValue::optional(&self.node),
+                "label" => FieldValue::Labels(&self.labels),
+                _ => FieldValue::Missing,
+            }
+        }
+
+        fn search_text(&self) -> &str {
+            &self.name
+        }
+
+        fn field_selector(name: &str) -> Option<&'static str> {
+            match name {
+                "ns" => Some("metadata.namespace"),
+                "node" => Some("spec.nodeName"),
+                _ => None,
+            }
+        }
+    }
+
+    fn record() -> Record {
+        Record {
+            name: "web-7f9c".to_string(),
+            namespace: "kube-system".to_string(),
+            restarts: 5,
+            node: Some("ip-10-0-1-12".to_string()),
+            labels: hashmap! { "app".to_string() => "web".to_string() },
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        Query::parse::<Record>(query).unwrap().matches(&record())
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert!(Query::parse::<Record>("  ").unwrap().is_empty());
+        assert!(matches(""));
+    }
+
+    #[test]
+    fn combines_terms() {
+        assert!(matches(
+            "ns:kube-system label:app=web restarts:>3 node:ip-10-*"
+        ));
+        assert!(!matches("ns:kube-system label:app=api"));
+    }
+
+    #[test]
+    fn negates_terms() {
+        assert!(matches("ns:!default"));
+        assert!(!matches("ns:!kube-system"));
+        assert!(matches("!api"));
+        assert!(!matches("!web"));
+    }
+
+    #[test]
+    fn compares_numbers() {
+        assert!(matches("restarts:5"));
+        assert!(matches("restarts:>=5"));
+        assert!(!matches("restarts:>5"));
+        assert!(matches("restarts:<=5"));
+        assert!(!matches("restarts:<5"));
+        assert!(matches("restarts:!0"));
+    }
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_matches("ip-10-*", "ip-10-0-1-12"));
+        assert!(glob_matches("*-7f9?", "web-7f9c"));
+        assert!(glob_matches("KUBE-*", "kube-system"));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(!glob_matches("ip-10-*", "ip-11-0-1-12"));
+        assert!(!glob_matches("web", "web-7f9c"));
+    }
+
+    #[test]
+    fn matches_label_keys() {
+        assert!(matches("label:app"));
+        assert!(matches("label:app=w*"));
+        assert!(!matches("label:tier"));
+        assert!(matches("label:!tier"));
+
+        // sent as a label selector, which is case sensitive
+        assert!(!matches("label:app=Web"));
+        assert!(!matches("label:App"));
+    }
+
+    #[test]
+    fn missing_fields_only_match_negated() {
+        let record = Record {
+            node: None,
+            ..record()
+        };
+
+        let query = Query::parse::<Record>("node:ip-*").unwrap();
+        assert!(!query.matches(&record));
+
+        let query = Query::parse::<Record>("node:!ip-*").unwrap();
+        assert!(query.matches(&record));
+    }
+
+    #[test]
+    fn fuzzy_matches_free_text() {
+        let record = Record {
+            name: "pg-pool-7f9c".to_string(),
+            ..record()
+        };
+
+        let query = Query::parse::<Record>("pgpool ns:kube-system").unwrap();
+        assert!(query.has_text());
+        assert_eq!(query.score(&record).unwrap().ranges.len(), 2);
+
+        let query = Query::parse::<Record>("poolpg").unwrap();
+        assert!(!query.matches(&record));
+    }
+
+    #[test]
+    fn pushes_down_selectors() {
+        let selectors = |query| Query::parse::<Record>(query).unwrap().selectors::<Record>();
+
+        assert_eq!(selectors(""), Selectors::default());
+        assert_eq!(
+            selectors("label:app=web label:!canary ns:Kube-System node:!ip-10-0-1-12"),
+            Selectors {
+                labels: Some("app=web,!canary".to_string()),
+                fields: Some(
+                    "metadata.namespace=kube-system,spec.nodeName!=ip-10-0-1-12".to_string()
+                ),
+            }
+        );
+
+        // free text, globs, numbers and fields without a selector path are only matched here,
+        // the rest of the query is still sent
+        assert_eq!(
+            selectors("web ns:default"),
+            Selectors {
+                labels: None,
+                fields: Some("metadata.namespace=default".to_string()),
+            }
+        );
+        assert_eq!(
+            selectors("label:app=web* label:tier=db restarts:>3 name:web"),
+            Selectors {
+                labels: Some("tier=db".to_string()),
+                fields: None,
+            }
+        );
+        assert_eq!(selectors("restarts:>3"), Selectors::default());
+
+        // invalid label syntax would fail the whole list, it is only matched here
+        assert_eq!(
+            selectors(r#"label:app=a,b "label:my app" label:app=we%b label:tier=db"#),
+            Selectors {
+                labels: Some("tier=db".to_string()),
+                fields: None,
+            }
+        );
+        assert_eq!(
+            selectors("label:app.kubernetes.io/name=web label:Bad_Prefix/name"),
+            Selectors {
+                labels: Some("app.kubernetes.io/name=web".to_string()),
+                fields: None,
+            }
+        );
+    }
+
+    #[test]
+    fn reports_invalid_labels() {
+        let invalid_label = |query| Query::parse::<Record>(query).unwrap().invalid_label();
+
+        assert_eq!(
+            invalid_label("label:app=web label:!canary label:app=web*"),
+            None
+        );
+        assert_eq!(
+            invalid_label("label:app=a,b"),
+            Some(QueryError::InvalidLabel("app=a,b".to_string()))
+        );
+        assert_eq!(
+            invalid_label(r#""label:my app""#),
+            Some(QueryError::InvalidLabel("my app".to_string()))
+        );
+        assert_eq!(
+            invalid_label("label:-app"),
+            Some(QueryError::InvalidLabel("-app".to_string()))
+        );
+
+        // still matched on the records
+        assert!(matches("label:app=web label:!a,b"));
+    }
+
+    #[test]
+    fn keeps_quoted_whitespace() {
+        assert_eq!(
+            tokenize(r#"name:"my pod" ns:default"#).unwrap(),
+            vec!["name:my pod", "ns:default"]
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert!(matches!(
+            Query::parse::<Record>("phase:Running"),
+            Err(QueryError::UnknownField { field, .. }) if field == "phase"
+        ));
+
+        assert_eq!(
+            Query::parse::<Record>("restarts:>many"),
+            Err(QueryError::InvalidNumber {
+                field: "restarts".to_string(),
+                value: ">many".to_string()
+            })
+        );
+
+        assert_eq!(
+            Query::parse::<Record>("ns:"),
+            Err(QueryError::MissingValue("ns".to_string()))
+        );
+
+        assert_eq!(
+            Query::parse::<Record>(r#"name:"web"#),
+            Err(QueryError::UnclosedQuote)
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use uniffi::{Enum, Record};
 
+use crate::kubernetes::custom_resource::{CustomResourceKind, CustomResourceKindId};
+
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Enum)]
 pub enum TabId {
     ClusterTab,
@@ -39,6 +41,7 @@ pub enum TabId {
     PodSecurityPolicies,
     Charts,
     Releases,
+    CustomResource { kind_id: CustomResourceKindId },
 }
 
 impl TabId {
@@ -81,6 +84,7 @@ impl TabId {
             TabId::PodSecurityPolicies => "Pod Security Policies".to_string(),
             TabId::Charts => "Charts".to_string(),
             TabId::Releases => "Releases".to_string(),
+            TabId::CustomResource { kind_id } => kind_id.to_string(),
         }
     }
 }
@@ -100,6 +104,16 @@ impl Tab {
             icon: icon.into(),
         }
     }
+
+    pub fn custom_resource(kind: &CustomResourceKind) -> Self {
+        Self {
+            id: TabId::CustomResource {
+                kind_id: kind.id.clone(),
+            },
+            icon: "puzzlepiece.extension".to_string(),
+            name: kind.kind.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq)]
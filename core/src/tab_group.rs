@@ -11,6 +11,7 @@ pub enum TabGroupId {
     Storage,
     AccessControl,
     Helm,
+    CustomResources,
 }
 
 impl TabGroupId {
@@ -23,6 +24,7 @@ impl TabGroupId {
             TabGroupId::Storage => "Storage".to_string(),
             TabGroupId::AccessControl => "Access Control".to_string(),
             TabGroupId::Helm => "Helm".to_string(),
+            TabGroupId::CustomResources => "Custom Resources".to_string(),
         }
     }
 }
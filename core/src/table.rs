@@ -0,0 +1,263 @@
+//! Sorting and column layout of the resource tables, shared by every front end
+//!
+//! Columns are identified by the name of the field they show, ex: `restarts`, a table can be
+//! sorted by any number of them, ties on the first key are ordered by the next one and so on
+
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use uniffi::Record;
+
+/// Sort by one column
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Record, Serialize, Deserialize)]
+pub struct SortKey {
+    pub field: String,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Record, Serialize, Deserialize)]
+pub struct Column {
+    pub field: String,
+    pub visible: bool,
+}
+
+/// Order and visibility of the columns of a table, and the keys it is sorted by
+#[derive(Debug, Clone, Default, PartialEq, Eq, Record, Serialize, Deserialize)]
+pub struct TableLayout {
+    pub columns: Vec<Column>,
+    pub sort: Vec<SortKey>,
+}
+
+/// Value of a field to sort on, a record without the field sorts before the ones with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortValue<'a> {
+    Missing,
+    Number(i64),
+    Text(&'a str),
+}
+
+impl<'a> SortValue<'a> {
+    pub fn optional(value: &'a Option<String>) -> Self {
+        value.as_deref().map_or(Self::Missing, Self::Text)
+    }
+
+    /// Case insensitive for text, falling back to the exact text so the order is total
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Missing, Self::Missing) => Ordering::Equal,
+            (Self::Missing, _) => Ordering::Less,
+            (_, Self::Missing) => Ordering::Greater,
+            (Self::Number(lhs), Self::Number(rhs)) => lhs.cmp(rhs),
+            (Self::Text(lhs), Self::Text(rhs)) => lhs
+                .to_lowercase()
+                .cmp(&rhs.to_lowercase())
+                .then_with(|| lhs.cmp(rhs)),
+            (Self::Number(_), Self::Text(_)) => Ordering::Less,
+            (Self::Text(_), Self::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// A record shown in a table with sortable columns
+pub trait Sortable {
+    /// Every column of the table, in their default order
+    const COLUMNS: &'static [&'static str];
+
+    fn sort_value(&self, field: &str) -> SortValue<'_>;
+
+    fn default_layout() -> TableLayout {
+        TableLayout {
+            columns: Self::COLUMNS
+                .iter()
+                .map(|field| Column {
+                    field: field.to_string(),
+                    visible: true,
+                })
+                .collect(),
+            sort: vec![],
+        }
+    }
+}
+
+impl TableLayout {
+    /// Drops columns and sort keys the record doesn't have, and adds new columns at the end, so a
+    /// layout saved by an older version still lists every column once
+    pub fn normalized<R: Sortable>(mut self) -> Self {
+        let known = |field: &str| R::COLUMNS.contains(&field);
+
+        let mut seen = Vec::with_capacity(self.columns.len());
+        self.columns.retain(|column| {
+            let keep = known(&column.field) && !seen.contains(&column.field);
+            seen.push(column.field.clone());
+            keep
+        });
+
+        for field in R::COLUMNS {
+            if !self.columns.iter().any(|column| column.field == *field) {
+                self.columns.push(Column {
+                    field: field.to_string(),
+                    visible: true,
+                });
+            }
+        }
+
+        self.sort = sort_keys::<R>(self.sort);
+        self
+    }
+}
+
+/// Keeps the first key of each known field, a later key on the same field would never be used
+pub fn sort_keys<R: Sortable>(keys: Vec<SortKey>) -> Vec<SortKey> {
+    let mut sort: Vec<SortKey> = Vec::with_capacity(keys.len());
+
+    for key in keys {
+        if R::COLUMNS.contains(&key.field.as_str()) && !sort.iter().any(|k| k.field == key.field) {
+            sort.push(key);
+        }
+    }
+
+    sort
+}
+
+/// Stable sort by every key in turn, records equal on every key keep their current order
+pub fn sort_by_keys<R: Sortable>(records: &mut [R], keys: &[SortKey]) {
+    if keys.is_empty() {
+        return;
+    }
+
+    records.sort_by(|lhs, rhs| {
+        keys.iter()
+            .map(|key| {
+                let ordering = lhs
+                    .sort_value(&key.field)
+                    .compare(&rhs.sort_value(&key.field));
+
+                if key.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Record {
+        name: &'static str,
+        namespace: Option<String>,
+        restarts: i64,
+    }
+
+    impl Sortable for Record {
+        const COLUMNS: &'static [&'static str] = &["name", "namespace", "restarts"];
+
+        fn sort_value(&self, field: &str) -> SortValue<'_> {
+            match field {
+                "name" => SortValue::Text(self.name),
+                "namespace" => SortValue::optional(&self.namespace),
+                "restarts" => SortValue::Number(self.restarts),
+                _ => SortValue::Missing,
+            }
+        }
+    }
+
+    fn record(name: &'static str, namespace: Option<&str>, restarts: i64) -> Record {
+        Record {
+            name,
+            namespace: namespace.map(str::to_string),
+            restarts,
+        }
+    }
+
+    fn key(field: &str, descending: bool) -> SortKey {
+        SortKey {
+            field: field.to_string(),
+            descending,
+        }
+    }
+
+    fn names(records: &[Record]) -> Vec<&str> {
+        records.iter().map(|record| record.name).collect()
+    }
+
+    #[test]
+    fn sorts_by_later_keys_on_ties() {
+        let mut records = vec![
+            record("a", Some("web"), 1),
+            record("b", Some("db"), 5),
+            record("c", Some("web"), 3),
+            record("d", Some("db"), 0),
+        ];
+
+        sort_by_keys(
+            &mut records,
+            &[key("namespace", false), key("restarts", true)],
+        );
+
+        assert_eq!(names(&records), vec!["b", "d", "c", "a"]);
+    }
+
+    #[test]
+    fn keeps_the_order_of_equal_records() {
+        let mut records = vec![
+            record("c", None, 1),
+            record("a", None, 1),
+            record("b", None, 1),
+        ];
+
+        sort_by_keys(&mut records, &[key("restarts", false)]);
+        assert_eq!(names(&records), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn missing_values_sort_first_and_text_ignores_case() {
+        let mut records = vec![
+            record("a", Some("Web"), 0),
+            record("b", None, 0),
+            record("c", Some("db"), 0),
+        ];
+
+        sort_by_keys(&mut records, &[key("namespace", false)]);
+        assert_eq!(names(&records), vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn normalizes_saved_layouts() {
+        let layout = TableLayout {
+            columns: vec![
+                Column {
+                    field: "restarts".to_string(),
+                    visible: false,
+                },
+                Column {
+                    field: "removed".to_string(),
+                    visible: true,
+                },
+                Column {
+                    field: "restarts".to_string(),
+                    visible: true,
+                },
+            ],
+            sort: vec![key("removed", false), key("name", true), key("name", false)],
+        }
+        .normalized::<Record>();
+
+        let columns = layout
+            .columns
+            .iter()
+            .map(|column| (column.field.as_str(), column.visible))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            columns,
+            vec![("restarts", false), ("name", true), ("namespace", true)]
+        );
+        assert_eq!(layout.sort, vec![key("name", true)]);
+    }
+}
@@ -7,7 +7,11 @@ use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 
-use crate::{cluster::ClusterId, view_models::WindowId};
+use crate::{
+    cluster::ClusterId,
+    table::{Sortable, TableLayout},
+    view_models::WindowId,
+};
 
 pub static APP_DIR: Lazy<Xdg> = Lazy::new(|| {
     let app_strategy_args = AppStrategyArgs {
@@ -21,10 +25,24 @@ pub static APP_DIR: Lazy<Xdg> = Lazy::new(|| {
 
 pub static USER_CONFIG: Lazy<RwLock<UserConfig>> = Lazy::new(|| RwLock::new(UserConfig::load()));
 
+const DEFAULT_QUOTA_WARNING_THRESHOLD: f64 = 80.0;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UserConfig {
     pub selected_cluster: Option<ClusterId>,
     pub window_configs: HashMap<WindowId, WindowConfig>,
+
+    /// percentage of a resource quota used before the namespace view warns about it
+    #[serde(default = "default_quota_warning_threshold")]
+    pub quota_warning_threshold: f64,
+
+    /// column layout and sort of each resource table, by the kind of resource it shows
+    #[serde(default)]
+    pub table_layouts: HashMap<String, TableLayout>,
+}
+
+fn default_quota_warning_threshold() -> f64 {
+    DEFAULT_QUOTA_WARNING_THRESHOLD
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -43,6 +61,8 @@ impl UserConfig {
         Self {
             selected_cluster: None,
             window_configs: HashMap::new(),
+            quota_warning_threshold: DEFAULT_QUOTA_WARNING_THRESHOLD,
+            table_layouts: HashMap::new(),
         }
     }
 
@@ -102,6 +122,29 @@ impl UserConfig {
         self.save()
     }
 
+    /// Clamped to a percentage, a value that isn't a number resets it to the default
+    pub fn set_quota_warning_threshold(&mut self, threshold: f64) -> Result<()> {
+        self.quota_warning_threshold = if threshold.is_finite() {
+            threshold.clamp(0.0, 100.0)
+        } else {
+            DEFAULT_QUOTA_WARNING_THRESHOLD
+        };
+        self.save()
+    }
+
+    pub fn get_table_layout<R: Sortable>(&self, kind: &str) -> TableLayout {
+        self.table_layouts
+            .get(kind)
+            .cloned()
+            .map(TableLayout::normalized::<R>)
+            .unwrap_or_else(R::default_layout)
+    }
+
+    pub fn set_table_layout(&mut self, kind: &str, layout: TableLayout) -> Result<()> {
+        self.table_layouts.insert(kind.to_string(), layout);
+        self.save()
+    }
+
     pub fn save(&self) -> Result<()> {
         let config_path = APP_DIR.config_dir().join("user_config.json");
         let config_str =
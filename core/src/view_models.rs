@@ -1,10 +1,20 @@
 use derive_more::{AsRef, Display, From, FromStr};
 use serde::{Deserialize, Serialize};
 
+pub mod custom_resource;
 pub mod global;
+pub mod helm;
+pub mod horizontal_pod_autoscaler;
+pub mod lease;
+pub mod limit_range;
 pub mod main;
 pub mod node;
 pub mod pod;
+pub mod pod_disruption_budget;
+pub mod priority_class;
+pub mod resource;
+pub mod resource_quota;
+pub mod runtime_class;
 
 #[derive(
     Debug, Clone, AsRef, From, FromStr, Display, Hash, PartialEq, Eq, Serialize, Deserialize,
@@ -0,0 +1,525 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use kube::api::DynamicObject;
+use log::{debug, error};
+use parking_lot::RwLock;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        self,
+        cache::{ConnectionState, ResourceCache},
+        custom_resource::{
+            CustomResource, CustomResourceDetail, CustomResourceId, CustomResourceKind,
+            CustomResourceKindId,
+        },
+    },
+    task::{self, spawn_actor},
+    LoadStatus,
+};
+
+use super::resource;
+
+#[derive(Error, Debug)]
+pub enum CustomResourceError {
+    #[error(transparent)]
+    CustomResourceLoadError(eyre::Report),
+
+    #[error("custom resource kind {0} is not served by the cluster")]
+    KindNotFound(CustomResourceKindId),
+}
+
+#[uniffi::export(callback_interface)]
+pub trait CustomResourceViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: CustomResourceViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum CustomResourceViewModelMessage {
+    Loading,
+    Loaded {
+        kind: CustomResourceKind,
+        resources: Vec<CustomResource>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+}
+
+#[derive(Object)]
+pub struct RustCustomResourceViewModel {
+    actor: RwLock<Addr<CustomResourceViewModel>>,
+}
+
+/// List, watch and detail of the objects of one custom resource kind at a time
+pub struct CustomResourceViewModel {
+    addr: WeakAddr<Self>,
+
+    /// Objects of the selected kind, shared with the other windows
+    cache: Option<Arc<ResourceCache<DynamicObject>>>,
+    watcher: Option<JoinHandle<()>>,
+    kinds: HashMap<CustomResourceKindId, CustomResourceKind>,
+    selected_kind: Option<CustomResourceKind>,
+    resources: LoadStatus<HashMap<CustomResourceId, DynamicObject>, String>,
+    responder: Option<Box<dyn CustomResourceViewModelCallback>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustCustomResourceViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn resources(self: Arc<Self>) -> Vec<CustomResource> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move { call!(actor.resources()).await.unwrap_or_default() })
+    }
+
+    pub fn selected_kind(self: Arc<Self>) -> Option<CustomResourceKind> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move { call!(actor.selected_kind()).await.ok().flatten() })
+    }
+
+    pub fn detail(self: Arc<Self>, id: CustomResourceId) -> Option<CustomResourceDetail> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move { call!(actor.detail(id)).await.ok().flatten() })
+    }
+
+    pub async fn fetch_kinds(&self, selected_cluster: ClusterId) -> Vec<CustomResourceKind> {
+        let actor = self.actor.read().clone();
+
+        match call!(actor.load_kinds(selected_cluster)).await {
+            Ok(kinds) => kinds,
+            Err(error) => {
+                error!("failed to fetch custom resource kinds: {error}");
+                vec![]
+            }
+        }
+    }
+
+    /// Loads the objects of the kind and watches them, replacing the previously selected kind
+    pub async fn select_kind(&self, selected_cluster: ClusterId, kind_id: CustomResourceKindId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.select_kind(selected_cluster, kind_id)).await {
+            error!("failed to select custom resource kind: {error}");
+        }
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn CustomResourceViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("custom resource view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(CustomResourceViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_resources(&self, selected_cluster: ClusterId) {
+        debug!("fetching custom resources for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch custom resources: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start custom resource watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl Default for CustomResourceViewModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CustomResourceViewModel {
+    pub fn new() -> Self {
+        Self {
+            addr: Default::default(),
+            cache: None,
+            watcher: None,
+            kinds: HashMap::new(),
+            selected_kind: None,
+            resources: LoadStatus::Initial,
+            responder: None,
+        }
+    }
+
+    pub async fn is_started(&self) -> ActorResult<()> {
+        Produces::ok(())
+    }
+
+    pub async fn selected_kind(&self) -> ActorResult<Option<CustomResourceKind>> {
+        Produces::ok(self.selected_kind.clone())
+    }
+
+    pub async fn resources(&self) -> ActorResult<Vec<CustomResource>> {
+        Produces::ok(self.custom_resources())
+    }
+
+    pub async fn detail(&self, id: CustomResourceId) -> ActorResult<Option<CustomResourceDetail>> {
+        let (Some(kind), LoadStatus::Loaded(resources)) = (&self.selected_kind, &self.resources)
+        else {
+            return Produces::ok(None);
+        };
+
+        Produces::ok(resources.get(&id).map(|object| kind.detail(object)))
+    }
+
+    pub async fn add_callback_listener(
+        &mut self,
+        responder: Box<dyn CustomResourceViewModelCallback>,
+    ) {
+        self.responder = Some(responder);
+    }
+
+    pub async fn callback(&self, msg: CustomResourceViewModelMessage) {
+        match self.responder.as_ref() {
+            Some(responder) => responder.callback(msg),
+            None => error!("custom resource callback called before init"),
+        }
+    }
+
+    pub async fn load_kinds(
+        &mut self,
+        selected_cluster: ClusterId,
+    ) -> ActorResult<Vec<CustomResourceKind>> {
+        let client = resource::client(&selected_cluster)
+            .await
+            .map_err(CustomResourceError::CustomResourceLoadError)?;
+
+        let kinds = kubernetes::custom_resource::kinds(client)
+            .await
+            .map_err(CustomResourceError::CustomResourceLoadError)?;
+
+        self.kinds = kinds
+            .iter()
+            .map(|kind| (kind.id.clone(), kind.clone()))
+            .collect();
+
+        Produces::ok(kinds)
+    }
+
+    pub async fn select_kind(
+        &mut self,
+        selected_cluster: ClusterId,
+        kind_id: CustomResourceKindId,
+    ) -> ActorResult<()> {
+        if !self.kinds.contains_key(&kind_id) {
+            self.load_kinds(selected_cluster.clone()).await?;
+        }
+
+        let kind = self
+            .kinds
+            .get(&kind_id)
+            .cloned()
+            .ok_or(CustomResourceError::KindNotFound(kind_id))?;
+
+        self.selected_kind = Some(kind);
+        self.resources = LoadStatus::Initial;
+
+        self.notify_and_load(selected_cluster.clone()).await?;
+        self.start_watcher(selected_cluster).await?;
+
+        Produces::ok(())
+    }
+
+    pub async fn notify_and_load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        self.callback(CustomResourceViewModelMessage::Loading).await;
+        self.load(selected_cluster).await?;
+
+        Produces::ok(())
+    }
+
+    pub async fn load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let Some(kind) = self.selected_kind.clone() else {
+            return Produces::ok(());
+        };
+
+        let cache = self
+            .cache(selected_cluster, &kind)
+            .await
+            .map_err(CustomResourceError::CustomResourceLoadError)?;
+
+        // the watcher sends the objects once the cache listed them all
+        if !cache.is_ready() {
+            if self.watcher.is_none() {
+                self.watcher = Some(self.spawn_watcher(cache, kind));
+            }
+
+            return Produces::ok(());
+        }
+
+        let resources = kubernetes::custom_resource::objects(&kind, &cache.objects());
+        self.resources = LoadStatus::Loaded(resources);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn start_watcher(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let Some(kind) = self.selected_kind.clone() else {
+            return Produces::ok(());
+        };
+
+        let cache = self.cache(selected_cluster, &kind).await?;
+
+        let task = self.spawn_watcher(cache, kind);
+        if let Some(previous) = self.watcher.replace(task) {
+            previous.abort();
+        }
+
+        Produces::ok(())
+    }
+
+    /// Stops listening for changes and releases the cache, which stops once no other window uses
+    /// it
+    pub async fn stop_watcher(&mut self) -> ActorResult<()> {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+
+        self.cache = None;
+
+        Produces::ok(())
+    }
+
+    pub async fn connection_changed(
+        &mut self,
+        kind_id: CustomResourceKindId,
+        state: ConnectionState,
+    ) -> ActorResult<()> {
+        if !self.is_selected(&kind_id) {
+            return Produces::ok(());
+        }
+
+        // the cache keeps retrying the initial list, the objects are sent once it succeeds
+        if let LoadStatus::Loaded(_) = self.resources {
+            return Produces::ok(());
+        }
+
+        if let ConnectionState::Reconnecting { error } | ConnectionState::Disconnected { error } =
+            state
+        {
+            self.callback(CustomResourceViewModelMessage::LoadingFailed { error })
+                .await;
+        }
+
+        Produces::ok(())
+    }
+
+    /// Replaces every object, when the cache re-listed or the watcher fell behind
+    pub async fn replaced(
+        &mut self,
+        kind_id: CustomResourceKindId,
+        resources: HashMap<CustomResourceId, DynamicObject>,
+    ) -> ActorResult<()> {
+        if !self.is_selected(&kind_id) {
+            return Produces::ok(());
+        }
+
+        if matches!(&self.resources, LoadStatus::Loaded(current) if current == &resources) {
+            return Produces::ok(());
+        }
+
+        self.resources = LoadStatus::Loaded(resources);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn applied(
+        &mut self,
+        kind_id: CustomResourceKindId,
+        object: DynamicObject,
+    ) -> ActorResult<()> {
+        let Some(kind) = self
+            .selected_kind
+            .as_ref()
+            .filter(|kind| kind.id == kind_id)
+        else {
+            debug!("event for {kind_id} which is no longer selected, ignoring");
+            return Produces::ok(());
+        };
+
+        let LoadStatus::Loaded(resources) = &mut self.resources else {
+            return Produces::ok(());
+        };
+
+        let id = kind.resource(&object).id;
+        resources.insert(id, object);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn deleted(
+        &mut self,
+        kind_id: CustomResourceKindId,
+        object: DynamicObject,
+    ) -> ActorResult<()> {
+        let Some(kind) = self
+            .selected_kind
+            .as_ref()
+            .filter(|kind| kind.id == kind_id)
+        else {
+            return Produces::ok(());
+        };
+
+        let LoadStatus::Loaded(resources) = &mut self.resources else {
+            return Produces::ok(());
+        };
+
+        if resources.remove(&kind.resource(&object).id).is_some() {
+            self.notify_loaded().await;
+        }
+
+        Produces::ok(())
+    }
+
+    fn is_selected(&self, kind_id: &CustomResourceKindId) -> bool {
+        self.selected_kind
+            .as_ref()
+            .is_some_and(|kind| &kind.id == kind_id)
+    }
+
+    /// The cache of the kind on the cluster, subscribing to it on first use or when the cluster or
+    /// the kind changed
+    async fn cache(
+        &mut self,
+        selected_cluster: ClusterId,
+        kind: &CustomResourceKind,
+    ) -> eyre::Result<Arc<ResourceCache<DynamicObject>>> {
+        let api_resource = kind.api_resource();
+
+        if let Some(cache) = self.cache.clone() {
+            if cache.cluster_id() == &selected_cluster && cache.kind() == &api_resource {
+                return Ok(cache);
+            }
+
+            // changes from the previous cluster or kind must not reach the new objects
+            if let Some(watcher) = self.watcher.take() {
+                watcher.abort();
+            }
+        }
+
+        let client = resource::client(&selected_cluster).await?;
+        let cache = ResourceCache::get_or_start_with(
+            &selected_cluster,
+            &api_resource,
+            &Default::default(),
+            client,
+        );
+        self.cache = Some(cache.clone());
+
+        Ok(cache)
+    }
+
+    fn spawn_watcher(
+        &self,
+        cache: Arc<ResourceCache<DynamicObject>>,
+        kind: CustomResourceKind,
+    ) -> JoinHandle<()> {
+        let addr = self.addr.clone();
+
+        task::spawn(async move {
+            if let Err(error) = kubernetes::custom_resource::watch(addr, cache, kind).await {
+                error!("custom resource watcher stopped: {error:?}");
+            }
+        })
+    }
+
+    fn custom_resources(&self) -> Vec<CustomResource> {
+        match (&self.selected_kind, &self.resources) {
+            (Some(kind), LoadStatus::Loaded(resources)) => resources
+                .values()
+                .map(|object| kind.resource(object))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    async fn notify_loaded(&self) {
+        if let Some(kind) = &self.selected_kind {
+            self.callback(CustomResourceViewModelMessage::Loaded {
+                kind: kind.clone(),
+                resources: self.custom_resources(),
+            })
+            .await
+        }
+    }
+}
+
+impl From<CustomResourceError> for CustomResourceViewModelMessage {
+    fn from(error: CustomResourceError) -> Self {
+        Self::LoadingFailed {
+            error: error.to_string(),
+        }
+    }
+}
+
+impl Drop for CustomResourceViewModel {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Actor for CustomResourceViewModel {
+    async fn started(&mut self, addr: Addr<Self>) -> ActorResult<()> {
+        self.addr = addr.downgrade();
+        Produces::ok(())
+    }
+
+    async fn error(&mut self, error: ActorError) -> bool {
+        error!("CustomResourceViewModel Actor Error: {error:?}");
+
+        if let Some(error) = error.downcast::<CustomResourceError>().ok().map(|e| *e) {
+            self.callback(error.into()).await
+        } else {
+            self.callback(CustomResourceViewModelMessage::LoadingFailed {
+                error: "Unknown error, please see logs".to_string(),
+            })
+            .await
+        };
+
+        false
+    }
+}
@@ -0,0 +1,573 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use eyre::eyre;
+use k8s_openapi::api::core::v1::Secret as K8sSecret;
+use log::{debug, error};
+use parking_lot::RwLock;
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        self,
+        cache::{ConnectionState, ResourceCache},
+        helm::{HelmChart, HelmRelease, HelmReleaseId, HelmReleaseRevision, HelmRevisionDetail},
+        manifest::ManifestObjectDiff,
+    },
+    task::{self, spawn_actor},
+    LoadStatus,
+};
+
+use super::resource;
+
+#[derive(Error, Debug)]
+pub enum HelmError {
+    #[error(transparent)]
+    HelmLoadError(eyre::Report),
+
+    #[error(transparent)]
+    ClientError(eyre::Report),
+
+    #[error("helm release {0} not found")]
+    ReleaseNotFound(HelmReleaseId),
+
+    #[error(transparent)]
+    HelmRevisionError(#[from] kubernetes::helm::Error),
+}
+
+#[uniffi::export(callback_interface)]
+pub trait HelmViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: HelmViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum HelmViewModelMessage {
+    Loading,
+    Loaded {
+        releases: Vec<HelmRelease>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    RolledBack {
+        release_id: HelmReleaseId,
+        revision: i32,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+#[derive(Object)]
+pub struct RustHelmViewModel {
+    actor: RwLock<Addr<HelmViewModel>>,
+}
+
+pub struct HelmViewModel {
+    addr: WeakAddr<Self>,
+
+    /// Release secrets of the cluster, shared with the other windows
+    cache: Option<Arc<ResourceCache<K8sSecret>>>,
+    watcher: Option<JoinHandle<()>>,
+    releases: LoadStatus<HashMap<HelmReleaseId, HelmRelease>, String>,
+    responder: Option<Box<dyn HelmViewModelCallback>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustHelmViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn releases(self: Arc<Self>) -> Vec<HelmRelease> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move { call!(actor.releases()).await.unwrap_or_default() })
+    }
+
+    pub fn charts(self: Arc<Self>) -> Vec<HelmChart> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move { call!(actor.charts()).await.unwrap_or_default() })
+    }
+
+    /// Reads the revision from the cluster, `None` if it couldn't be read, the error is sent as a
+    /// toast message
+    pub async fn revision_detail(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) -> Option<HelmRevisionDetail> {
+        let actor = self.actor.read().clone();
+
+        call!(actor.revision_detail(selected_cluster, release_id, revision))
+            .await
+            .ok()
+    }
+
+    /// Manifest changes going from the `from` revision to the `to` revision
+    pub async fn revision_diff(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        from: i32,
+        to: i32,
+    ) -> Option<Vec<ManifestObjectDiff>> {
+        let actor = self.actor.read().clone();
+
+        call!(actor.revision_diff(selected_cluster, release_id, from, to))
+            .await
+            .ok()
+    }
+
+    /// Drift of the live objects from the manifest of the revision
+    pub async fn live_diff(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) -> Option<Vec<ManifestObjectDiff>> {
+        let actor = self.actor.read().clone();
+
+        call!(actor.live_diff(selected_cluster, release_id, revision))
+            .await
+            .ok()
+    }
+
+    pub async fn rollback(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) {
+        let actor = self.actor.read().clone();
+        send!(actor.rollback(selected_cluster, release_id, revision));
+    }
+
+    pub async fn initialize_model_with_responder(&self, responder: Box<dyn HelmViewModelCallback>) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("helm view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(HelmViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_releases(&self, selected_cluster: ClusterId) {
+        debug!("fetching helm releases for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch helm releases: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start helm release watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl Default for HelmViewModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HelmViewModel {
+    pub fn new() -> Self {
+        Self {
+            addr: Default::default(),
+            cache: None,
+            watcher: None,
+            releases: LoadStatus::Initial,
+            responder: None,
+        }
+    }
+
+    pub async fn is_started(&self) -> ActorResult<()> {
+        Produces::ok(())
+    }
+
+    pub async fn releases(&self) -> ActorResult<Vec<HelmRelease>> {
+        match &self.releases {
+            LoadStatus::Loaded(releases) => Produces::ok(releases.values().cloned().collect()),
+            _ => Produces::ok(vec![]),
+        }
+    }
+
+    pub async fn charts(&self) -> ActorResult<Vec<HelmChart>> {
+        match &self.releases {
+            LoadStatus::Loaded(releases) => {
+                Produces::ok(kubernetes::helm::charts(releases.values()))
+            }
+            _ => Produces::ok(vec![]),
+        }
+    }
+
+    pub async fn revision_detail(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) -> ActorResult<HelmRevisionDetail> {
+        let (namespace, name) = self.release_namespace_and_name(&release_id)?;
+        let client = resource::client(&selected_cluster)
+            .await
+            .map_err(HelmError::ClientError)?;
+
+        let result = kubernetes::helm::get_revision(client, &namespace, &name, revision)
+            .await
+            .and_then(|release| release.detail());
+
+        Self::produce_or_toast(result, "unable to read helm revision")
+    }
+
+    pub async fn revision_diff(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        from: i32,
+        to: i32,
+    ) -> ActorResult<Vec<ManifestObjectDiff>> {
+        let (namespace, name) = self.release_namespace_and_name(&release_id)?;
+        let client = resource::client(&selected_cluster)
+            .await
+            .map_err(HelmError::ClientError)?;
+
+        let result = kubernetes::helm::revision_diff(client, &namespace, &name, from, to).await;
+        Self::produce_or_toast(result, "unable to diff helm revisions")
+    }
+
+    pub async fn live_diff(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) -> ActorResult<Vec<ManifestObjectDiff>> {
+        let (namespace, name) = self.release_namespace_and_name(&release_id)?;
+        let client = resource::client(&selected_cluster)
+            .await
+            .map_err(HelmError::ClientError)?;
+
+        let result = kubernetes::helm::live_diff(client, &namespace, &name, revision).await;
+        Self::produce_or_toast(result, "unable to diff helm revision against the cluster")
+    }
+
+    pub async fn rollback(
+        &self,
+        selected_cluster: ClusterId,
+        release_id: HelmReleaseId,
+        revision: i32,
+    ) -> ActorResult<()> {
+        let (namespace, name) = self.release_namespace_and_name(&release_id)?;
+        let client = resource::client(&selected_cluster)
+            .await
+            .map_err(HelmError::ClientError)?;
+
+        // the new revision reaches the releases through the watcher
+        let result = kubernetes::helm::rollback(client, &namespace, &name, revision).await;
+        Self::produce_or_toast(result, "unable to roll back helm release")?;
+
+        self.callback(HelmViewModelMessage::RolledBack {
+            release_id,
+            revision,
+        })
+        .await;
+
+        Produces::ok(())
+    }
+
+    /// Returns the error as a [`HelmError`] so [`Actor::error`] shows it as a toast, without
+    /// failing the loaded releases
+    fn produce_or_toast<T: Send + 'static>(
+        result: Result<T, kubernetes::helm::Error>,
+        context: &str,
+    ) -> ActorResult<T> {
+        match result {
+            Ok(value) => Produces::ok(value),
+            Err(error) => {
+                error!("{context}: {error}");
+                Err(HelmError::from(error).into())
+            }
+        }
+    }
+
+    fn release_namespace_and_name(
+        &self,
+        release_id: &HelmReleaseId,
+    ) -> Result<(String, String), HelmError> {
+        let LoadStatus::Loaded(releases) = &self.releases else {
+            return Err(HelmError::ReleaseNotFound(release_id.clone()));
+        };
+
+        releases
+            .get(release_id)
+            .map(|release| (release.namespace.clone(), release.name.clone()))
+            .ok_or_else(|| HelmError::ReleaseNotFound(release_id.clone()))
+    }
+
+    pub async fn add_callback_listener(&mut self, responder: Box<dyn HelmViewModelCallback>) {
+        self.responder = Some(responder);
+    }
+
+    pub async fn callback(&self, msg: HelmViewModelMessage) {
+        match self.responder.as_ref() {
+            Some(responder) => responder.callback(msg),
+            None => error!("helm callback called before init"),
+        }
+    }
+
+    pub async fn notify_and_load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        self.callback(HelmViewModelMessage::Loading).await;
+        self.load(selected_cluster).await?;
+
+        Produces::ok(())
+    }
+
+    pub async fn load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let cache = self
+            .cache(selected_cluster)
+            .await
+            .map_err(HelmError::HelmLoadError)?;
+
+        // the watcher sends the releases once the cache listed every release secret
+        if !cache.is_ready() {
+            if self.watcher.is_none() {
+                self.watcher = Some(self.spawn_watcher(cache));
+            }
+
+            return Produces::ok(());
+        }
+
+        self.releases = LoadStatus::Loaded(kubernetes::helm::releases(&cache.objects()));
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn start_watcher(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let cache = self.cache(selected_cluster).await?;
+
+        let task = self.spawn_watcher(cache);
+        if let Some(previous) = self.watcher.replace(task) {
+            previous.abort();
+        }
+
+        Produces::ok(())
+    }
+
+    /// Stops listening for changes and releases the cache, which stops once no other window uses
+    /// it
+    pub async fn stop_watcher(&mut self) -> ActorResult<()> {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+
+        self.cache = None;
+
+        Produces::ok(())
+    }
+
+    pub async fn connection_changed(&mut self, state: ConnectionState) -> ActorResult<()> {
+        // the cache keeps retrying the initial list, the releases are sent once it succeeds
+        if let LoadStatus::Loaded(_) = self.releases {
+            return Produces::ok(());
+        }
+
+        if let ConnectionState::Reconnecting { error } | ConnectionState::Disconnected { error } =
+            state
+        {
+            self.callback(HelmViewModelMessage::LoadingFailed { error })
+                .await;
+        }
+
+        Produces::ok(())
+    }
+
+    /// Replaces every release, when the cache re-listed or the watcher fell behind
+    pub async fn replaced(
+        &mut self,
+        releases: HashMap<HelmReleaseId, HelmRelease>,
+    ) -> ActorResult<()> {
+        if matches!(&self.releases, LoadStatus::Loaded(current) if current == &releases) {
+            return Produces::ok(());
+        }
+
+        self.releases = LoadStatus::Loaded(releases);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn applied(
+        &mut self,
+        release_id: HelmReleaseId,
+        revision: HelmReleaseRevision,
+    ) -> ActorResult<()> {
+        let LoadStatus::Loaded(releases) = &mut self.releases else {
+            return Produces::ok(());
+        };
+
+        let release = match releases.remove(&release_id) {
+            Some(release) => release.with_revision(revision),
+            None => {
+                let (namespace, name) = release_id
+                    .as_ref()
+                    .split_once('/')
+                    .ok_or_else(|| eyre!("invalid helm release id {release_id}"))?;
+
+                HelmRelease::from_revisions(namespace, name, vec![revision])
+                    .expect("just added a revision")
+            }
+        };
+
+        releases.insert(release_id, release);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn deleted(&mut self, release_id: HelmReleaseId, revision: i32) -> ActorResult<()> {
+        let LoadStatus::Loaded(releases) = &mut self.releases else {
+            return Produces::ok(());
+        };
+
+        let Some(release) = releases.remove(&release_id) else {
+            return Produces::ok(());
+        };
+
+        // helm prunes old revisions, the release only goes away with its last revision
+        if let Some(release) = release.without_revision(revision) {
+            releases.insert(release_id, release);
+        }
+
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    /// The release secrets cache of the cluster, subscribing to it on first use or when the
+    /// cluster changed
+    async fn cache(
+        &mut self,
+        selected_cluster: ClusterId,
+    ) -> eyre::Result<Arc<ResourceCache<K8sSecret>>> {
+        if let Some(cache) = self.cache.clone() {
+            if cache.cluster_id() == &selected_cluster {
+                return Ok(cache);
+            }
+
+            // changes from the previous cluster must not reach the new releases
+            if let Some(watcher) = self.watcher.take() {
+                watcher.abort();
+            }
+        }
+
+        let client = resource::client(&selected_cluster).await?;
+        let selectors = kubernetes::helm::release_secret_selectors();
+
+        let cache = ResourceCache::get_or_start(&selected_cluster, &selectors, client);
+        self.cache = Some(cache.clone());
+
+        Ok(cache)
+    }
+
+    fn spawn_watcher(&self, cache: Arc<ResourceCache<K8sSecret>>) -> JoinHandle<()> {
+        let addr = self.addr.clone();
+
+        task::spawn(async move {
+            if let Err(error) = kubernetes::helm::watch(addr, cache).await {
+                error!("helm release watcher stopped: {error:?}");
+            }
+        })
+    }
+
+    async fn notify_loaded(&self) {
+        if let LoadStatus::Loaded(releases) = &self.releases {
+            self.callback(HelmViewModelMessage::Loaded {
+                releases: releases.values().cloned().collect(),
+            })
+            .await
+        }
+    }
+}
+
+impl From<HelmError> for HelmViewModelMessage {
+    fn from(error: HelmError) -> Self {
+        match error {
+            HelmError::HelmLoadError(e) => Self::LoadingFailed {
+                error: e.to_string(),
+            },
+
+            HelmError::ClientError(error) => Self::ToastErrorMessage {
+                message: error.to_string(),
+            },
+
+            HelmError::ReleaseNotFound(release_id) => Self::ToastErrorMessage {
+                message: format!("Helm release ({release_id}) not found"),
+            },
+
+            HelmError::HelmRevisionError(error) => Self::ToastErrorMessage {
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl Drop for HelmViewModel {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Actor for HelmViewModel {
+    async fn started(&mut self, addr: Addr<Self>) -> ActorResult<()> {
+        self.addr = addr.downgrade();
+        Produces::ok(())
+    }
+
+    async fn error(&mut self, error: ActorError) -> bool {
+        error!("HelmViewModel Actor Error: {error:?}");
+
+        if let Some(error) = error.downcast::<HelmError>().ok().map(|e| *e) {
+            self.callback(error.into()).await
+        } else {
+            self.callback(HelmViewModelMessage::LoadingFailed {
+                error: "Unknown error, please see logs".to_string(),
+            })
+            .await
+        };
+
+        false
+    }
+}
@@ -0,0 +1,194 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use k8s_openapi::api::autoscaling::v2::HorizontalPodAutoscaler as K8sHorizontalPodAutoscaler;
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        cache::ConnectionState,
+        horizontal_pod_autoscaler::{
+            HorizontalPodAutoscaler, HorizontalPodAutoscalerId, ScalingHistory, ScalingSample,
+        },
+        resource::ListProgress,
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{ResourceCallback, ResourceMessage, ResourceViewModel};
+
+#[uniffi::export(callback_interface)]
+pub trait HorizontalPodAutoscalerViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: HorizontalPodAutoscalerViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum HorizontalPodAutoscalerViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        horizontal_pod_autoscalers: Vec<HorizontalPodAutoscaler>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<HorizontalPodAutoscaler>,
+        updated: Vec<HorizontalPodAutoscaler>,
+        removed: Vec<HorizontalPodAutoscalerId>,
+        positions: HashMap<HorizontalPodAutoscalerId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+pub type HorizontalPodAutoscalerViewModel = ResourceViewModel<K8sHorizontalPodAutoscaler>;
+
+#[derive(Object)]
+pub struct RustHorizontalPodAutoscalerViewModel {
+    actor: RwLock<Addr<HorizontalPodAutoscalerViewModel>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustHorizontalPodAutoscalerViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn horizontal_pod_autoscalers(self: Arc<Self>) -> Vec<HorizontalPodAutoscaler> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Replica counts and metric readings observed since the autoscaler cache of the cluster
+    /// started, oldest first
+    pub fn scaling_history(self: Arc<Self>, id: HorizontalPodAutoscalerId) -> Vec<ScalingSample> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.history(id))
+                .await
+                .ok()
+                .flatten()
+                .as_ref()
+                .map(ScalingHistory::samples)
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn HorizontalPodAutoscalerViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("horizontal pod autoscaler view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(HorizontalPodAutoscalerViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_horizontal_pod_autoscalers(&self, selected_cluster: ClusterId) {
+        debug!("fetching horizontal pod autoscalers for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch horizontal pod autoscalers: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start horizontal pod autoscaler watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl ResourceCallback<HorizontalPodAutoscaler>
+    for Box<dyn HorizontalPodAutoscalerViewModelCallback>
+{
+    fn callback(&self, message: ResourceMessage<HorizontalPodAutoscaler>) {
+        use HorizontalPodAutoscalerViewModelMessage as HpaMsg;
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => HpaMsg::Loading { progress },
+            Msg::Loaded { records, .. } => HpaMsg::Loaded {
+                horizontal_pod_autoscalers: records,
+            },
+            Msg::LoadingFailed { error } => HpaMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => HpaMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => HpaMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => HpaMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => HpaMsg::ToastWarningMessage { message },
+            Msg::ToastErrorMessage { message } => HpaMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}
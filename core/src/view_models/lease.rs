@@ -0,0 +1,189 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use chrono::Utc;
+use k8s_openapi::api::coordination::v1::Lease as K8sLease;
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        cache::ConnectionState,
+        lease::{Lease, LeaseId},
+        resource::ListProgress,
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{ResourceCallback, ResourceMessage, ResourceViewModel};
+
+#[uniffi::export(callback_interface)]
+pub trait LeaseViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: LeaseViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum LeaseViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        leases: Vec<Lease>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<Lease>,
+        updated: Vec<Lease>,
+        removed: Vec<LeaseId>,
+        positions: HashMap<LeaseId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+pub type LeaseViewModel = ResourceViewModel<K8sLease>;
+
+#[derive(Object)]
+pub struct RustLeaseViewModel {
+    actor: RwLock<Addr<LeaseViewModel>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustLeaseViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn leases(self: Arc<Self>) -> Vec<Lease> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Leader election leases whose holder stopped renewing them, usually a wedged controller
+    pub fn stale_leader_election_leases(self: Arc<Self>) -> Vec<Lease> {
+        let actor = self.actor.read().clone();
+
+        let leases =
+            task::block_on(async move { call!(actor.all_records()).await.unwrap_or_default() });
+
+        let now = Utc::now().timestamp();
+
+        leases
+            .into_iter()
+            .map(|lease| lease.with_staleness(now))
+            .filter(|lease| lease.is_leader_election && lease.stale)
+            .collect()
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn LeaseViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("lease view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(LeaseViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_leases(&self, selected_cluster: ClusterId) {
+        debug!("fetching leases for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch leases: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start lease watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl ResourceCallback<Lease> for Box<dyn LeaseViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<Lease>) {
+        use LeaseViewModelMessage as LeaseMsg;
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => LeaseMsg::Loading { progress },
+            Msg::Loaded { records, .. } => LeaseMsg::Loaded { leases: records },
+            Msg::LoadingFailed { error } => LeaseMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => LeaseMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => LeaseMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => LeaseMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => LeaseMsg::ToastWarningMessage { message },
+            Msg::ToastErrorMessage { message } => LeaseMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}
@@ -0,0 +1,191 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use k8s_openapi::api::core::v1::LimitRange as K8sLimitRange;
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        self,
+        cache::ConnectionState,
+        limit_range::{InjectedDefault, LimitRange, LimitRangeId},
+        resource::ListProgress,
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{ResourceCallback, ResourceMessage, ResourceViewModel};
+
+#[uniffi::export(callback_interface)]
+pub trait LimitRangeViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: LimitRangeViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum LimitRangeViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        limit_ranges: Vec<LimitRange>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<LimitRange>,
+        updated: Vec<LimitRange>,
+        removed: Vec<LimitRangeId>,
+        positions: HashMap<LimitRangeId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+pub type LimitRangeViewModel = ResourceViewModel<K8sLimitRange>;
+
+#[derive(Object)]
+pub struct RustLimitRangeViewModel {
+    actor: RwLock<Addr<LimitRangeViewModel>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustLimitRangeViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn limit_ranges(self: Arc<Self>) -> Vec<LimitRange> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Requests and limits that will be injected into a container in the namespace that
+    /// doesn't set any resources
+    pub fn injected_defaults(self: Arc<Self>, namespace: String) -> Vec<InjectedDefault> {
+        let actor = self.actor.read().clone();
+
+        let mut limit_ranges =
+            task::block_on(async move { call!(actor.all_records()).await.unwrap_or_default() });
+
+        limit_ranges.retain(|limit_range| limit_range.namespace == namespace);
+
+        // the admission plugin has no defined order, use a stable one so the result doesn't jump
+        limit_ranges.sort_by(|a, b| a.name.cmp(&b.name));
+
+        kubernetes::limit_range::injected_defaults(&limit_ranges)
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn LimitRangeViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("limit range view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(LimitRangeViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_limit_ranges(&self, selected_cluster: ClusterId) {
+        debug!("fetching limit ranges for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch limit ranges: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start limit range watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl ResourceCallback<LimitRange> for Box<dyn LimitRangeViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<LimitRange>) {
+        use LimitRangeViewModelMessage as LimitRangeMsg;
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => LimitRangeMsg::Loading { progress },
+            Msg::Loaded { records, .. } => LimitRangeMsg::Loaded {
+                limit_ranges: records,
+            },
+            Msg::LoadingFailed { error } => LimitRangeMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => LimitRangeMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => LimitRangeMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => LimitRangeMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => LimitRangeMsg::ToastWarningMessage { message },
+            Msg::ToastErrorMessage { message } => LimitRangeMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}
@@ -9,7 +9,9 @@ use std::{collections::HashMap, sync::Arc};
 
 use crate::{
     cluster::{Cluster, ClusterId},
+    fuzzy::{fuzzy_match, FuzzyMatch},
     key_handler::{FocusRegion, KeyAwareEvent, KeyHandler},
+    kubernetes::{self, custom_resource::CustomResourceKind},
     tab::{Tab, TabId},
     tab_group::{TabGroup, TabGroupId, TabGroups},
     user_config::USER_CONFIG,
@@ -48,6 +50,9 @@ pub enum MainViewModelField {
     TabGroupExpansions {
         expansions: HashMap<TabGroupId, bool>,
     },
+    TabGroups {
+        tab_groups: Vec<TabGroup>,
+    },
 }
 
 #[uniffi::export(callback_interface)]
@@ -127,6 +132,11 @@ impl RustMainViewModel {
         self.inner.read().tab_groups_filtered().0
     }
 
+    /// Matched characters of the tab names for the search last passed to `tab_groups_filtered`
+    pub fn tab_highlights(&self) -> HashMap<TabId, FuzzyMatch> {
+        self.inner.read().tab_highlights()
+    }
+
     pub fn select_first_filtered_tab(&self) {
         if self.inner.write().set_first_filtered_tab().is_none() {
             return;
@@ -189,6 +199,39 @@ impl RustMainViewModel {
         }
     }
 
+    /// Discovers the custom resource kinds served by the cluster and adds a tab for each one
+    pub async fn load_custom_resource_tabs(&self, selected_cluster: ClusterId) {
+        if let Err(error) = GlobalViewModel::check_and_load_client(&selected_cluster).await {
+            error!("failed to load client for custom resources: {error}");
+            return;
+        }
+
+        let Some(client) = GlobalViewModel::global()
+            .read()
+            .get_cluster_client(&selected_cluster)
+        else {
+            error!("client not found, unable to load custom resources");
+            return;
+        };
+
+        let kinds = match kubernetes::custom_resource::kinds(client).await {
+            Ok(kinds) => kinds,
+            Err(error) => {
+                error!("failed to discover custom resources: {error}");
+                return;
+            }
+        };
+
+        self.inner.write().set_custom_resource_tabs(&kinds);
+
+        Updater::send(
+            &self.window_id,
+            MainViewModelField::TabGroups {
+                tab_groups: self.inner.read().tab_groups_filtered().0,
+            },
+        );
+    }
+
     pub fn handle_key_input(&self, key_input: KeyAwareEvent) -> bool {
         let prevent_default = self.inner.write().handle_key_input(key_input);
         Updater::send(
@@ -275,17 +318,25 @@ impl MainViewModel {
             ],
         );
 
-        let tab_groups = vec![general, workloads, config, network, storage, access_control];
+        let helm = TabGroup::new(
+            TabGroupId::Helm,
+            vec![
+                Tab::new(TabId::Releases, "helm"),
+                Tab::new(TabId::Charts, "shippingbox"),
+            ],
+        );
 
-        let tabs: Vec<Tab> = tab_groups
-            .iter()
-            .flat_map(|tab_group| tab_group.tabs.clone())
-            .collect();
+        let tab_groups = vec![
+            general,
+            workloads,
+            config,
+            network,
+            storage,
+            access_control,
+            helm,
+        ];
 
-        let tabs_map = tabs
-            .iter()
-            .map(|tab| (tab.id.clone(), tab.clone()))
-            .collect();
+        let (tabs, tabs_map) = Self::tabs(&tab_groups);
 
         let tab_group_expansions = tab_groups
             .iter()
@@ -333,42 +384,98 @@ impl MainViewModel {
             .get_cluster(cluster_id)
     }
 
+    fn tabs(tab_groups: &[TabGroup]) -> (Vec<Tab>, HashMap<TabId, Tab>) {
+        let tabs: Vec<Tab> = tab_groups
+            .iter()
+            .flat_map(|tab_group| tab_group.tabs.clone())
+            .collect();
+
+        let tabs_map = tabs
+            .iter()
+            .map(|tab| (tab.id.clone(), tab.clone()))
+            .collect();
+
+        (tabs, tabs_map)
+    }
+
+    /// Replaces the custom resources tab group, which is only shown when the cluster has CRDs
+    pub fn set_custom_resource_tabs(&mut self, kinds: &[CustomResourceKind]) {
+        let tab_groups = &mut self.tab_groups.0;
+        tab_groups.retain(|tab_group| tab_group.id != TabGroupId::CustomResources);
+
+        if !kinds.is_empty() {
+            let tabs = kinds.iter().map(Tab::custom_resource).collect();
+            tab_groups.push(TabGroup::new(TabGroupId::CustomResources, tabs));
+
+            self.tab_group_expansions
+                .entry(TabGroupId::CustomResources)
+                .or_insert(true);
+        }
+
+        (self.tabs, self.tabs_map) = Self::tabs(&self.tab_groups.0);
+
+        // the selected kind is no longer served by the cluster
+        if !self.tabs_map.contains_key(&self.selected_tab) {
+            self.selected_tab = TabId::ClusterTab;
+        }
+    }
+
     pub fn select_tab(&mut self, selected_tab: TabId) {
         self.selected_tab = selected_tab;
         self.expand_selected_tabs_tab_group();
     }
 
+    /// Tabs fuzzy matching the search, the best matches first in each group and the group with
+    /// the best match first
     pub fn tab_groups_filtered(&self) -> TabGroups {
-        if self.search.is_none() {
+        let Some(search) = self.search.as_ref() else {
             return self.tab_groups.clone();
-        }
-
-        let search = self.search.as_ref().expect("just checked search exists");
+        };
 
-        let tab_groups = self
+        let mut tab_groups = self
             .tab_groups
             .0
             .iter()
             .filter_map(|tab_group| {
-                let tabs = tab_group
+                let mut tabs = tab_group
                     .tabs
                     .iter()
-                    .filter(|tab| tab.name.to_lowercase().contains(&search.to_lowercase()))
-                    .cloned()
-                    .collect::<Vec<Tab>>();
+                    .filter_map(|tab| Some((fuzzy_match(search, &tab.name)?.score, tab.clone())))
+                    .collect::<Vec<(i64, Tab)>>();
 
-                if tabs.is_empty() {
-                    return None;
-                };
+                // stable, so tabs with the same score keep their order
+                tabs.sort_by(|(a, _), (b, _)| b.cmp(a));
+                let best = tabs.first()?.0;
 
-                Some(TabGroup {
-                    tabs,
+                let tab_group = TabGroup {
+                    tabs: tabs.into_iter().map(|(_, tab)| tab).collect(),
                     ..tab_group.clone()
-                })
+                };
+
+                Some((best, tab_group))
             })
-            .collect::<Vec<TabGroup>>();
+            .collect::<Vec<(i64, TabGroup)>>();
+
+        tab_groups.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        TabGroups(
+            tab_groups
+                .into_iter()
+                .map(|(_, tab_group)| tab_group)
+                .collect(),
+        )
+    }
 
-        TabGroups(tab_groups)
+    /// Matched characters of the tab names for the current search
+    pub fn tab_highlights(&self) -> HashMap<TabId, FuzzyMatch> {
+        let Some(search) = self.search.as_ref() else {
+            return HashMap::new();
+        };
+
+        self.tabs
+            .iter()
+            .filter_map(|tab| Some((tab.id.clone(), fuzzy_match(search, &tab.name)?)))
+            .collect()
     }
 
     pub fn set_first_filtered_tab(&mut self) -> Option<()> {
@@ -1,33 +1,31 @@
 use std::{collections::HashMap, sync::Arc};
 
 use act_zero::*;
-use eyre::{eyre, Result};
-use kube::Client;
+use k8s_openapi::api::core::v1::Node as K8sNode;
 use log::{debug, error, warn};
-use tokio::sync::RwLock;
+use parking_lot::RwLock;
 
 use fake::{Fake, Faker};
 
-use thiserror::Error;
-
-use super::WindowId;
+use super::{
+    resource::{self, ResourceCallback, ResourceMessage, ResourceViewModel},
+    WindowId,
+};
 use crate::{
     cluster::ClusterId,
     kubernetes::{
         self,
-        node::{Node, NodeId},
+        cache::ConnectionState,
+        drain::{self, DrainOptions, DrainPodProgress},
+        node::{ConditionTransition, Node, NodeHistory, NodeId, Taint},
+        node_edit::{self, NodeEdit},
+        pod::Pod,
+        resource::ListProgress,
     },
+    table::{Column, SortKey, TableLayout},
     task,
 };
 
-use crate::view_models::global::GlobalViewModel;
-
-#[derive(Error, Debug)]
-pub enum NodeError {
-    #[error(transparent)]
-    NodeLoadError(eyre::Report),
-}
-
 #[uniffi::export(callback_interface)]
 pub trait NodeViewModelCallback: Send + Sync + 'static {
     fn callback(&self, message: NodeViewModelMessage);
@@ -35,9 +33,52 @@ pub trait NodeViewModelCallback: Send + Sync + 'static {
 
 #[derive(uniffi::Enum)]
 pub enum NodeViewModelMessage {
-    Loading,
-    Loaded { nodes: Vec<Node> },
-    LoadingFailed { error: String },
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        nodes: Vec<Node>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<Node>,
+        updated: Vec<Node>,
+        removed: Vec<NodeId>,
+        positions: HashMap<NodeId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    /// Sent each time a pod being drained changes status
+    DrainProgress {
+        node_id: NodeId,
+        progress: DrainPodProgress,
+    },
+    /// The node stays cordoned even if the drain failed
+    DrainFinished {
+        node_id: NodeId,
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
 }
 
 #[derive(uniffi::Enum)]
@@ -48,50 +89,35 @@ pub enum NodeLoadStatus {
     Error { error: String },
 }
 
-impl From<NodeError> for NodeViewModelMessage {
-    fn from(error: NodeError) -> Self {
-        match error {
-            NodeError::NodeLoadError(e) => NodeViewModelMessage::LoadingFailed {
-                error: e.to_string(),
-            },
-        }
-    }
-}
+pub type NodeViewModel = ResourceViewModel<K8sNode>;
 
 pub struct RustNodeViewModel {
-    state: Arc<RwLock<State>>,
+    actor: RwLock<Addr<NodeViewModel>>,
+
+    /// Also given to the actor, node actions report to it directly
+    responder: RwLock<Option<Arc<dyn NodeViewModelCallback>>>,
 
     #[allow(dead_code)]
     window_id: WindowId,
 }
 
-pub struct State {
-    extra_worker: Addr<Worker>,
-    current_worker: Addr<Worker>,
-
-    nodes: Option<HashMap<NodeId, Node>>,
-    responder: Option<Box<dyn NodeViewModelCallback>>,
-}
-
-pub struct Worker {
-    addr: WeakAddr<Self>,
-    state: Arc<RwLock<State>>,
-}
-
 impl RustNodeViewModel {
     pub fn new(window_id: String) -> Self {
-        let window_id = WindowId(window_id);
-
-        let state = Arc::new(RwLock::new(State::new(window_id.clone())));
-
-        Self { state, window_id }
+        Self {
+            actor: RwLock::new(Default::default()),
+            responder: RwLock::new(None),
+            window_id: WindowId(window_id),
+        }
     }
 
     pub fn preview(window_id: String) -> Self {
-        let window_id = WindowId(window_id);
+        let nodes = (0..16).map(|_| Faker.fake::<Node>());
 
-        let state = Arc::new(RwLock::new(State::preview()));
-        Self { state, window_id }
+        Self {
+            actor: RwLock::new(task::spawn_actor(NodeViewModel::preview(nodes))),
+            responder: RwLock::new(None),
+            window_id: WindowId(window_id),
+        }
     }
 }
 
@@ -100,217 +126,225 @@ impl RustNodeViewModel {
     pub async fn add_callback_listener(&self, responder: Box<dyn NodeViewModelCallback>) {
         debug!("node view model callback listener added");
 
-        let mut state = self.state.write().await;
-        state.responder = Some(responder);
-        state.extra_worker = Worker::start_actor(self.state.clone());
+        // previews start with their actor already running
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_err() {
+            *self.actor.write() = task::spawn_actor(NodeViewModel::new());
+        }
+
+        let responder: Arc<dyn NodeViewModelCallback> = Arc::from(responder);
+        *self.responder.write() = Some(responder.clone());
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
     }
 
     /// Sets the the loading status to loading and fetches the nodes for the selected cluster.
     pub async fn fetch_nodes(&self, selected_cluster: ClusterId) {
         debug!("fetching nodes");
+        let actor = self.actor.read().clone();
 
-        let worker = Worker::start_actor(self.state.clone());
-        self.state.write().await.current_worker = worker.clone();
-
-        let _ = call!(worker.notify_and_load_nodes(selected_cluster.clone())).await;
-        let _ = call!(worker.start_watcher(selected_cluster.clone())).await;
-    }
-
-    pub async fn stop_watcher(&self) {
-        debug!("stopping watcher");
-        self.state.write().await.current_worker = Addr::default();
-    }
-
-    pub fn nodes(&self, selected_cluster: ClusterId) -> Vec<Node> {
-        // TODO: handle error
-        let state = self.state.clone();
-
-        task::block_on(async move {
-            state
-                .read()
-                .await
-                .nodes(selected_cluster)
-                .unwrap_or_default()
-        })
-    }
-}
-
-impl State {
-    pub fn preview() -> Self {
-        let mut nodes = HashMap::new();
-
-        for _ in 0..16 {
-            let node = Faker.fake::<Node>();
-            nodes.insert(node.id.clone(), node);
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster.clone())).await {
+            error!("failed to fetch nodes: {error}");
         }
 
-        Self {
-            extra_worker: Default::default(),
-            current_worker: Default::default(),
-            nodes: Some(nodes),
-            responder: None,
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start node watcher: {error}");
         }
     }
 
-    pub fn new(_window_id: WindowId) -> Self {
-        Self {
-            extra_worker: Default::default(),
-            current_worker: Default::default(),
-            nodes: None,
-            responder: None,
-        }
+    /// Sorts by each key in turn, the keys are saved with the table layout
+    pub fn set_sort(&self, keys: Vec<SortKey>) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_sort(keys));
     }
 
-    pub fn nodes(&self, selected_cluster: ClusterId) -> Result<Vec<Node>> {
-        debug!("getting nodes called");
-
-        if self.nodes.is_none() {
-            warn!("nodes not loaded, fetching nodes");
-            send!(self.extra_worker.notify_and_load_nodes(selected_cluster));
-        };
-
-        let nodes = self
-            .nodes
-            .as_ref()
-            .ok_or_else(|| eyre!("nodes not loaded"))?;
-
-        Ok(nodes.values().cloned().collect())
+    pub fn set_columns(&self, columns: Vec<Column>) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_columns(columns));
     }
-}
 
-impl Worker {
-    pub fn start_actor(state: Arc<RwLock<State>>) -> Addr<Self> {
-        task::spawn_actor(Self::new(state))
+    pub async fn layout(&self) -> TableLayout {
+        let actor = self.actor.read().clone();
+        call!(actor.layout()).await.unwrap_or_default()
     }
 
-    pub fn new(state: Arc<RwLock<State>>) -> Self {
-        Self {
-            addr: Default::default(),
-            state,
-        }
-    }
+    /// Condition transitions observed since the node cache of the cluster started, oldest first
+    pub async fn condition_history(&self, node_id: NodeId) -> Vec<ConditionTransition> {
+        let actor = self.actor.read().clone();
 
-    pub async fn callback(&self, msg: NodeViewModelMessage) {
-        if let Some(responder) = self.state.read().await.responder.as_ref() {
-            responder.callback(msg);
-        } else {
-            warn!("node view model callback called, before initialized");
-        }
+        call!(actor.history(node_id))
+            .await
+            .ok()
+            .flatten()
+            .as_ref()
+            .map(NodeHistory::transitions)
+            .unwrap_or_default()
     }
 
-    pub async fn applied(&mut self, node: Node) -> ActorResult<()> {
-        if let Some(ref nodes) = self.state.read().await.nodes {
-            if let Some(existing_node) = nodes.get(&node.id) {
-                if existing_node == &node {
-                    debug!("same node already exists, ignoring");
-                    return Produces::ok(());
-                }
-            }
+    /// Pods scheduled on the node with their phase and usage, for the node details
+    pub async fn pods(&self, selected_cluster: ClusterId, node_id: NodeId) -> Vec<Pod> {
+        let pods = async {
+            let client = resource::client(&selected_cluster).await?;
+            kubernetes::pod::on_node(&selected_cluster, client, &node_id).await
         };
 
-        if let Some(ref mut nodes) = self.state.write().await.nodes {
-            debug!("node updated, notifying listeners");
-            nodes.insert(node.id.clone(), node);
+        pods.await.unwrap_or_else(|error| {
+            error!("failed to get pods on node {}: {error}", node_id.raw_value);
+            vec![]
+        })
+    }
 
-            self.callback(NodeViewModelMessage::Loaded {
-                nodes: nodes.values().cloned().collect(),
-            })
+    /// Marks the node unschedulable, the pods already on it keep running
+    pub async fn cordon(&self, selected_cluster: ClusterId, node_id: NodeId) {
+        self.set_unschedulable(selected_cluster, node_id, true)
             .await
-        };
-
-        Produces::ok(())
     }
 
-    pub async fn deleted(&mut self, node: Node) -> ActorResult<()> {
-        if let Some(nodes) = self.state.write().await.nodes.as_mut() {
-            debug!("node deleted, notifying listeners");
-            nodes.remove(&node.id);
-
-            self.callback(NodeViewModelMessage::Loaded {
-                nodes: nodes.values().cloned().collect(),
-            })
+    pub async fn uncordon(&self, selected_cluster: ClusterId, node_id: NodeId) {
+        self.set_unschedulable(selected_cluster, node_id, false)
             .await
-        };
-
-        Produces::ok(())
     }
 
-    pub async fn load_nodes(&mut self, selected_cluster: impl AsRef<ClusterId>) -> ActorResult<()> {
-        debug!("loading nodes");
+    /// Cordons the node and evicts its pods, the progress of each pod is sent as
+    /// `DrainProgress` and the result as `DrainFinished`
+    pub async fn drain(&self, selected_cluster: ClusterId, node_id: NodeId, options: DrainOptions) {
+        let responder = self.responder.read().clone();
+        let send = |message| match &responder {
+            Some(responder) => responder.callback(message),
+            None => error!("node callback called before init"),
+        };
 
-        let selected_cluster = selected_cluster.as_ref();
-        GlobalViewModel::check_and_load_client(selected_cluster).await?;
+        let result = async {
+            let client = resource::client(&selected_cluster).await?;
+            drain::drain(client, &node_id, &options, |progress| {
+                send(NodeViewModelMessage::DrainProgress {
+                    node_id: node_id.clone(),
+                    progress,
+                })
+            })
+            .await?;
 
-        let client: Client = GlobalViewModel::global()
-            .read()
-            .get_cluster_client(selected_cluster)
-            .ok_or_else(|| eyre!("client not found"))?;
+            Ok::<_, eyre::Report>(())
+        };
 
-        let nodes = kubernetes::node::get_all(client.clone())
-            .await
-            .map_err(NodeError::NodeLoadError)?;
+        let error = result.await.err().map(|error| {
+            error!("failed to drain node {}: {error}", node_id.raw_value);
+            error.to_string()
+        });
 
-        self.state.write().await.nodes = Some(nodes.clone());
+        send(NodeViewModelMessage::DrainFinished { node_id, error });
+    }
 
-        // notify frontend, nodes loaded
-        self.callback(NodeViewModelMessage::Loaded {
-            nodes: nodes.into_values().collect(),
-        })
-        .await;
+    /// Edits the taints and labels of the node as it was loaded, nothing is changed if the node
+    /// changed since, the edited node arrives through the watcher
+    pub async fn edit(&self, selected_cluster: ClusterId, node: Node, edits: Vec<NodeEdit>) {
+        let result = async {
+            let client = resource::client(&selected_cluster).await?;
+            node_edit::edit(client, &node, &edits).await?;
+            Ok::<_, eyre::Report>(())
+        };
 
-        Produces::ok(())
+        if let Err(error) = result.await {
+            error!("failed to edit node {}: {error}", node.name);
+            self.toast_error(error.to_string());
+        }
     }
 
-    async fn notify_and_load_nodes(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
-        // notify and load
-        self.callback(NodeViewModelMessage::Loading).await;
-
-        self.load_nodes(&selected_cluster)
-            .await
-            .map_err(|e| NodeError::NodeLoadError(eyre!("{e:?}")))?;
+    /// Pods on the node that the `NoExecute` taint would evict, shown before adding it
+    pub async fn untolerated_pods(
+        &self,
+        selected_cluster: ClusterId,
+        node_id: NodeId,
+        taint: Taint,
+    ) -> Vec<Pod> {
+        let pods = self.pods(selected_cluster, node_id).await;
+        node_edit::untolerated_pods(&taint, pods)
+    }
 
-        Produces::ok(())
+    pub async fn stop_watcher(&self) {
+        debug!("stopping watcher");
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
     }
 
-    async fn start_watcher(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
-        debug!("starting nodes watcher");
+    pub fn nodes(&self, selected_cluster: ClusterId) -> Vec<Node> {
+        let actor = self.actor.read().clone();
 
-        let client: Client = GlobalViewModel::global()
-            .read()
-            .get_cluster_client(selected_cluster.as_ref())
-            .ok_or_else(|| eyre!("client not found"))?;
+        task::block_on(async move {
+            let nodes = call!(actor.records()).await.ok().flatten();
 
-        // start watcher
-        self.addr.send_fut_with(|addr| async move {
-            kubernetes::node::watch(addr, selected_cluster, client)
-                .await
-                .unwrap();
-        });
+            if nodes.is_none() {
+                warn!("nodes not loaded, fetching nodes");
+                send!(actor.notify_and_load(selected_cluster));
+            }
 
-        Produces::ok(())
+            nodes.unwrap_or_default()
+        })
     }
 }
 
-#[async_trait::async_trait]
-impl Actor for Worker {
-    async fn started(&mut self, addr: Addr<Self>) -> ActorResult<()> {
-        self.addr = addr.downgrade();
+impl RustNodeViewModel {
+    async fn set_unschedulable(
+        &self,
+        selected_cluster: ClusterId,
+        node_id: NodeId,
+        unschedulable: bool,
+    ) {
+        let result = async {
+            let client = resource::client(&selected_cluster).await?;
+            drain::set_unschedulable(client, &node_id, unschedulable).await?;
+            Ok::<_, eyre::Report>(())
+        };
 
-        Produces::ok(())
+        if let Err(error) = result.await {
+            error!("failed to update node {}: {error}", node_id.raw_value);
+            self.toast_error(error.to_string());
+        }
     }
 
-    async fn error(&mut self, error: ActorError) -> bool {
-        error!("NodeViewModel Actor Error: {error:?}");
+    fn toast_error(&self, message: String) {
+        match self.responder.read().as_ref() {
+            Some(responder) => {
+                responder.callback(NodeViewModelMessage::ToastErrorMessage { message })
+            }
+            None => error!("node callback called before init"),
+        }
+    }
+}
 
-        if let Some(error) = error.downcast::<NodeError>().ok().map(|e| *e) {
-            self.callback(error.into()).await
-        } else {
-            self.callback(NodeViewModelMessage::LoadingFailed {
-                error: "Unknown error, please see logs".to_string(),
-            })
-            .await
+impl ResourceCallback<Node> for Arc<dyn NodeViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<Node>) {
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => NodeViewModelMessage::Loading { progress },
+            Msg::Loaded { records, .. } => NodeViewModelMessage::Loaded { nodes: records },
+            Msg::LoadingFailed { error } => NodeViewModelMessage::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => NodeViewModelMessage::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => NodeViewModelMessage::ConnectionChanged { state },
+            Msg::SearchError { error } => NodeViewModelMessage::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                NodeViewModelMessage::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => {
+                NodeViewModelMessage::ToastErrorMessage { message }
+            }
         };
 
-        false
+        self.as_ref().callback(message)
     }
 }
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::HashMap, sync::Arc};
 
 use k8s_openapi::api::core::v1::Pod as K8sPod;
 
@@ -6,32 +6,36 @@ use either::Either;
 use eyre::eyre;
 use fake::{Fake, Faker};
 use futures::{stream::FuturesUnordered, StreamExt};
-use kube::{core::Status, Client};
+use kube::core::Status;
 use log::{debug, error, warn};
 use parking_lot::RwLock;
 use thiserror::Error;
-use tokio::{task::JoinHandle, time};
 use uniffi::Object;
 
 use act_zero::*;
 
 use crate::{
     cluster::ClusterId,
+    fuzzy::FuzzyMatch,
     kubernetes::{
         self,
+        cache::ConnectionState,
+        diagnosis::{self, Finding},
         pod::{Pod, PodId},
+        pod_spec::{self, ContainerEnv},
+        resource::ListProgress,
     },
+    table::{Column, SortKey, TableLayout},
     task::{self, spawn_actor},
-    LoadStatus,
 };
 
-use super::global::GlobalViewModel;
+use super::{
+    global::GlobalViewModel,
+    resource::{self, ResourceCallback, ResourceMessage, ResourceViewModel},
+};
 
 #[derive(Error, Debug)]
 pub enum PodError {
-    #[error(transparent)]
-    PodLoadError(eyre::Report),
-
     #[error("pod {0} not found for delete")]
     PodNotFoundForDelete(PodId),
 
@@ -56,27 +60,54 @@ pub trait PodViewModelCallback: Send + Sync + 'static {
 
 #[derive(uniffi::Enum)]
 pub enum PodViewModelMessage {
-    Loading,
-    Loaded { pods: Vec<Pod> },
-    LoadingFailed { error: String },
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    /// Sorted by relevance when the search has free text, highlights are the matched characters
+    /// of the pod names
+    Loaded {
+        pods: Vec<Pod>,
+        highlights: HashMap<PodId, FuzzyMatch>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<Pod>,
+        updated: Vec<Pod>,
+        removed: Vec<PodId>,
+        highlights: HashMap<PodId, FuzzyMatch>,
+        positions: HashMap<PodId, u32>,
+    },
 
-    ToastWarningMessage { message: String },
-    ToastErrorMessage { message: String },
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
 }
 
+pub type PodViewModel = ResourceViewModel<K8sPod>;
+
 #[derive(Object)]
 pub struct RustPodViewModel {
     actor: RwLock<Addr<PodViewModel>>,
 }
 
-pub struct PodViewModel {
-    addr: Addr<Self>,
-    search: String,
-    watcher: Addr<Watcher>,
-    pods: LoadStatus<HashMap<PodId, Pod>, String>,
-    responder: Option<Box<dyn PodViewModelCallback>>,
-}
-
 #[uniffi::export(async_runtime = "tokio")]
 impl RustPodViewModel {
     #[uniffi::constructor]
@@ -88,8 +119,10 @@ impl RustPodViewModel {
 
     #[uniffi::constructor]
     pub fn preview() -> Arc<Self> {
+        let pods = (0..16).map(|_| Faker.fake::<Pod>());
+
         Arc::new(Self {
-            actor: RwLock::new(Default::default()),
+            actor: RwLock::new(spawn_actor(PodViewModel::preview(pods))),
         })
     }
 
@@ -98,10 +131,11 @@ impl RustPodViewModel {
         let actor = self.actor.read().clone();
 
         task::block_on(async move {
-            match call!(actor.pods()).await {
-                Ok(Some(pods)) => pods.into_values().collect(),
-                _ => vec![],
-            }
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
         })
     }
 
@@ -110,14 +144,100 @@ impl RustPodViewModel {
         send!(actor.set_search(search));
     }
 
+    /// Sorts by each key in turn, the keys are saved with the table layout
+    pub fn set_sort(self: Arc<Self>, keys: Vec<SortKey>) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_sort(keys));
+    }
+
+    pub fn set_columns(self: Arc<Self>, columns: Vec<Column>) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_columns(columns));
+    }
+
+    pub async fn layout(&self) -> TableLayout {
+        let actor = self.actor.read().clone();
+        call!(actor.layout()).await.unwrap_or_default()
+    }
+
+    /// Lists only the pod metadata first on large clusters, so names and namespaces show before
+    /// the full pods are loaded
+    pub fn set_metadata_first(self: Arc<Self>, metadata_first: bool) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_metadata_first(metadata_first));
+    }
+
     pub async fn delete_pod(self: Arc<Self>, selected_cluster: ClusterId, pod_id: PodId) {
         let actor = self.actor.read().clone();
-        let _ = call!(actor.delete_pod(selected_cluster, pod_id)).await;
+
+        if let Err(error) = Self::delete(&actor, selected_cluster, vec![pod_id]).await {
+            error!("failed to delete pod: {error}");
+        }
     }
 
     pub async fn delete_pods(self: Arc<Self>, selected_cluster: ClusterId, pod_ids: Vec<PodId>) {
         let actor = self.actor.read().clone();
-        let _ = call!(actor.delete_pods(selected_cluster, pod_ids)).await;
+
+        if let Err(error) = Self::delete(&actor, selected_cluster, pod_ids).await {
+            error!("failed to delete pods: {error}");
+        }
+    }
+
+    /// Env of each container with the ConfigMap and Secret values looked up, for the pod details
+    pub async fn container_env(
+        self: Arc<Self>,
+        selected_cluster: ClusterId,
+        pod_id: PodId,
+    ) -> Vec<ContainerEnv> {
+        let actor = self.actor.read().clone();
+
+        let env = async {
+            let pod = call!(actor.records_with_ids(vec![pod_id.clone()]))
+                .await?
+                .pop()
+                .ok_or_else(|| eyre!("pod {pod_id} not found"))?;
+
+            let client = resource::client(&selected_cluster).await?;
+            pod_spec::resolve_env(client, &pod).await
+        };
+
+        match env.await {
+            Ok(env) => env,
+            Err(error) => {
+                error!("failed to resolve env of pod {pod_id}: {error}");
+                send!(actor.callback(ResourceMessage::ToastErrorMessage {
+                    message: format!("Unable to resolve the env of pod {pod_id}: {error}"),
+                }));
+
+                vec![]
+            }
+        }
+    }
+
+    /// Ranked findings on why the pod isn't healthy, from its events, node and controller
+    pub async fn diagnose(
+        self: Arc<Self>,
+        selected_cluster: ClusterId,
+        pod_id: PodId,
+    ) -> Vec<Finding> {
+        let actor = self.actor.read().clone();
+
+        let findings = async {
+            let pod = call!(actor.records_with_ids(vec![pod_id.clone()]))
+                .await?
+                .pop()
+                .ok_or_else(|| eyre!("pod {pod_id} not found"))?;
+
+            let client = resource::client(&selected_cluster).await?;
+            let context = diagnosis::context(client, &pod).await;
+            Ok::<_, eyre::Report>(diagnosis::diagnose(&pod, &context))
+        };
+
+        // an empty list reads as nothing wrong, a failure is a finding of its own
+        findings.await.unwrap_or_else(|error| {
+            error!("failed to diagnose pod {pod_id}: {error}");
+            vec![Finding::diagnosis_failed(error.to_string())]
+        })
     }
 
     pub async fn initialize_model_with_responder(&self, responder: Box<dyn PodViewModelCallback>) {
@@ -144,168 +264,53 @@ impl RustPodViewModel {
         debug!("starting pod watcher for cluster: {selected_cluster:?}");
         let actor = self.actor.read().clone();
 
-        call!(actor.start_watcher(selected_cluster))
-            .await
-            .expect("failed to start pod watcher");
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start pod watcher: {error}");
+        }
     }
 
     pub async fn stop_watcher(&self) {
         debug!("stopping pod watcher");
         let actor = self.actor.read().clone();
-        call!(actor.stop_watcher())
-            .await
-            .expect("failed to stop pod watcher");
+        let _ = call!(actor.stop_watcher()).await;
     }
 
     pub async fn fetch_pods(&self, selected_cluster: ClusterId) {
         debug!("fetching pods for cluster: {selected_cluster:?}");
         let actor = self.actor.read().clone();
 
-        if let Err(error) = call!(actor.notify_and_load_pods(selected_cluster)).await {
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
             error!("failed to fetch pods: {error}");
         }
     }
 }
 
-impl Default for PodViewModel {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl PodViewModel {
-    pub fn new() -> Self {
-        Self {
-            addr: Default::default(),
-            watcher: Default::default(),
-
-            search: String::new(),
-            pods: LoadStatus::Initial,
-            responder: None,
-        }
-    }
-
-    pub fn preview() -> Self {
-        Self {
-            addr: Default::default(),
-            watcher: Default::default(),
-            search: String::new(),
-            pods: LoadStatus::Loaded(
-                (0..16)
-                    .map(|_| Faker.fake::<Pod>())
-                    .map(|pod| (pod.id.clone(), pod))
-                    .collect(),
-            ),
-            responder: None,
-        }
-    }
-
-    pub async fn set_search(&mut self, search: String) {
-        self.search = search;
-        self.notify_pods_loaded().await;
-    }
-
-    pub async fn is_started(&self) -> ActorResult<()> {
-        Produces::ok(())
-    }
-
-    fn filtered_pods_iter(&self) -> Option<impl Iterator<Item = (&PodId, &Pod)>> {
-        match &self.pods {
-            LoadStatus::Loaded(pods) => {
-                let pods = pods.iter().filter(|(_, pod)| {
-                    if self.search.is_empty() {
-                        return true;
-                    }
-
-                    pod.id.as_ref().contains(&self.search) || pod.name.contains(&self.search)
-                });
-
-                Some(pods)
-            }
-            _ => None,
-        }
-    }
-
-    pub fn pods_filtered_vec(&self) -> Option<Vec<Pod>> {
-        let pods: Vec<_> = self
-            .filtered_pods_iter()?
-            .map(|(_, pod)| pod.clone())
-            .collect::<Vec<_>>();
-
-        Some(pods)
-    }
-
-    pub async fn pods(&self) -> ActorResult<Option<HashMap<PodId, Pod>>> {
-        match self.filtered_pods_iter() {
-            Some(pods_iter) => {
-                let pods = pods_iter.map(|(k, v)| (k.clone(), v.clone())).collect();
-                Produces::ok(Some(pods))
-            }
-            None => Produces::ok(None),
-        }
-    }
-
-    pub async fn update_pod(&mut self, pod: Pod) -> Option<Pod> {
-        match &mut self.pods {
-            LoadStatus::Loaded(pods) => pods.insert(pod.id.clone(), pod),
-            _ => None,
-        }
-    }
-
-    pub async fn delete_pod(
-        &mut self,
-        selected_cluster: ClusterId,
-        pod_id: impl Into<Cow<'_, PodId>>,
-    ) -> ActorResult<()> {
-        let pod_id: Cow<'_, PodId> = pod_id.into();
-        let pod_id = pod_id.as_ref();
-
-        debug!("deleting pod: {:?}", pod_id);
-        let LoadStatus::Loaded(pods) = &self.pods else {
-            return Err(eyre::eyre!("pods not loaded").into());
-        };
-
-        let pod = pods
-            .get(pod_id)
-            .cloned()
-            .ok_or_else(|| PodError::PodNotFoundForDelete(pod_id.clone()))?;
-
-        let client = GlobalViewModel::global()
-            .read()
-            .get_cluster_client(&selected_cluster)
-            .ok_or_else(|| eyre!("client not found"))?;
-
-        kubernetes::pod::delete(client, &pod).await?;
-
-        Produces::ok(())
-    }
-
-    pub async fn delete_pods(
-        &mut self,
+impl RustPodViewModel {
+    /// Deletes the pods, grouped by namespace, failures are sent to the UI as toasts
+    async fn delete(
+        actor: &Addr<PodViewModel>,
         selected_cluster: ClusterId,
         pod_ids: Vec<PodId>,
-    ) -> ActorResult<()> {
+    ) -> eyre::Result<()> {
         if pod_ids.is_empty() {
-            return Produces::ok(());
-        }
-
-        if pod_ids.len() == 1 {
-            return self.delete_pod(selected_cluster, &pod_ids[0]).await;
+            return Ok(());
         }
 
         debug!("deleting pods: {:?}", pod_ids);
-        let LoadStatus::Loaded(pods) = &self.pods else {
-            return Err(eyre::eyre!("pods not loaded").into());
-        };
+        let pods = call!(actor.records_with_ids(pod_ids.clone())).await?;
 
-        let grouped: HashMap<String, Vec<PodId>> = pod_ids
-            .into_iter()
-            .filter_map(|pod_id| {
-                let namespace = pods.get(&pod_id).as_ref()?.namespace.clone();
-                Some((namespace, pod_id))
-            })
-            .fold(HashMap::new(), |mut acc, (namespace, pod_id)| {
-                acc.entry(namespace).or_insert_with(Vec::new).push(pod_id);
+        for pod_id in pod_ids.iter() {
+            if !pods.iter().any(|pod| &pod.id == pod_id) {
+                let error = PodError::PodNotFoundForDelete(pod_id.clone());
+                send!(actor.callback(error.into()));
+            }
+        }
+
+        let grouped: HashMap<String, Vec<PodId>> =
+            pods.into_iter().fold(HashMap::new(), |mut acc, pod| {
+                acc.entry(pod.namespace)
+                    .or_insert_with(Vec::new)
+                    .push(pod.id);
                 acc
             });
 
@@ -334,136 +339,61 @@ impl PodViewModel {
         for result in results {
             if let Err(kubernetes::pod::Error::DeleteError(pod_id, error)) = result {
                 error!("failed to delete pod ({pod_id}): {error:?}");
-                self.callback(PodError::PodDeleteError(pod_id, error).into())
-                    .await
+                send!(actor.callback(PodError::PodDeleteError(pod_id, error).into()));
             }
         }
 
-        Produces::ok(())
+        Ok(())
     }
+}
 
-    pub async fn add_callback_listener(&mut self, responder: Box<dyn PodViewModelCallback>) {
-        self.responder = Some(responder);
-    }
-
-    pub async fn callback(&self, msg: PodViewModelMessage) {
-        self.responder
-            .as_ref()
-            .expect("pod callback called before init")
-            .callback(msg);
-    }
-
-    pub async fn notify_and_load_pods(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
-        debug!("notifying and loading pods");
-
-        // notify UI that pods are going to be loaded
-        self.notify_pods_loading().await;
-
-        // handle loading pods and notifying its done
-        self.load_pods(selected_cluster).await?;
-
-        Produces::ok(())
-    }
-
-    pub async fn load_pods(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
-        // TODO: handle error
-        GlobalViewModel::check_and_load_client(&selected_cluster).await?;
-
-        let client: Client = GlobalViewModel::global()
-            .read()
-            .get_cluster_client(&selected_cluster)
-            .ok_or_else(|| eyre!("client not found"))?;
-
-        // fetch pods
-        let pods_map = kubernetes::pod::get_all(client).await?;
-
-        // save in model
-        self.pods = LoadStatus::Loaded(pods_map);
-
-        // notify ui
-        self.notify_pods_loaded().await;
-
-        Produces::ok(())
-    }
-
-    pub async fn start_watcher(&mut self, selected_cluster: ClusterId) {
-        // create watcher actor
-        self.watcher = spawn_actor(Watcher::new(selected_cluster, self.addr.clone()));
-
-        // start watcher
-        send!(self.watcher.start_watcher());
-
-        // start refresh on interval
-        send!(self.watcher.refresh_on_interval());
-    }
-
-    pub async fn stop_watcher(&mut self) -> ActorResult<()> {
-        std::mem::take(&mut self.watcher);
-        Produces::ok(())
-    }
-
-    pub async fn applied(&mut self, pod: Pod) -> ActorResult<()> {
-        debug!("pod applied: {:?}", pod.id);
-
-        if let Produces::Value(Some(ref pods)) = self.pods().await? {
-            if let Some(existing_pod) = pods.get(&pod.id) {
-                if existing_pod == &pod {
-                    debug!("same pod already exists, ignoring");
-                    return Produces::ok(());
-                }
+impl ResourceCallback<Pod> for Box<dyn PodViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<Pod>) {
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => PodViewModelMessage::Loading { progress },
+            Msg::Loaded {
+                records,
+                highlights,
+            } => PodViewModelMessage::Loaded {
+                pods: records,
+                highlights,
+            },
+            Msg::LoadingFailed { error } => PodViewModelMessage::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                highlights,
+                positions,
+            } => PodViewModelMessage::Changed {
+                inserted,
+                updated,
+                removed,
+                highlights,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => PodViewModelMessage::ConnectionChanged { state },
+            Msg::SearchError { error } => PodViewModelMessage::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                PodViewModelMessage::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => {
+                PodViewModelMessage::ToastErrorMessage { message }
             }
         };
 
-        // update existing pod
-        self.update_pod(pod).await;
-
-        // notify pods updated
-        self.notify_pods_loaded().await;
-
-        Produces::ok(())
-    }
-
-    pub async fn deleted(&mut self, pod: Pod) -> ActorResult<()> {
-        debug!("deleted: {:?}", pod.id);
-
-        let LoadStatus::Loaded(pods) = &mut self.pods else {
-            return Produces::ok(());
-        };
-
-        debug!("removing pod: {:?}", pod.id);
-        if pods.remove(&pod.id).is_some() {
-            // only notify if pod existed before
-            self.notify_pods_loaded().await;
-        } else {
-            debug!("pod not found: {:?}", pod.id);
-        }
-
-        Produces::ok(())
-    }
-
-    async fn notify_pods_loading(&self) {
-        self.callback(PodViewModelMessage::Loading).await
-    }
-
-    async fn notify_pods_loaded(&self) {
-        if let Some(pods) = self.pods_filtered_vec() {
-            debug!("notifying pods loaded");
-
-            self.callback(PodViewModelMessage::Loaded { pods }).await
-        }
+        self.as_ref().callback(message)
     }
 }
 
-impl From<PodError> for PodViewModelMessage {
+impl From<PodError> for ResourceMessage<Pod> {
     fn from(error: PodError) -> Self {
         use PodError as E;
-        use PodViewModelMessage as Msg;
+        use ResourceMessage as Msg;
 
         match error {
-            E::PodLoadError(e) => Msg::LoadingFailed {
-                error: e.to_string(),
-            },
-
             E::PodNotFoundForDelete(pod_id) => Msg::ToastWarningMessage {
                 message: format!("Pod with id ({pod_id}) not found, unable to delete"),
             },
@@ -474,92 +404,3 @@ impl From<PodError> for PodViewModelMessage {
         }
     }
 }
-
-#[async_trait::async_trait]
-impl Actor for PodViewModel {
-    async fn started(&mut self, addr: Addr<Self>) -> ActorResult<()> {
-        self.addr = addr;
-        Produces::ok(())
-    }
-
-    async fn error(&mut self, error: ActorError) -> bool {
-        error!("PodViewModel Actor Error: {error:?}");
-
-        if let Some(error) = error.downcast::<PodError>().ok().map(|e| *e) {
-            self.callback(error.into()).await
-        } else {
-            self.callback(PodViewModelMessage::LoadingFailed {
-                error: "Unknown error, please see logs".to_string(),
-            })
-            .await
-        };
-
-        false
-    }
-}
-
-impl Actor for Watcher {}
-pub struct Watcher {
-    selected_cluster: ClusterId,
-    model_actor: Addr<PodViewModel>,
-    tasks: Vec<JoinHandle<()>>,
-}
-
-impl Watcher {
-    fn new(selected_cluster: ClusterId, addr: Addr<PodViewModel>) -> Self {
-        Self {
-            selected_cluster,
-            model_actor: addr,
-            tasks: Vec::with_capacity(2),
-        }
-    }
-
-    async fn start_watcher(&mut self) -> ActorResult<()> {
-        GlobalViewModel::check_and_load_client(&self.selected_cluster).await?;
-
-        let client = GlobalViewModel::global()
-            .read()
-            .get_cluster_client(&self.selected_cluster)
-            .ok_or_else(|| eyre!("client not found"))?;
-
-        let model_actor = self.model_actor.clone();
-        let selected_cluster = self.selected_cluster.clone();
-
-        let task = task::spawn(async move {
-            kubernetes::pod::watch(model_actor, selected_cluster, client)
-                .await
-                .expect("pod watcher failed to start");
-        });
-
-        self.tasks.push(task);
-
-        Produces::ok(())
-    }
-
-    async fn refresh_on_interval(&mut self) {
-        let model_actor = self.model_actor.clone();
-        let selected_cluster = self.selected_cluster.clone();
-
-        let task = tokio::spawn(async move {
-            let mut interval = time::interval(Duration::from_secs(60));
-            interval.tick().await;
-
-            loop {
-                interval.tick().await;
-                debug!("60 seconds past, loading pods");
-                send!(model_actor.load_pods(selected_cluster.clone()));
-            }
-        });
-
-        self.tasks.push(task);
-    }
-}
-
-impl Drop for Watcher {
-    fn drop(&mut self) {
-        debug!("dropping pod watcher, aborting all tasks");
-        for task in self.tasks.iter() {
-            task.abort();
-        }
-    }
-}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use act_zero::*;
+use k8s_openapi::api::{
+    core::v1::Pod as K8sPod, policy::v1::PodDisruptionBudget as K8sPodDisruptionBudget,
+};
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        self,
+        cache::ConnectionState,
+        node::NodeId,
+        pod::{Pod, PodId},
+        pod_disruption_budget::{DrainBlocker, PodDisruptionBudget, PodDisruptionBudgetId},
+        resource::ListProgress,
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{RelatedCache, ResourceCallback, ResourceMessage, ResourceViewModel};
+
+/// Pod changes are collected for this long before the UI is told to read the matching pods again
+const POD_CHANGES_INTERVAL: Duration = Duration::from_millis(500);
+
+#[uniffi::export(callback_interface)]
+pub trait PodDisruptionBudgetViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: PodDisruptionBudgetViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum PodDisruptionBudgetViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        pod_disruption_budgets: Vec<PodDisruptionBudget>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<PodDisruptionBudget>,
+        updated: Vec<PodDisruptionBudget>,
+        removed: Vec<PodDisruptionBudgetId>,
+        positions: HashMap<PodDisruptionBudgetId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+
+    /// Pods changed, the matching pods and drain blockers should be read again
+    PodsChanged,
+}
+
+pub type PodDisruptionBudgetViewModel = ResourceViewModel<K8sPodDisruptionBudget>;
+
+#[derive(Object)]
+pub struct RustPodDisruptionBudgetViewModel {
+    actor: RwLock<Addr<PodDisruptionBudgetViewModel>>,
+
+    /// Selectors are resolved against the pod cache shared with the other windows
+    pods: RwLock<Option<RelatedCache<K8sPod>>>,
+    responder: RwLock<Option<Arc<dyn PodDisruptionBudgetViewModelCallback>>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustPodDisruptionBudgetViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+            pods: RwLock::new(None),
+            responder: RwLock::new(None),
+        })
+    }
+
+    pub fn pod_disruption_budgets(self: Arc<Self>) -> Vec<PodDisruptionBudget> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Pods currently selected by the pod disruption budget
+    pub fn matching_pods(self: Arc<Self>, id: PodDisruptionBudgetId) -> Vec<Pod> {
+        let actor = self.actor.read().clone();
+
+        let pdbs = task::block_on(async move {
+            call!(actor.records_with_ids(vec![id]))
+                .await
+                .unwrap_or_default()
+        });
+
+        let Some(pdb) = pdbs.first() else {
+            return vec![];
+        };
+
+        let pods = self.pods();
+        pdb.matching_pods(pods.values())
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Pod disruption budgets that would block evicting the pods on the node during a drain
+    pub fn drain_blockers(self: Arc<Self>, node_id: NodeId) -> Vec<DrainBlocker> {
+        let actor = self.actor.read().clone();
+
+        let pdbs =
+            task::block_on(async move { call!(actor.all_records()).await.unwrap_or_default() });
+
+        let pods = self.pods();
+        kubernetes::pod_disruption_budget::drain_blockers(&node_id, &pdbs, pods.values())
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn PodDisruptionBudgetViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("pod disruption budget view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(PodDisruptionBudgetViewModel::new());
+        }
+
+        let responder: Arc<dyn PodDisruptionBudgetViewModelCallback> = Arc::from(responder);
+        *self.responder.write() = Some(responder.clone());
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_pod_disruption_budgets(&self, selected_cluster: ClusterId) {
+        debug!("fetching pod disruption budgets for cluster: {selected_cluster:?}");
+        self.watch_pods(&selected_cluster).await;
+
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch pod disruption budgets: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        self.watch_pods(&selected_cluster).await;
+
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start pod disruption budget watcher: {error}");
+        }
+    }
+
+    /// Stops watching and releases the pod cache, which stops once no other window uses it
+    pub async fn stop_watcher(&self) {
+        *self.pods.write() = None;
+
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl RustPodDisruptionBudgetViewModel {
+    fn pods(&self) -> HashMap<PodId, Pod> {
+        self.pods
+            .read()
+            .as_ref()
+            .map(RelatedCache::records)
+            .unwrap_or_default()
+    }
+
+    /// Starts following the pods of the cluster, the UI is told when they change
+    async fn watch_pods(&self, selected_cluster: &ClusterId) {
+        let watching = self
+            .pods
+            .read()
+            .as_ref()
+            .is_some_and(|pods| pods.cluster_id() == selected_cluster);
+
+        if watching {
+            return;
+        }
+
+        let responder = self.responder.read().clone();
+        let on_change = move || match &responder {
+            Some(responder) => responder.callback(PodDisruptionBudgetViewModelMessage::PodsChanged),
+            None => error!("pod disruption budget callback called before init"),
+        };
+
+        match RelatedCache::start(selected_cluster, POD_CHANGES_INTERVAL, on_change).await {
+            Ok(pods) => *self.pods.write() = Some(pods),
+            Err(error) => error!("failed to watch pods for pod disruption budgets: {error}"),
+        }
+    }
+}
+
+impl ResourceCallback<PodDisruptionBudget> for Arc<dyn PodDisruptionBudgetViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<PodDisruptionBudget>) {
+        use PodDisruptionBudgetViewModelMessage as PodDisruptionBudgetMsg;
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => PodDisruptionBudgetMsg::Loading { progress },
+            Msg::Loaded { records, .. } => PodDisruptionBudgetMsg::Loaded {
+                pod_disruption_budgets: records,
+            },
+            Msg::LoadingFailed { error } => PodDisruptionBudgetMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => PodDisruptionBudgetMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => PodDisruptionBudgetMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => PodDisruptionBudgetMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                PodDisruptionBudgetMsg::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => {
+                PodDisruptionBudgetMsg::ToastErrorMessage { message }
+            }
+        };
+
+        self.as_ref().callback(message)
+    }
+}
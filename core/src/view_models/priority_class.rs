@@ -0,0 +1,250 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use act_zero::*;
+use k8s_openapi::api::{
+    core::v1::Pod as K8sPod, scheduling::v1::PriorityClass as K8sPriorityClass,
+};
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        cache::ConnectionState,
+        pod::{Pod, PodId},
+        priority_class::{PriorityClass, PriorityClassId, PriorityClassUsage},
+        resource::ListProgress,
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{RelatedCache, ResourceCallback, ResourceMessage, ResourceViewModel};
+
+/// Pod changes are collected for this long before the UI is told to read the usages again
+const POD_CHANGES_INTERVAL: Duration = Duration::from_millis(500);
+
+#[uniffi::export(callback_interface)]
+pub trait PriorityClassViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: PriorityClassViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum PriorityClassViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        priority_classes: Vec<PriorityClass>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<PriorityClass>,
+        updated: Vec<PriorityClass>,
+        removed: Vec<PriorityClassId>,
+        positions: HashMap<PriorityClassId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+
+    /// Pods changed, the usages should be read again
+    PodsChanged,
+}
+
+pub type PriorityClassViewModel = ResourceViewModel<K8sPriorityClass>;
+
+#[derive(Object)]
+pub struct RustPriorityClassViewModel {
+    actor: RwLock<Addr<PriorityClassViewModel>>,
+
+    /// Pods are linked to their priority class from the pod cache shared with the other windows
+    pods: RwLock<Option<RelatedCache<K8sPod>>>,
+    responder: RwLock<Option<Arc<dyn PriorityClassViewModelCallback>>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustPriorityClassViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+            pods: RwLock::new(None),
+            responder: RwLock::new(None),
+        })
+    }
+
+    pub fn priority_classes(self: Arc<Self>) -> Vec<PriorityClass> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Pods using each priority class and how many pods each class can preempt
+    pub fn priority_class_usages(self: Arc<Self>) -> Vec<PriorityClassUsage> {
+        let actor = self.actor.read().clone();
+
+        let priority_classes =
+            task::block_on(async move { call!(actor.all_records()).await.unwrap_or_default() });
+
+        let pods = self.pods();
+
+        priority_classes
+            .iter()
+            .map(|priority_class| priority_class.usage(pods.values()))
+            .collect()
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn PriorityClassViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("priority class view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(PriorityClassViewModel::new());
+        }
+
+        let responder: Arc<dyn PriorityClassViewModelCallback> = Arc::from(responder);
+        *self.responder.write() = Some(responder.clone());
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_priority_classes(&self, selected_cluster: ClusterId) {
+        debug!("fetching priority classes for cluster: {selected_cluster:?}");
+        self.watch_pods(&selected_cluster).await;
+
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch priority classes: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        self.watch_pods(&selected_cluster).await;
+
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start priority class watcher: {error}");
+        }
+    }
+
+    /// Stops watching and releases the pod cache, which stops once no other window uses it
+    pub async fn stop_watcher(&self) {
+        *self.pods.write() = None;
+
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl RustPriorityClassViewModel {
+    fn pods(&self) -> HashMap<PodId, Pod> {
+        self.pods
+            .read()
+            .as_ref()
+            .map(RelatedCache::records)
+            .unwrap_or_default()
+    }
+
+    /// Starts following the pods of the cluster, the UI is told when they change
+    async fn watch_pods(&self, selected_cluster: &ClusterId) {
+        let watching = self
+            .pods
+            .read()
+            .as_ref()
+            .is_some_and(|pods| pods.cluster_id() == selected_cluster);
+
+        if watching {
+            return;
+        }
+
+        let responder = self.responder.read().clone();
+        let on_change = move || match &responder {
+            Some(responder) => responder.callback(PriorityClassViewModelMessage::PodsChanged),
+            None => error!("priority class callback called before init"),
+        };
+
+        match RelatedCache::start(selected_cluster, POD_CHANGES_INTERVAL, on_change).await {
+            Ok(pods) => *self.pods.write() = Some(pods),
+            Err(error) => error!("failed to watch pods for priority classes: {error}"),
+        }
+    }
+}
+
+impl ResourceCallback<PriorityClass> for Arc<dyn PriorityClassViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<PriorityClass>) {
+        use PriorityClassViewModelMessage as PriorityClassMsg;
+        use ResourceMessage as Msg;
+
+        let message = match message {
+            Msg::Loading { progress } => PriorityClassMsg::Loading { progress },
+            Msg::Loaded { records, .. } => PriorityClassMsg::Loaded {
+                priority_classes: records,
+            },
+            Msg::LoadingFailed { error } => PriorityClassMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => PriorityClassMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => PriorityClassMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => PriorityClassMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                PriorityClassMsg::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => PriorityClassMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}
@@ -0,0 +1,823 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use act_zero::*;
+use eyre::eyre;
+use kube::Client;
+use log::{debug, error};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+
+use crate::{
+    cluster::ClusterId,
+    fuzzy::FuzzyMatch,
+    kubernetes::{
+        self,
+        cache::{ConnectionState, ResourceCache},
+        resource::{
+            KubeResource, ListProgress, RecordHistory, RecordId, ResourceRecord, UsageById,
+        },
+    },
+    query::{Query, Selectors},
+    table::{self, Column, SortKey, TableLayout},
+    task,
+    user_config::USER_CONFIG,
+    LoadStatus,
+};
+
+use super::global::GlobalViewModel;
+
+#[derive(Error, Debug)]
+pub enum ResourceError {
+    #[error(transparent)]
+    LoadError(eyre::Report),
+}
+
+/// Changes from the watcher are collected for this long and sent to the UI as one batch
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+/// How long the search has to stay the same before its selectors are sent to the api server,
+/// every change of selectors re-lists and restarts the watch
+const SELECTOR_DELAY: Duration = Duration::from_millis(500);
+
+/// Messages every resource view model sends, converted into the uniffi message of the kind by its
+/// [`ResourceCallback`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResourceMessage<R: ResourceRecord> {
+    /// Progress is sent after each page while listing, following a `Loaded` with the records
+    /// listed so far, the list is done at the next `Loaded`
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    /// Sorted by the sort keys of the table, ties by relevance when the search has free text and
+    /// by id otherwise
+    Loaded {
+        records: Vec<R>,
+        highlights: Highlights<R>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Changes to the records since the last `Loaded` or `Changed`, only for records matching the
+    /// query, sorted like `Loaded`, with the position of every inserted and updated record in the
+    /// list after the changes
+    Changed {
+        inserted: Vec<R>,
+        updated: Vec<R>,
+        removed: Vec<R::Id>,
+        highlights: Highlights<R>,
+        positions: Positions<R>,
+    },
+
+    /// The records are stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// Sent after every search, `None` once the search parses again
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+/// Fuzzy matches of the search on the records that have them, to highlight the matched characters
+pub type Highlights<R> = HashMap<<R as ResourceRecord>::Id, FuzzyMatch>;
+
+/// Index of each changed record in the list sorted like `Loaded`, inserting the changed records
+/// in order of their index into the unchanged ones gives the sorted list
+pub type Positions<R> = HashMap<<R as ResourceRecord>::Id, u32>;
+
+pub trait ResourceCallback<R: ResourceRecord>: Send + Sync + 'static {
+    fn callback(&self, message: ResourceMessage<R>);
+}
+
+/// Loading status, watcher, search and error reporting shared by the resource tabs, records come
+/// from the [`ResourceCache`] of the cluster shared with the other windows
+pub struct ResourceViewModel<K: KubeResource> {
+    addr: WeakAddr<Self>,
+    query: Query,
+
+    /// Part of the query the api server filters by, the cache lists and watches with these
+    selectors: Selectors,
+    selectors_generation: u64,
+
+    /// Columns the table is sorted by, saved in the user config
+    sort: Vec<SortKey>,
+
+    /// Whether a cache started by this view model lists only the metadata first
+    metadata_first: bool,
+    cache: Option<Arc<ResourceCache<K>>>,
+    watcher: Option<JoinHandle<()>>,
+    connection: ConnectionState,
+
+    /// Last usage from the metrics api, polled while the watcher runs
+    usage: UsageById<K>,
+    usage_poller: Option<JoinHandle<()>>,
+
+    records: LoadStatus<HashMap<RecordId<K>, K::Record>, String>,
+
+    /// Ids of the records the UI has, as of the last message sent
+    shown: HashSet<RecordId<K>>,
+
+    /// Ids changed by the watcher since the last message sent
+    pending: HashSet<RecordId<K>>,
+    flush_scheduled: bool,
+
+    responder: Option<Box<dyn ResourceCallback<K::Record>>>,
+}
+
+impl<K: KubeResource> Default for ResourceViewModel<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: KubeResource> ResourceViewModel<K> {
+    pub fn new() -> Self {
+        Self {
+            addr: Default::default(),
+            query: Query::default(),
+            selectors: Selectors::default(),
+            selectors_generation: 0,
+            sort: USER_CONFIG
+                .read()
+                .get_table_layout::<K::Record>(K::NAME)
+                .sort,
+            metadata_first: false,
+            cache: None,
+            watcher: None,
+            connection: ConnectionState::Connected,
+            usage: HashMap::new(),
+            usage_poller: None,
+            records: LoadStatus::Initial,
+            shown: HashSet::new(),
+            pending: HashSet::new(),
+            flush_scheduled: false,
+            responder: None,
+        }
+    }
+
+    /// Starts with already loaded records, used by the SwiftUI previews
+    pub fn preview(records: impl IntoIterator<Item = K::Record>) -> Self {
+        Self {
+            records: LoadStatus::Loaded(
+                records
+                    .into_iter()
+                    .map(|record| (record.id().clone(), record))
+                    .collect(),
+            ),
+            ..Self::new()
+        }
+    }
+
+    pub async fn is_started(&self) -> ActorResult<()> {
+        Produces::ok(())
+    }
+
+    pub async fn is_loaded(&self) -> ActorResult<bool> {
+        Produces::ok(matches!(self.records, LoadStatus::Loaded(_)))
+    }
+
+    pub async fn add_callback_listener(&mut self, responder: impl ResourceCallback<K::Record>) {
+        self.responder = Some(Box::new(responder));
+    }
+
+    pub async fn callback(&self, msg: ResourceMessage<K::Record>) {
+        match self.responder.as_ref() {
+            Some(responder) => responder.callback(msg),
+            None => error!("{} callback called before init", K::NAME),
+        }
+    }
+
+    /// Parses the search as a [`Query`], on a parse error the previous query is kept, invalid label
+    /// syntax is reported but the query is still used
+    pub async fn set_search(&mut self, search: String) {
+        match Query::parse::<K::Record>(&search) {
+            Ok(query) => {
+                let error = query.invalid_label().map(|error| error.to_string());
+                self.callback(ResourceMessage::SearchError { error }).await;
+
+                if query == self.query {
+                    return;
+                }
+
+                self.query = query;
+            }
+            Err(error) => {
+                let error = Some(error.to_string());
+                self.callback(ResourceMessage::SearchError { error }).await;
+                return;
+            }
+        }
+
+        // the query filters the loaded records right away, the selectors follow once typing stops
+        self.notify_loaded().await;
+        self.schedule_selectors();
+    }
+
+    /// Re-lists with the selectors of the current query, skipped if the search changed again
+    /// since it was scheduled
+    pub async fn apply_selectors(&mut self, generation: u64) -> ActorResult<()> {
+        if generation != self.selectors_generation {
+            return Produces::ok(());
+        }
+
+        let selectors = self.query.selectors::<K::Record>();
+        if selectors == self.selectors {
+            return Produces::ok(());
+        }
+
+        debug!("{} selectors changed: {selectors:?}", K::NAME);
+        self.selectors = selectors;
+
+        // nothing is loaded yet, the first load uses the new selectors
+        let Some(cluster) = self.cache.as_ref().map(|cache| cache.cluster_id().clone()) else {
+            return Produces::ok(());
+        };
+
+        let watching = self.watcher.is_some();
+        self.load(cluster.clone()).await?;
+
+        if watching {
+            self.start_watcher(cluster).await?;
+        }
+
+        Produces::ok(())
+    }
+
+    /// Saved column layout and sort of the table
+    pub async fn layout(&self) -> ActorResult<TableLayout> {
+        Produces::ok(USER_CONFIG.read().get_table_layout::<K::Record>(K::NAME))
+    }
+
+    /// Sorts by each key in turn, unknown fields are ignored, an empty list sorts by relevance and
+    /// id again
+    pub async fn set_sort(&mut self, keys: Vec<SortKey>) {
+        let sort = table::sort_keys::<K::Record>(keys);
+        if sort == self.sort {
+            return;
+        }
+
+        self.sort = sort.clone();
+        self.save_layout(|layout| layout.sort = sort);
+        self.notify_loaded().await;
+    }
+
+    /// Order and visibility of the columns, unknown columns are dropped and missing ones added at
+    /// the end
+    pub async fn set_columns(&mut self, columns: Vec<Column>) {
+        self.save_layout(|layout| layout.columns = columns);
+    }
+
+    /// List only the metadata on the first load, showing the names right away while the cache
+    /// lists the full objects, has no effect once another window started the cache
+    pub async fn set_metadata_first(&mut self, metadata_first: bool) {
+        self.metadata_first = metadata_first;
+    }
+
+    /// Records matching the search, sorted like `Loaded`, `None` until loaded
+    pub async fn records(&self) -> ActorResult<Option<Vec<K::Record>>> {
+        Produces::ok(self.filtered_records().map(|(records, _)| records))
+    }
+
+    /// Every record ignoring the search, empty until loaded
+    pub async fn all_records(&self) -> ActorResult<Vec<K::Record>> {
+        let LoadStatus::Loaded(records) = &self.records else {
+            return Produces::ok(vec![]);
+        };
+
+        Produces::ok(records.values().cloned().collect())
+    }
+
+    /// Records with the given ids, ignoring the search, unknown ids are skipped
+    pub async fn records_with_ids(&self, ids: Vec<RecordId<K>>) -> ActorResult<Vec<K::Record>> {
+        let LoadStatus::Loaded(records) = &self.records else {
+            return Produces::ok(vec![]);
+        };
+
+        Produces::ok(
+            ids.iter()
+                .filter_map(|id| records.get(id))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// What the shared cache observed on the object, `None` if it never saw it
+    pub async fn history(&self, id: RecordId<K>) -> ActorResult<Option<RecordHistory<K>>> {
+        Produces::ok(self.cache.as_ref().and_then(|cache| cache.history(&id)))
+    }
+
+    pub async fn notify_and_load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        debug!("notifying and loading {}s", K::NAME);
+
+        self.callback(ResourceMessage::Loading { progress: None })
+            .await;
+        self.load(selected_cluster).await?;
+
+        Produces::ok(())
+    }
+
+    pub async fn load(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let cache = self
+            .cache(selected_cluster.clone())
+            .await
+            .map_err(ResourceError::LoadError)?;
+
+        // while the cache is still listing, the watcher sends each page as it arrives and the
+        // records once the cache is ready, without holding the actor until then
+        if !cache.is_ready() {
+            if self.watcher.is_none() {
+                self.watcher = Some(self.spawn_watcher(cache));
+            }
+
+            return Produces::ok(());
+        }
+
+        let records = cache.records();
+        self.records = LoadStatus::Loaded(self.with_usage(records));
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn start_watcher(&mut self, selected_cluster: ClusterId) -> ActorResult<()> {
+        let cache = self.cache(selected_cluster.clone()).await?;
+        let client = client(&selected_cluster).await?;
+        let cluster_id = selected_cluster.clone();
+
+        let task = self.spawn_watcher(cache);
+        if let Some(previous) = self.watcher.replace(task) {
+            previous.abort();
+        }
+
+        let addr = self.addr.clone();
+        let poller = task::spawn(async move {
+            if let Err(error) = kubernetes::resource::poll_usage(addr, cluster_id, client).await {
+                error!("{} usage polling stopped: {error:?}", K::NAME);
+            }
+        });
+
+        if let Some(previous) = self.usage_poller.replace(poller) {
+            previous.abort();
+        }
+
+        Produces::ok(())
+    }
+
+    /// Stops listening for changes and releases the cache, which stops once no other window uses
+    /// it
+    pub async fn stop_watcher(&mut self) -> ActorResult<()> {
+        self.stop_tasks();
+        self.cache = None;
+
+        Produces::ok(())
+    }
+
+    pub async fn connection_changed(&mut self, state: ConnectionState) -> ActorResult<()> {
+        if self.connection == state {
+            return Produces::ok(());
+        }
+
+        // the cache keeps retrying the initial list, the records are sent once it succeeds
+        if !matches!(self.records, LoadStatus::Loaded(_)) {
+            if let ConnectionState::Reconnecting { error }
+            | ConnectionState::Disconnected { error } = &state
+            {
+                let error = error.clone();
+                self.callback(ResourceMessage::LoadingFailed { error })
+                    .await;
+            }
+        }
+
+        self.connection = state.clone();
+        self.callback(ResourceMessage::ConnectionChanged { state })
+            .await;
+
+        Produces::ok(())
+    }
+
+    /// A page of the cache's initial list arrived, sends the records listed so far and the progress
+    pub async fn listed(
+        &mut self,
+        records: HashMap<RecordId<K>, K::Record>,
+        progress: ListProgress,
+    ) -> ActorResult<()> {
+        debug!("listed {} {}s", progress.loaded, K::NAME);
+
+        self.records = LoadStatus::Loaded(self.with_usage(records));
+        self.notify_loaded().await;
+
+        let progress = Some(progress);
+        self.callback(ResourceMessage::Loading { progress }).await;
+
+        Produces::ok(())
+    }
+
+    /// Replaces every record, when the cache re-listed or the watcher fell behind
+    pub async fn replaced(&mut self, records: HashMap<RecordId<K>, K::Record>) -> ActorResult<()> {
+        let records = self.with_usage(records);
+        if matches!(&self.records, LoadStatus::Loaded(current) if current == &records) {
+            return Produces::ok(());
+        }
+
+        self.records = LoadStatus::Loaded(records);
+        self.notify_loaded().await;
+
+        Produces::ok(())
+    }
+
+    pub async fn applied(&mut self, mut record: K::Record) -> ActorResult<()> {
+        if !self.usage.is_empty() {
+            record.set_usage(self.usage.get(record.id()));
+        }
+
+        let LoadStatus::Loaded(records) = &mut self.records else {
+            return Produces::ok(());
+        };
+
+        if records.get(record.id()) == Some(&record) {
+            debug!("same {} already exists, ignoring", K::NAME);
+            return Produces::ok(());
+        }
+
+        let id = record.id().clone();
+        records.insert(id.clone(), record);
+        self.changed(id);
+
+        Produces::ok(())
+    }
+
+    pub async fn deleted(&mut self, record: K::Record) -> ActorResult<()> {
+        let LoadStatus::Loaded(records) = &mut self.records else {
+            return Produces::ok(());
+        };
+
+        // only notify if the record existed before
+        if records.remove(record.id()).is_some() {
+            self.changed(record.id().clone());
+        }
+
+        Produces::ok(())
+    }
+
+    /// Merges the new usage into the records, records whose usage changed are sent with the next
+    /// batch of changes
+    pub async fn usage_loaded(&mut self, usage: UsageById<K>) -> ActorResult<()> {
+        self.usage = usage;
+
+        let LoadStatus::Loaded(records) = &mut self.records else {
+            return Produces::ok(());
+        };
+
+        let mut changed = vec![];
+        for (id, record) in records.iter_mut() {
+            let before = record.clone();
+            record.set_usage(self.usage.get(id));
+
+            if *record != before {
+                changed.push(id.clone());
+            }
+        }
+
+        for id in changed {
+            self.changed(id);
+        }
+
+        Produces::ok(())
+    }
+
+    /// Sends the changes collected since the last message
+    pub async fn flush_changes(&mut self) {
+        self.flush_scheduled = false;
+
+        let LoadStatus::Loaded(records) = &self.records else {
+            self.pending.clear();
+            return;
+        };
+
+        let mut inserted = vec![];
+        let mut updated = vec![];
+        let mut removed = vec![];
+        let mut highlights = HashMap::new();
+
+        for id in self.pending.drain() {
+            let matched = records.get(&id).and_then(|record| {
+                let score = self.query.score(record)?;
+                Some((record.clone(), score))
+            });
+
+            let Some((record, score)) = matched else {
+                if self.shown.remove(&id) {
+                    removed.push(id);
+                }
+                continue;
+            };
+
+            if !score.ranges.is_empty() {
+                highlights.insert(id.clone(), score.clone());
+            }
+
+            if self.shown.insert(id) {
+                inserted.push((record, score));
+            } else {
+                updated.push((record, score));
+            }
+        }
+
+        if inserted.is_empty() && updated.is_empty() && removed.is_empty() {
+            return;
+        }
+
+        // changed values can move records anywhere in a table sorted by its columns, the
+        // unchanged records keep their order
+        let positions = if inserted.is_empty() && updated.is_empty() {
+            Positions::<K::Record>::new()
+        } else {
+            let changed = inserted
+                .iter()
+                .chain(&updated)
+                .map(|(record, _)| record.id())
+                .collect::<HashSet<_>>();
+
+            let (matched, _) = matching_records(&self.query, &self.sort, records);
+            matched
+                .iter()
+                .enumerate()
+                .filter(|(_, record)| changed.contains(record.id()))
+                .map(|(index, record)| (record.id().clone(), index as u32))
+                .collect()
+        };
+
+        let inserted = sorted(&self.query, &self.sort, inserted);
+        let updated = sorted(&self.query, &self.sort, updated);
+        removed.sort();
+
+        self.callback(ResourceMessage::Changed {
+            inserted,
+            updated,
+            removed,
+            highlights,
+            positions,
+        })
+        .await
+    }
+
+    fn save_layout(&self, update: impl FnOnce(&mut TableLayout)) {
+        let mut config = USER_CONFIG.write();
+
+        let mut layout = config.get_table_layout::<K::Record>(K::NAME);
+        update(&mut layout);
+
+        let layout = layout.normalized::<K::Record>();
+        if let Err(error) = config.set_table_layout(K::NAME, layout) {
+            error!("failed to save {} table layout: {error}", K::NAME);
+        }
+    }
+
+    fn schedule_selectors(&mut self) {
+        self.selectors_generation += 1;
+
+        let generation = self.selectors_generation;
+        let addr = self.addr.clone();
+
+        task::spawn(async move {
+            tokio::time::sleep(SELECTOR_DELAY).await;
+            send!(addr.apply_selectors(generation));
+        });
+    }
+
+    /// Queues the change, the first change after a message schedules the next batch
+    fn changed(&mut self, id: RecordId<K>) {
+        self.pending.insert(id);
+
+        if self.flush_scheduled {
+            return;
+        }
+
+        self.flush_scheduled = true;
+        let addr = self.addr.clone();
+
+        task::spawn(async move {
+            tokio::time::sleep(FRAME_INTERVAL).await;
+            send!(addr.flush_changes());
+        });
+    }
+
+    /// The shared cache for the cluster, subscribing to it on first use or when the cluster or the
+    /// selectors changed
+    async fn cache(&mut self, selected_cluster: ClusterId) -> eyre::Result<Arc<ResourceCache<K>>> {
+        if let Some(cache) = self.cache.clone() {
+            if cache.cluster_id() == &selected_cluster && cache.selectors() == &self.selectors {
+                return Ok(cache);
+            }
+
+            // changes from the previous cluster or selectors must not reach the new records
+            self.stop_tasks();
+
+            if cache.cluster_id() != &selected_cluster {
+                self.usage.clear();
+            }
+        }
+
+        let client = client(&selected_cluster).await?;
+        let cache = if self.metadata_first {
+            ResourceCache::get_or_start_metadata_first(&selected_cluster, &self.selectors, client)
+        } else {
+            ResourceCache::get_or_start(&selected_cluster, &self.selectors, client)
+        };
+        self.cache = Some(cache.clone());
+
+        Ok(cache)
+    }
+
+    fn spawn_watcher(&self, cache: Arc<ResourceCache<K>>) -> JoinHandle<()> {
+        let addr = self.addr.clone();
+
+        task::spawn(async move {
+            if let Err(error) = kubernetes::resource::watch(addr, cache).await {
+                error!("{} watcher stopped: {error:?}", K::NAME);
+            }
+        })
+    }
+
+    fn stop_tasks(&mut self) {
+        if let Some(watcher) = self.watcher.take() {
+            watcher.abort();
+        }
+
+        if let Some(poller) = self.usage_poller.take() {
+            poller.abort();
+        }
+    }
+
+    fn with_usage(
+        &self,
+        mut records: HashMap<RecordId<K>, K::Record>,
+    ) -> HashMap<RecordId<K>, K::Record> {
+        // records keep no usage until the first is loaded
+        if self.usage.is_empty() {
+            return records;
+        }
+
+        for (id, record) in records.iter_mut() {
+            record.set_usage(self.usage.get(id));
+        }
+
+        records
+    }
+
+    fn filtered_records(&self) -> Option<(Vec<K::Record>, Highlights<K::Record>)> {
+        let LoadStatus::Loaded(records) = &self.records else {
+            return None;
+        };
+
+        Some(matching_records(&self.query, &self.sort, records))
+    }
+
+    /// Sends every record matching the search, replacing any changes not sent yet
+    async fn notify_loaded(&mut self) {
+        if let Some((records, highlights)) = self.filtered_records() {
+            self.shown = records.iter().map(|record| record.id().clone()).collect();
+            self.pending.clear();
+
+            self.callback(ResourceMessage::Loaded {
+                records,
+                highlights,
+            })
+            .await
+        }
+    }
+}
+
+/// Records of another kind the view model's own are computed against, ex: the pods a budget
+/// covers, from the cache shared with the other windows
+pub struct RelatedCache<K: KubeResource> {
+    cache: Arc<ResourceCache<K>>,
+    watcher: JoinHandle<()>,
+}
+
+impl<K: KubeResource> RelatedCache<K> {
+    /// Calls `on_change` after the records changed, changes within `interval` are collected into
+    /// one call
+    pub async fn start(
+        selected_cluster: &ClusterId,
+        interval: Duration,
+        on_change: impl Fn() + Send + 'static,
+    ) -> eyre::Result<Self> {
+        let client = client(selected_cluster).await?;
+        let cache = ResourceCache::get_or_start(selected_cluster, &Selectors::default(), client);
+        let watcher = cache.notify_changes(interval, on_change);
+
+        Ok(Self { cache, watcher })
+    }
+
+    pub fn cluster_id(&self) -> &ClusterId {
+        self.cache.cluster_id()
+    }
+
+    /// Records listed so far, filled in while the cache does its initial list
+    pub fn records(&self) -> HashMap<RecordId<K>, K::Record> {
+        self.cache.records()
+    }
+}
+
+impl<K: KubeResource> Drop for RelatedCache<K> {
+    fn drop(&mut self) {
+        self.watcher.abort();
+    }
+}
+
+pub(crate) async fn client(selected_cluster: &ClusterId) -> eyre::Result<Client> {
+    GlobalViewModel::check_and_load_client(selected_cluster).await?;
+
+    GlobalViewModel::global()
+        .read()
+        .get_cluster_client(selected_cluster)
+        .ok_or_else(|| eyre!("client not found"))
+}
+
+/// Records matching the query, sorted like `Loaded`, with their highlights
+fn matching_records<R: ResourceRecord>(
+    query: &Query,
+    sort: &[SortKey],
+    records: &HashMap<R::Id, R>,
+) -> (Vec<R>, Highlights<R>) {
+    let matched = records
+        .values()
+        .filter_map(|record| Some((record.clone(), query.score(record)?)))
+        .collect::<Vec<_>>();
+
+    let highlights = matched
+        .iter()
+        .filter(|(_, score)| !score.ranges.is_empty())
+        .map(|(record, score)| (record.id().clone(), score.clone()))
+        .collect();
+
+    (sorted(query, sort, matched), highlights)
+}
+
+/// By the sort keys, then best matches first when the query has free text, by id otherwise
+fn sorted<R: ResourceRecord>(
+    query: &Query,
+    sort: &[SortKey],
+    mut matched: Vec<(R, FuzzyMatch)>,
+) -> Vec<R> {
+    if query.has_text() {
+        matched.sort_by(|(a, a_score), (b, b_score)| {
+            b_score
+                .score
+                .cmp(&a_score.score)
+                .then_with(|| a.id().cmp(b.id()))
+        });
+    } else {
+        matched.sort_by(|(a, _), (b, _)| a.id().cmp(b.id()));
+    }
+
+    let mut records = matched
+        .into_iter()
+        .map(|(record, _)| record)
+        .collect::<Vec<_>>();
+
+    table::sort_by_keys(&mut records, sort);
+    records
+}
+
+impl<K: KubeResource> Drop for ResourceViewModel<K> {
+    fn drop(&mut self) {
+        self.stop_tasks();
+    }
+}
+
+#[async_trait::async_trait]
+impl<K: KubeResource> Actor for ResourceViewModel<K> {
+    async fn started(&mut self, addr: Addr<Self>) -> ActorResult<()> {
+        self.addr = addr.downgrade();
+        Produces::ok(())
+    }
+
+    async fn error(&mut self, error: ActorError) -> bool {
+        error!("{} view model actor error: {error:?}", K::NAME);
+
+        let error = match error.downcast::<ResourceError>().map(|e| *e) {
+            Ok(ResourceError::LoadError(error)) => error.to_string(),
+            Err(_) => "Unknown error, please see logs".to_string(),
+        };
+
+        self.callback(ResourceMessage::LoadingFailed { error })
+            .await;
+
+        false
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use k8s_openapi::api::core::v1::ResourceQuota as K8sResourceQuota;
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        cache::ConnectionState,
+        resource::ListProgress,
+        resource_quota::{QuotaWarning, ResourceQuota, ResourceQuotaId},
+    },
+    task::{self, spawn_actor},
+    user_config::USER_CONFIG,
+};
+
+use super::resource::{ResourceCallback, ResourceMessage, ResourceViewModel};
+
+#[uniffi::export(callback_interface)]
+pub trait ResourceQuotaViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: ResourceQuotaViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum ResourceQuotaViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        resource_quotas: Vec<ResourceQuota>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<ResourceQuota>,
+        updated: Vec<ResourceQuota>,
+        removed: Vec<ResourceQuotaId>,
+        positions: HashMap<ResourceQuotaId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+pub type ResourceQuotaViewModel = ResourceViewModel<K8sResourceQuota>;
+
+#[derive(Object)]
+pub struct RustResourceQuotaViewModel {
+    actor: RwLock<Addr<ResourceQuotaViewModel>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustResourceQuotaViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn resource_quotas(self: Arc<Self>) -> Vec<ResourceQuota> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    /// Quota resources in the namespace that are above the user's warning threshold
+    pub fn namespace_warnings(self: Arc<Self>, namespace: String) -> Vec<QuotaWarning> {
+        let actor = self.actor.read().clone();
+
+        let quotas =
+            task::block_on(async move { call!(actor.all_records()).await.unwrap_or_default() });
+
+        let threshold = USER_CONFIG.read().quota_warning_threshold;
+
+        let mut warnings = quotas
+            .iter()
+            .filter(|quota| quota.namespace == namespace)
+            .flat_map(|quota| quota.warnings(threshold))
+            .collect::<Vec<_>>();
+
+        // most used first
+        warnings.sort_by(|a, b| {
+            let a = a.usage.used_percent.unwrap_or_default();
+            let b = b.usage.used_percent.unwrap_or_default();
+            b.total_cmp(&a)
+        });
+
+        warnings
+    }
+
+    pub fn warning_threshold(&self) -> f64 {
+        USER_CONFIG.read().quota_warning_threshold
+    }
+
+    pub fn set_warning_threshold(&self, threshold: f64) {
+        if let Err(err) = USER_CONFIG.write().set_quota_warning_threshold(threshold) {
+            error!("failed to set quota warning threshold: {err}");
+        }
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn ResourceQuotaViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("resource quota view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(ResourceQuotaViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_resource_quotas(&self, selected_cluster: ClusterId) {
+        debug!("fetching resource quotas for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch resource quotas: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start resource quota watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl ResourceCallback<ResourceQuota> for Box<dyn ResourceQuotaViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<ResourceQuota>) {
+        use ResourceMessage as Msg;
+        use ResourceQuotaViewModelMessage as ResourceQuotaMsg;
+
+        let message = match message {
+            Msg::Loading { progress } => ResourceQuotaMsg::Loading { progress },
+            Msg::Loaded { records, .. } => ResourceQuotaMsg::Loaded {
+                resource_quotas: records,
+            },
+            Msg::LoadingFailed { error } => ResourceQuotaMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => ResourceQuotaMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => ResourceQuotaMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => ResourceQuotaMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                ResourceQuotaMsg::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => ResourceQuotaMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashMap, sync::Arc};
+
+use act_zero::*;
+use k8s_openapi::api::node::v1::RuntimeClass as K8sRuntimeClass;
+use log::{debug, error};
+use parking_lot::RwLock;
+use uniffi::Object;
+
+use crate::{
+    cluster::ClusterId,
+    kubernetes::{
+        cache::ConnectionState,
+        resource::ListProgress,
+        runtime_class::{RuntimeClass, RuntimeClassId},
+    },
+    task::{self, spawn_actor},
+};
+
+use super::resource::{ResourceCallback, ResourceMessage, ResourceViewModel};
+
+#[uniffi::export(callback_interface)]
+pub trait RuntimeClassViewModelCallback: Send + Sync + 'static {
+    fn callback(&self, message: RuntimeClassViewModelMessage);
+}
+
+#[derive(uniffi::Enum)]
+pub enum RuntimeClassViewModelMessage {
+    /// Progress is sent for each page of the initial list
+    Loading {
+        progress: Option<ListProgress>,
+    },
+    Loaded {
+        runtime_classes: Vec<RuntimeClass>,
+    },
+    LoadingFailed {
+        error: String,
+    },
+
+    /// Batched changes from the watcher, applied on top of the last `Loaded`
+    Changed {
+        inserted: Vec<RuntimeClass>,
+        updated: Vec<RuntimeClass>,
+        removed: Vec<RuntimeClassId>,
+        positions: HashMap<RuntimeClassId, u32>,
+    },
+
+    /// The list is stale while not connected
+    ConnectionChanged {
+        state: ConnectionState,
+    },
+
+    /// The search couldn't be parsed as a query, `None` once it can
+    SearchError {
+        error: Option<String>,
+    },
+
+    ToastWarningMessage {
+        message: String,
+    },
+    ToastErrorMessage {
+        message: String,
+    },
+}
+
+pub type RuntimeClassViewModel = ResourceViewModel<K8sRuntimeClass>;
+
+#[derive(Object)]
+pub struct RustRuntimeClassViewModel {
+    actor: RwLock<Addr<RuntimeClassViewModel>>,
+}
+
+#[uniffi::export(async_runtime = "tokio")]
+impl RustRuntimeClassViewModel {
+    #[uniffi::constructor]
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            actor: RwLock::new(Default::default()),
+        })
+    }
+
+    pub fn runtime_classes(self: Arc<Self>) -> Vec<RuntimeClass> {
+        let actor = self.actor.read().clone();
+
+        task::block_on(async move {
+            call!(actor.records())
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn set_search(self: Arc<Self>, search: String) {
+        let actor = self.actor.read().clone();
+        send!(actor.set_search(search));
+    }
+
+    pub async fn initialize_model_with_responder(
+        &self,
+        responder: Box<dyn RuntimeClassViewModelCallback>,
+    ) {
+        // only initialize once
+        let actor = self.actor.read().clone();
+        if call!(actor.is_started()).await.is_ok() {
+            debug!("runtime class view model already initialized");
+            return;
+        }
+
+        {
+            let mut actor = self.actor.write();
+            *actor = spawn_actor(RuntimeClassViewModel::new());
+        }
+
+        let actor = self.actor.read().clone();
+        call!(actor.add_callback_listener(responder))
+            .await
+            .expect("failed to add callback listener");
+    }
+
+    pub async fn fetch_runtime_classes(&self, selected_cluster: ClusterId) {
+        debug!("fetching runtime classes for cluster: {selected_cluster:?}");
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.notify_and_load(selected_cluster)).await {
+            error!("failed to fetch runtime classes: {error}");
+        }
+    }
+
+    pub async fn start_watcher(&self, selected_cluster: ClusterId) {
+        let actor = self.actor.read().clone();
+
+        if let Err(error) = call!(actor.start_watcher(selected_cluster)).await {
+            error!("failed to start runtime class watcher: {error}");
+        }
+    }
+
+    pub async fn stop_watcher(&self) {
+        let actor = self.actor.read().clone();
+        let _ = call!(actor.stop_watcher()).await;
+    }
+}
+
+impl ResourceCallback<RuntimeClass> for Box<dyn RuntimeClassViewModelCallback> {
+    fn callback(&self, message: ResourceMessage<RuntimeClass>) {
+        use ResourceMessage as Msg;
+        use RuntimeClassViewModelMessage as RuntimeClassMsg;
+
+        let message = match message {
+            Msg::Loading { progress } => RuntimeClassMsg::Loading { progress },
+            Msg::Loaded { records, .. } => RuntimeClassMsg::Loaded {
+                runtime_classes: records,
+            },
+            Msg::LoadingFailed { error } => RuntimeClassMsg::LoadingFailed { error },
+            Msg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+                ..
+            } => RuntimeClassMsg::Changed {
+                inserted,
+                updated,
+                removed,
+                positions,
+            },
+            Msg::ConnectionChanged { state } => RuntimeClassMsg::ConnectionChanged { state },
+            Msg::SearchError { error } => RuntimeClassMsg::SearchError { error },
+            Msg::ToastWarningMessage { message } => {
+                RuntimeClassMsg::ToastWarningMessage { message }
+            }
+            Msg::ToastErrorMessage { message } => RuntimeClassMsg::ToastErrorMessage { message },
+        };
+
+        self.as_ref().callback(message)
+    }
+}